@@ -735,6 +735,30 @@ impl<E: KvEngine> CoprocessorHost<E> {
         store_size
     }
 
+    /// Whether a region heartbeat should be sent, per every registered
+    /// `PdTaskObserver`. Suppressed (returns `false`) as soon as any
+    /// observer says so.
+    pub fn pre_region_heartbeat(&self, hb_task: &crate::store::worker::HeartbeatTask) -> bool {
+        for observer in &self.registry.pd_task_observers {
+            let observer = observer.observer.inner();
+            if !observer.pre_region_heartbeat(hb_task) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Folds every registered `PdTaskObserver::cap_raft_log_gc_index` over
+    /// `candidate_index`, each one narrowing the previous result further.
+    pub fn cap_raft_log_gc_index(&self, region_id: u64, candidate_index: u64) -> u64 {
+        let mut index = candidate_index;
+        for observer in &self.registry.pd_task_observers {
+            let observer = observer.observer.inner();
+            index = observer.cap_raft_log_gc_index(region_id, index);
+        }
+        index
+    }
+
     pub fn on_role_change(&self, region: &Region, role_change: RoleChange) {
         loop_ob!(
             region,
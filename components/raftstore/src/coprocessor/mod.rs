@@ -261,6 +261,26 @@ pub struct StoreSizeInfo {
 pub trait PdTaskObserver: Coprocessor {
     /// Compute capacity/used/available size of this store.
     fn on_compute_engine_size(&self, _: &mut Option<StoreSizeInfo>) {}
+
+    /// Called just before a region heartbeat would be scheduled to the PD
+    /// worker. Returning `false` suppresses this heartbeat, e.g. to dedup an
+    /// unchanged region's report or spread a store hosting huge numbers of
+    /// regions out over the reporting interval instead of bursting them all
+    /// at once.
+    fn pre_region_heartbeat(&self, _: &crate::store::worker::HeartbeatTask) -> bool {
+        true
+    }
+
+    /// Called by `on_raft_gc_log_tick` with the raft log index it is about
+    /// to propose compacting `region_id` to, before actually proposing the
+    /// `CompactLog` command. Observers may lower it (never raise it, the
+    /// caller floors the result at the region's first index) to defer the
+    /// proposal itself, e.g. so a region whose engine store hasn't yet
+    /// acknowledged that far isn't repeatedly asked to compact logs it will
+    /// just end up not needing a full snapshot to catch back up from.
+    fn cap_raft_log_gc_index(&self, _region_id: u64, candidate_index: u64) -> u64 {
+        candidate_index
+    }
 }
 
 pub struct RoleChange {
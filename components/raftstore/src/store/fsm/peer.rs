@@ -5510,6 +5510,18 @@ where
             replicated_idx
         };
         assert!(compact_idx >= first_idx);
+
+        // Give observers (e.g. the engine store proxy) a chance to defer the
+        // compaction when their acknowledged index lags behind `compact_idx`,
+        // so a slow engine store isn't forced into a full snapshot just
+        // because the raft log it was still relying on got truncated.
+        compact_idx = std::cmp::max(
+            self.ctx
+                .coprocessor_host
+                .cap_raft_log_gc_index(self.fsm.peer.region().get_id(), compact_idx),
+            first_idx,
+        );
+
         // Have no idea why subtract 1 here, but original code did this by magic.
         compact_idx -= 1;
         if compact_idx < first_idx {
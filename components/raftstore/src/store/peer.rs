@@ -5262,7 +5262,7 @@ where
     }
 
     pub fn heartbeat_pd<T>(&mut self, ctx: &PollContext<EK, ER, T>) {
-        let task = PdTask::Heartbeat(HeartbeatTask {
+        let hb_task = HeartbeatTask {
             term: self.term(),
             region: self.region().clone(),
             down_peers: self.collect_down_peers(ctx),
@@ -5274,7 +5274,11 @@ where
             approximate_keys: self.approximate_keys,
             replication_status: self.region_replication_status(),
             wait_data_peers: self.wait_data_peers.clone(),
-        });
+        };
+        if !ctx.coprocessor_host.pre_region_heartbeat(&hb_task) {
+            return;
+        }
+        let task = PdTask::Heartbeat(hb_task);
         if let Err(e) = ctx.pd_scheduler.schedule(task) {
             error!(
                 "failed to notify pd";
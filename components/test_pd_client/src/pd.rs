@@ -994,6 +994,22 @@ impl TestPdClient {
         self.cluster.rl().get_regions_number()
     }
 
+    /// Every region this client currently knows about, paired with its last
+    /// heartbeated leader (`None` if no leader has reported yet). Used by
+    /// `Cluster::topology` to build a point-in-time snapshot for tests to
+    /// assert on; iteration order follows `regions`' start-key ordering.
+    pub fn get_all_regions(&self) -> Vec<(metapb::Region, Option<metapb::Peer>)> {
+        let cluster = self.cluster.rl();
+        cluster
+            .regions
+            .values()
+            .map(|region| {
+                let leader = cluster.leaders.get(&region.get_id()).cloned();
+                (region.clone(), leader)
+            })
+            .collect()
+    }
+
     pub fn disable_default_operator(&self) {
         self.cluster.wl().enable_peer_count_check = false;
     }
@@ -0,0 +1,74 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Decodes raft log exports produced by `GET /debug/raft_log/<region_id>`
+//! and diffs two of them by index, for investigating "missing rows in
+//! TiFlash" reports: dump the same region's log from two stores (or before
+//! and after a suspected gap) and see which indexes are only on one side.
+//!
+//! Usage: `raft_log_diff <left.json> <right.json>`
+
+use std::{collections::BTreeMap, env, fs, process};
+
+use engine_store_ffi::core::raft_log_export::ExportedRaftEntry;
+
+fn load(path: &str) -> BTreeMap<u64, ExportedRaftEntry> {
+    let data = fs::read(path).unwrap_or_else(|e| {
+        eprintln!("failed to read {}: {}", path, e);
+        process::exit(1);
+    });
+    let entries: Vec<ExportedRaftEntry> = serde_json::from_slice(&data).unwrap_or_else(|e| {
+        eprintln!("failed to parse {}: {}", path, e);
+        process::exit(1);
+    });
+    entries.into_iter().map(|e| (e.index, e)).collect()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 {
+        eprintln!("usage: {} <left.json> <right.json>", args[0]);
+        process::exit(2);
+    }
+
+    let left = load(&args[1]);
+    let right = load(&args[2]);
+
+    let mut only_left = 0;
+    let mut only_right = 0;
+    let mut mismatched = 0;
+    let mut common = 0;
+
+    for (index, entry) in &left {
+        match right.get(index) {
+            None => {
+                only_left += 1;
+                println!("only in {}: index={} term={}", args[1], index, entry.term);
+            }
+            Some(other) if other != entry => {
+                mismatched += 1;
+                println!(
+                    "mismatch at index={}: {} term={} data_len={} vs {} term={} data_len={}",
+                    index,
+                    args[1],
+                    entry.term,
+                    entry.data.len(),
+                    args[2],
+                    other.term,
+                    other.data.len(),
+                );
+            }
+            Some(_) => common += 1,
+        }
+    }
+    for (index, entry) in &right {
+        if !left.contains_key(index) {
+            only_right += 1;
+            println!("only in {}: index={} term={}", args[2], index, entry.term);
+        }
+    }
+
+    println!(
+        "summary: {} only in left, {} only in right, {} mismatched, {} common",
+        only_left, only_right, mismatched, common
+    );
+}
@@ -0,0 +1,71 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use engine_traits::KvEngine;
+
+use crate::core::{common::*, ProxyForwarder};
+
+const ADMIN_JOURNAL_FILE_NAME: &str = "admin_decisions.log";
+
+/// A best-effort, append-only record of admin command decisions the
+/// forwarder made (e.g. filtering a `CompactLog`), so an operator can
+/// reconstruct what happened up to a crash without replaying the full raft
+/// log. This is a diagnostic aid, not a source of truth: the raft log and
+/// applied state remain authoritative.
+#[derive(Debug)]
+pub struct AdminDecisionJournal {
+    file: Mutex<Option<std::fs::File>>,
+}
+
+impl Default for AdminDecisionJournal {
+    fn default() -> Self {
+        AdminDecisionJournal {
+            file: Mutex::new(None),
+        }
+    }
+}
+
+impl AdminDecisionJournal {
+    fn ensure_open(&self, data_dir: &std::path::Path) -> std::io::Result<()> {
+        let mut guard = self.file.lock().unwrap();
+        if guard.is_some() {
+            return Ok(());
+        }
+        let path = data_dir.join(ADMIN_JOURNAL_FILE_NAME);
+        let f = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        *guard = Some(f);
+        Ok(())
+    }
+
+    pub fn record(&self, data_dir: &std::path::Path, line: &str) {
+        if let Err(e) = self.ensure_open(data_dir) {
+            warn!("failed to open admin decision journal"; "err" => ?e);
+            return;
+        }
+        let mut guard = self.file.lock().unwrap();
+        if let Some(f) = guard.as_mut() {
+            if let Err(e) = writeln!(f, "{}", line) {
+                warn!("failed to append to admin decision journal"; "err" => ?e);
+            }
+        }
+    }
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    pub fn record_admin_decision(&self, region_id: u64, index: u64, term: u64, decision: &str) {
+        if !self
+            .packed_envs
+            .engine_store_cfg
+            .enable_admin_decision_journal
+        {
+            return;
+        }
+        let line = format!(
+            "region={} index={} term={} decision={}",
+            region_id, index, term, decision
+        );
+        let data_dir = std::path::Path::new(self.engine.path());
+        self.debug_struct.admin_journal.record(data_dir, &line);
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use crate::core::{common::*, metrics::TIFLASH_APPLY_TERM_REGRESSION_COUNTER, ProxyForwarder};
+
+/// Guards against persisting an apply state that regressed in term.
+///
+/// `RaftApplyState` (what actually gets persisted to disk) has no term
+/// field of its own -- only `applied_index` -- so the proxy tracks the
+/// engine store's last-confirmed `(index, term)` per region itself,
+/// entirely on the Rust side, using the `cmd.term` that already flows
+/// through `post_exec_admin`/`post_exec_query` for every applied command.
+/// A leadership change mid-flush can otherwise let a stale in-flight apply
+/// from the old term land after a newer one from the new term, silently
+/// rolling the persisted term backwards while `applied_index` still moves
+/// forward -- exactly the divergence a `Persist` decision from
+/// `EngineStoreApplyRes` alone can't catch, since that enum carries no
+/// index/term of its own to check against (extending it would mean
+/// changing the bindgen'd FFI struct layout, out of scope here).
+pub struct AppliedTermGuard {
+    last_persisted: Mutex<HashMap<u64, (u64, u64)>>,
+}
+
+impl Default for AppliedTermGuard {
+    fn default() -> Self {
+        AppliedTermGuard {
+            last_persisted: Mutex::new(HashMap::default()),
+        }
+    }
+}
+
+impl AppliedTermGuard {
+    /// Records `(index, term)` as persisted for `region_id` and returns
+    /// `true`, unless doing so would regress the term at or above an
+    /// index this region already persisted, in which case the record is
+    /// left untouched and `false` is returned.
+    fn check_and_record(&self, region_id: u64, index: u64, term: u64) -> bool {
+        let mut last_persisted = self.last_persisted.lock().unwrap();
+        match last_persisted.get(&region_id) {
+            Some(&(last_index, last_term)) if index >= last_index && term < last_term => false,
+            _ => {
+                last_persisted.insert(region_id, (index, term));
+                true
+            }
+        }
+    }
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Called from `post_exec_admin`/`post_exec_query` right before
+    /// honoring a `persist` decision; returns `false` in place of `persist`
+    /// if persisting `(index, term)` for `region_id` would regress the
+    /// engine store's applied term, logging and counting the refusal.
+    pub fn guard_apply_term(&self, region_id: u64, index: u64, term: u64, persist: bool) -> bool {
+        if !persist {
+            return false;
+        }
+        let ok = self.applied_term_guard.check_and_record(region_id, index, term);
+        if !ok {
+            TIFLASH_APPLY_TERM_REGRESSION_COUNTER.inc();
+            error!(
+                "refusing to persist apply state with regressed term";
+                "region_id" => region_id,
+                "index" => index,
+                "term" => term,
+            );
+        }
+        ok
+    }
+}
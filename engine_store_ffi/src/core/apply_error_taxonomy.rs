@@ -0,0 +1,100 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use crate::core::{common::*, metrics::TIFLASH_APPLY_ERROR_COUNTER, ProxyForwarder};
+
+/// Why an engine-store apply didn't persist, as best this proxy can tell.
+///
+/// `EngineStoreApplyRes` only carries three values (`None`, `Persist`,
+/// `NotFound`) with no message or error code, so most of this classification
+/// leans on context this proxy already tracks itself (whether the region is
+/// pending removal, whether the engine store's last reported disk usage was
+/// over `disk_full_enter_ratio`) rather than anything the engine store says
+/// about the specific failure. `Corrupted` is kept as a variant because it's
+/// a real failure mode an operator will ask about, but nothing today can
+/// produce it: the ABI has no way for the engine store to report "this apply
+/// failed because the data was corrupt" as opposed to just not persisting.
+/// Distinguishing it needs a `gen-proxy-ffi` run adding a proper error
+/// payload to `EngineStoreApplyRes`, not done here.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde_derive::Serialize)]
+pub enum ApplyErrorClass {
+    RegionNotFound,
+    OutOfSpace,
+    Retryable,
+    /// Never produced today -- see this module's doc comment.
+    Corrupted,
+    Unknown,
+}
+
+impl ApplyErrorClass {
+    fn as_metric_label(self) -> &'static str {
+        match self {
+            ApplyErrorClass::RegionNotFound => "region_not_found",
+            ApplyErrorClass::OutOfSpace => "out_of_space",
+            ApplyErrorClass::Retryable => "retryable",
+            ApplyErrorClass::Corrupted => "corrupted",
+            ApplyErrorClass::Unknown => "unknown",
+        }
+    }
+}
+
+/// The raftstore-side behavior an [`ApplyErrorClass`] would call for.
+/// `classify_apply_error` never recommends `Tombstone`, `StoreOffline`, or
+/// `Panic` today -- those are decisions raftstore's existing, more
+/// context-aware paths already make deliberately (e.g. `pending_remove`
+/// gates the real tombstone path, `engine_store_disk_full` gates the real
+/// disk-status transition); this proxy taking one of those actions directly
+/// from a bare classification, without that surrounding context, would risk
+/// being wrong in a way that's worse than not acting. Kept as variants so
+/// the taxonomy's shape matches what an operator actually needs to reason
+/// about, and so a future caller with the right context can consult it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde_derive::Serialize)]
+pub enum ApplyErrorAction {
+    Retry,
+    Tombstone,
+    StoreOffline,
+    Panic,
+}
+
+/// Classifies a non-`Persist` apply result. Returns `None` for `Persist`
+/// (nothing to classify). `region_removed` should reflect
+/// `RegionState::pending_remove` and `disk_full` the last value observed via
+/// `engine_store_disk_full`.
+pub fn classify_apply_error(
+    res: EngineStoreApplyRes,
+    region_removed: bool,
+    disk_full: bool,
+) -> Option<(ApplyErrorClass, ApplyErrorAction)> {
+    match res {
+        EngineStoreApplyRes::Persist => None,
+        EngineStoreApplyRes::NotFound if region_removed => {
+            Some((ApplyErrorClass::RegionNotFound, ApplyErrorAction::Retry))
+        }
+        EngineStoreApplyRes::NotFound | EngineStoreApplyRes::None if disk_full => {
+            Some((ApplyErrorClass::OutOfSpace, ApplyErrorAction::Retry))
+        }
+        EngineStoreApplyRes::NotFound => {
+            Some((ApplyErrorClass::RegionNotFound, ApplyErrorAction::Retry))
+        }
+        EngineStoreApplyRes::None => Some((ApplyErrorClass::Retryable, ApplyErrorAction::Retry)),
+    }
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Classifies `res` and, for anything other than `Persist`, logs and
+    /// counts it under `TIFLASH_APPLY_ERROR_COUNTER` by class. Additive
+    /// observability only -- does not change whether the caller treats
+    /// `res` as persisted.
+    pub fn observe_apply_error(&self, region_id: u64, region_removed: bool, res: EngineStoreApplyRes) {
+        let disk_full = self.engine_store_disk_full.load(Ordering::Acquire);
+        if let Some((class, action)) = classify_apply_error(res, region_removed, disk_full) {
+            warn!("engine-store apply did not persist";
+                "region_id" => region_id,
+                "class" => ?class,
+                "recommended_action" => ?action,
+                "res" => ?res,
+            );
+            TIFLASH_APPLY_ERROR_COUNTER
+                .with_label_values(&[class.as_metric_label()])
+                .inc();
+        }
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//! Scheduled, throughput-gated defragmentation of this store's own raft log
+//! and apply-state history, on top of `core::checkpoint_compaction`.
+//!
+//! Apart from this sweep, `compact_durable_checkpoint` only fires from
+//! `ProxyForwarder::record_replay_debt`, and only when a region's replay
+//! debt clears -- which is only tracked with `enable-restart-detection` on
+//! (see `ProxyForwarder::refresh_replay_debt`) or after an operator
+//! rewind. Otherwise nothing proactively reclaims old
+//! `RaftApplyState` history or raft log entries outside of the normal
+//! PD-ticked `CompactLog` admission, so a store with heavy region churn
+//! (many short-lived regions, each leaving behind apply-state history)
+//! can build up tombstones that slow its next restart's raft-engine scan.
+//!
+//! This runs a periodic sweep that uses each region's own already-persisted
+//! `RaftApplyState.applied_index` as the safe-to-reclaim floor -- the same
+//! bound normal `CompactLog` admission already trusts, just applied
+//! proactively instead of waiting on `record_replay_debt` -- bounded to a
+//! fixed number of regions per run and skipped entirely outside a detected
+//! low-traffic window, so it never competes with foreground apply for IO.
+use std::time::Instant;
+
+use crate::core::{common::*, ProxyForwarder};
+
+/// A single bytes/time sample of `ProxyForwarder::forwarded_bytes`'s running
+/// total, so two consecutive samples can be turned into a throughput
+/// estimate without keeping a real sliding window.
+struct ThroughputSample {
+    at: Instant,
+    cumulative_bytes: u64,
+}
+
+/// Extra state `maybe_run_background_defrag` needs across calls. Lives as
+/// its own struct, rather than loose fields on `ProxyForwarder`, so it can
+/// be default-constructed once in `ProxyForwarder::new` alongside the
+/// feature's other state.
+pub(crate) struct BackgroundDefragState {
+    last_tick: Mutex<Instant>,
+    last_sample: Mutex<ThroughputSample>,
+    // Last region id swept, so consecutive runs advance round-robin through
+    // every region this proxy has forwarded a write for, instead of always
+    // restarting from the lowest region id and starving the rest.
+    cursor: Mutex<u64>,
+}
+
+impl Default for BackgroundDefragState {
+    fn default() -> Self {
+        let now = Instant::now();
+        BackgroundDefragState {
+            last_tick: Mutex::new(now),
+            last_sample: Mutex::new(ThroughputSample {
+                at: now,
+                cumulative_bytes: 0,
+            }),
+            cursor: Mutex::new(0),
+        }
+    }
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Estimated store-wide forwarded-write throughput in bytes/sec since
+    /// the previous call, derived from the delta in
+    /// `self.forwarded_bytes`'s running totals.
+    fn current_apply_throughput(&self) -> u64 {
+        let total: u64 = self.forwarded_bytes.read().unwrap().values().sum();
+        let mut sample = self.background_defrag.last_sample.lock().unwrap();
+        let elapsed_secs = sample.at.elapsed().as_secs();
+        let throughput = if elapsed_secs == 0 {
+            0
+        } else {
+            total.saturating_sub(sample.cumulative_bytes) / elapsed_secs
+        };
+        *sample = ThroughputSample {
+            at: Instant::now(),
+            cumulative_bytes: total,
+        };
+        throughput
+    }
+
+    /// Runs the sweep when due, or does nothing. Cheap to call on every
+    /// `CompactLog` admission: the interval and low-traffic checks below
+    /// both short-circuit before doing any real work.
+    pub fn maybe_run_background_defrag(&self) {
+        let cfg = &self.packed_envs.engine_store_cfg;
+        if !cfg.enable_background_defrag {
+            return;
+        }
+        {
+            let mut last_tick = self.background_defrag.last_tick.lock().unwrap();
+            if last_tick.elapsed() < cfg.background_defrag_check_interval.0 {
+                return;
+            }
+            *last_tick = Instant::now();
+        }
+        let throughput = self.current_apply_throughput();
+        if throughput > cfg.background_defrag_low_traffic_threshold.0 {
+            debug!("skipping background defrag, store not in a low-traffic window";
+                "throughput_bytes_per_sec" => throughput,
+                "threshold_bytes_per_sec" => cfg.background_defrag_low_traffic_threshold.0,
+            );
+            return;
+        }
+        self.run_defrag_sweep(cfg.background_defrag_max_regions_per_run);
+    }
+
+    /// Compacts up to `max_regions` regions' worth of raft log and
+    /// apply-state history, picked round-robin from every region this proxy
+    /// has forwarded a write for, via `compact_durable_checkpoint`. This is
+    /// the IO limit: a node with millions of regions gets swept gradually,
+    /// one bounded batch per low-traffic window, rather than all at once.
+    fn run_defrag_sweep(&self, max_regions: usize) {
+        if max_regions == 0 {
+            return;
+        }
+        let mut region_ids: Vec<u64> = self.forwarded_bytes.read().unwrap().keys().copied().collect();
+        if region_ids.is_empty() {
+            return;
+        }
+        region_ids.sort_unstable();
+        let mut cursor = self.background_defrag.cursor.lock().unwrap();
+        let start = region_ids.partition_point(|&id| id <= *cursor) % region_ids.len();
+        for offset in 0..region_ids.len().min(max_regions) {
+            let region_id = region_ids[(start + offset) % region_ids.len()];
+            match self.raft_engine.get_apply_state(region_id, u64::MAX) {
+                Ok(Some(apply_state)) => {
+                    self.compact_durable_checkpoint(region_id, apply_state.get_applied_index());
+                }
+                Ok(None) => (),
+                Err(e) => {
+                    warn!("failed to read apply state for background defrag";
+                        "region_id" => region_id, "err" => ?e);
+                }
+            }
+            *cursor = region_id;
+        }
+    }
+}
@@ -0,0 +1,43 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+use crate::core::{common::*, metrics::TIFLASH_CHECKPOINT_COMPACTION_COUNTER, ProxyForwarder};
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Called once the engine store has confirmed durability for `region_id`
+    /// through `durable_index` -- in practice, whenever
+    /// `core::replay_debt::record_replay_debt` sees a region's debt clear.
+    /// GCs every raft log entry below `durable_index` and drops all
+    /// but the latest apply-state history entry for it, so this store's own
+    /// raft engine doesn't keep growing behind an engine store that has
+    /// already durably applied past it. This runs independently of the
+    /// normal PD-ticked `CompactLog` admin command (see
+    /// `cap_raft_log_gc_index` in `raftstore::store::fsm::peer`): that path
+    /// only fires on the tick's own schedule and is capped to whatever index
+    /// the engine store has *acked*, whereas this fires as soon as the
+    /// engine store reports a *durable* checkpoint, which can otherwise lag
+    /// well behind the tick on an idle region.
+    pub fn compact_durable_checkpoint(&self, region_id: u64, durable_index: u64) {
+        let mut batch = self.raft_engine.log_batch(0);
+        if let Err(e) = self.raft_engine.gc(region_id, 0, durable_index, &mut batch) {
+            warn!("failed to gc raft log for durable checkpoint";
+                "region_id" => region_id, "durable_index" => durable_index, "err" => ?e);
+            return;
+        }
+        if let Err(e) =
+            self.raft_engine
+                .delete_all_but_one_states_before(region_id, durable_index, &mut batch)
+        {
+            warn!("failed to trim apply-state history for durable checkpoint";
+                "region_id" => region_id, "durable_index" => durable_index, "err" => ?e);
+            return;
+        }
+        if batch.is_empty() {
+            return;
+        }
+        match self.raft_engine.consume(&mut batch, false) {
+            Ok(_) => TIFLASH_CHECKPOINT_COMPACTION_COUNTER.inc(),
+            Err(e) => warn!("failed to persist durable checkpoint compaction";
+                "region_id" => region_id, "durable_index" => durable_index, "err" => ?e),
+        }
+    }
+}
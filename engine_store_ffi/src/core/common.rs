@@ -40,10 +40,10 @@ pub(crate) use crate::{
     ffi::{
         gen_engine_store_server_helper,
         interfaces_ffi::{
-            ColumnFamilyType, EngineStoreApplyRes, EngineStoreServerHelper, RaftCmdHeader,
-            RawCppPtr, WriteCmdType,
+            ColumnFamilyType, EngineStoreApplyRes, EngineStoreServerHelper, EngineStoreServerStatus,
+            RaftCmdHeader, RawCppPtr, WriteCmdType,
         },
-        name_to_cf, WriteCmds,
+        name_to_cf, ExtendedRaftCmdMeta, WriteCmds,
     },
     TiFlashEngine,
 };
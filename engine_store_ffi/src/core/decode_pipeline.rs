@@ -0,0 +1,130 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::sync::atomic::AtomicUsize;
+
+use api_version::{ApiV2, KvFormat};
+use engine_traits::{CF_DEFAULT, CF_WRITE};
+use kvproto::raft_cmdpb::Request;
+use txn_types::WriteRef;
+
+use crate::core::{common::*, ProxyForwarder};
+
+/// Releases its share of the decode-pipeline byte quota when dropped, so a
+/// batch is always accounted for exactly as long as its decode is in
+/// flight, panics included.
+struct QuotaGuard<'a> {
+    used: &'a AtomicUsize,
+    bytes: usize,
+}
+
+impl<'a> Drop for QuotaGuard<'a> {
+    fn drop(&mut self) {
+        self.used.fetch_sub(self.bytes, Ordering::AcqRel);
+    }
+}
+
+pub(crate) fn estimate_batch_bytes(requests: &[Request]) -> usize {
+    requests
+        .iter()
+        .map(|req| match req.get_cmd_type() {
+            CmdType::Put => req.get_put().get_key().len() + req.get_put().get_value().len(),
+            CmdType::Delete => req.get_delete().get_key().len(),
+            _ => 0,
+        })
+        .sum()
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Builds the `WriteCmds` view handed to `handle_write_raft_cmd` from a
+    /// batch of raft requests. When `engine_store_cfg.enable_decode_pipeline`
+    /// is set, this work runs on `self.decode_pool` instead of the caller's
+    /// (apply) thread, bounded by `decode_pipeline_quota`; over quota, it
+    /// falls back to decoding inline rather than blocking, since the point is
+    /// to bound memory, not to add backpressure.
+    ///
+    /// The call is still synchronous from the caller's point of view -- it
+    /// blocks until the buffer is ready -- so today this only moves CPU work
+    /// to another thread rather than letting the apply thread advance to the
+    /// next raft entry while decode is in flight. Overlapping those would
+    /// need `post_exec_query` to return a future instead of `bool`, which is
+    /// a larger change to the apply loop than this request covers.
+    pub(crate) fn decode_write_cmds(&self, requests: &[Request]) -> WriteCmds {
+        const NONE_STR: &str = "";
+        let track_txn_source = self.packed_envs.engine_store_cfg.enable_txn_source_tracking;
+        let forward_ttl = self.packed_envs.engine_store_cfg.enable_ttl_forwarding
+            && self.packed_envs.api_version == kvproto::kvrpcpb::ApiVersion::V2;
+
+        let build = |requests: &[Request]| -> WriteCmds {
+            let mut cmds = WriteCmds::take_pooled(requests.len());
+            for req in requests {
+                match req.get_cmd_type() {
+                    CmdType::Put => {
+                        let put = req.get_put();
+                        if self.is_write_replication_filtered(put.get_key()) {
+                            self.record_replication_filtered(
+                                (put.get_key().len() + put.get_value().len()) as u64,
+                            );
+                            continue;
+                        }
+                        let cf = name_to_cf(put.get_cf());
+                        if track_txn_source && put.get_cf() == CF_WRITE {
+                            if let Ok(w) = WriteRef::parse(put.get_value()) {
+                                if w.txn_source != 0 {
+                                    cmds.mark_txn_source(w.txn_source);
+                                }
+                            }
+                        }
+                        if forward_ttl && put.get_cf() == CF_DEFAULT {
+                            if let Ok(v) = ApiV2::decode_raw_value(put.get_value()) {
+                                if let Some(expire_ts) = v.expire_ts {
+                                    cmds.mark_expire_ts(expire_ts);
+                                }
+                            }
+                        }
+                        cmds.push(put.get_key(), put.get_value(), WriteCmdType::Put, cf);
+                    }
+                    CmdType::Delete => {
+                        let del = req.get_delete();
+                        if self.is_write_replication_filtered(del.get_key()) {
+                            self.record_replication_filtered(del.get_key().len() as u64);
+                            continue;
+                        }
+                        let cf = name_to_cf(del.get_cf());
+                        cmds.push(del.get_key(), NONE_STR.as_ref(), WriteCmdType::Del, cf);
+                    }
+                    _ => (),
+                }
+            }
+            cmds
+        };
+
+        let cfg = &self.packed_envs.engine_store_cfg;
+        let pool = match (cfg.enable_decode_pipeline, self.decode_pool.as_ref()) {
+            (true, Some(pool)) => pool,
+            _ => return build(requests),
+        };
+
+        let bytes = estimate_batch_bytes(requests);
+        let limit = cfg.decode_pipeline_quota.0 as usize;
+        let reserved = self.decode_pipeline_quota_used.fetch_add(bytes, Ordering::AcqRel);
+        if reserved + bytes > limit {
+            self.decode_pipeline_quota_used
+                .fetch_sub(bytes, Ordering::AcqRel);
+            return build(requests);
+        }
+        let _guard = QuotaGuard {
+            used: &self.decode_pipeline_quota_used,
+            bytes,
+        };
+
+        let requests = requests.to_vec();
+        let (tx, rx) = mpsc::channel();
+        pool.spawn(async move {
+            let _ = tx.send(build(&requests));
+        });
+        match rx.recv() {
+            Ok(cmds) => cmds,
+            Err(_) => build(&[]),
+        }
+    }
+}
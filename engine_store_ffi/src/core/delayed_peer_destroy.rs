@@ -0,0 +1,154 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//! A grace period between `on_region_changed` observing a learner peer's
+//! local destroy and this store actually notifying the engine store to
+//! purge its own copy (`core::segment_gc_journal`'s `handle_destroy` call),
+//! so a peer PD re-adds shortly after removing -- e.g. a rebalance that
+//! immediately regrets itself, or the back half of `core::rebuild_region`'s
+//! own remove-then-readd dance -- can be resurrected by a fresh snapshot
+//! landing on data the engine store never actually dropped, instead of
+//! forcing a full re-ingest from scratch.
+//!
+//! Raftstore itself still tears down the peer's local metadata and
+//! RocksDB data immediately, same as without this module -- this crate has
+//! no hook into that and doesn't need one, since a TiFlash-backed store
+//! holds almost none of the region's actual data locally anyway (see
+//! `core::region_stats`'s doc comment). What this delays is purely this
+//! crate's own handoff of the destroy to the engine store, which is the
+//! side actually holding the bulk of the data.
+//!
+//! No dedicated reaper thread: like `core::leader_transfer_coalescing` and
+//! `core::background_defrag`, the sweep piggybacks on the existing
+//! `CompactLog` admission tick (`core::checkpoint_compaction`'s own
+//! periodic heartbeat), so a pending destroy's grace period is checked
+//! roughly every tick rather than on its own schedule. A destroy sitting
+//! past its deadline for one extra tick is harmless; a dedicated thread
+//! for this alone isn't worth it.
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use lazy_static::lazy_static;
+use serde_derive::Serialize;
+
+use crate::core::{common::*, ProxyForwarder};
+
+/// A learner peer whose local destroy has been observed but whose
+/// engine-store purge is being held back until `due_at` elapses.
+#[derive(Clone, Debug, Serialize)]
+pub struct PendingDestroy {
+    pub region_id: u64,
+    #[serde(skip)]
+    pub start_key: Vec<u8>,
+    #[serde(skip)]
+    pub end_key: Vec<u8>,
+    pub conf_ver: u64,
+    pub version: u64,
+    pub requested_at_unix_secs: u64,
+    pub due_at_unix_secs: u64,
+    /// Best-effort size estimate recorded at the time the destroy was
+    /// deferred, for `/debug/pending_peer_destroy`'s disk-usage column.
+    /// `None` until `core::region_stats::region_approximate_stat` has a
+    /// real FFI slot to query it from -- see that module's doc comment.
+    pub approximate_size: Option<u64>,
+    #[serde(skip)]
+    due_at: Instant,
+}
+
+lazy_static! {
+    // Global rather than a `ProxyForwarder` field, matching
+    // `core::rebuild_region`'s `REBUILDS`: the status server reports this
+    // without holding a forwarder handle, and the callbacks that populate
+    // it live on `ProxyForwarder`.
+    static ref PENDING: Mutex<HashMap<u64, PendingDestroy>> = Mutex::new(HashMap::default());
+}
+
+fn unix_secs_from(instant: Instant, now_instant: Instant, now_unix: SystemTime) -> u64 {
+    let delta = instant.saturating_duration_since(now_instant);
+    (now_unix + delta)
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Defers `region_id`'s engine-store purge by `grace_period`, unless one is
+/// already pending for it. Returns `false` (leaving the existing deadline
+/// untouched) in that case, same semantics as `rebuild_region::request_rebuild`.
+pub fn defer_destroy(
+    region_id: u64,
+    start_key: &[u8],
+    end_key: &[u8],
+    conf_ver: u64,
+    version: u64,
+    approximate_size: Option<u64>,
+    grace_period: Duration,
+) -> bool {
+    let mut pending = PENDING.lock().unwrap();
+    if pending.contains_key(&region_id) {
+        return false;
+    }
+    let now_instant = Instant::now();
+    let now_unix = SystemTime::now();
+    let due_at = now_instant + grace_period;
+    pending.insert(
+        region_id,
+        PendingDestroy {
+            region_id,
+            start_key: start_key.to_vec(),
+            end_key: end_key.to_vec(),
+            conf_ver,
+            version,
+            requested_at_unix_secs: now_unix
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            due_at_unix_secs: unix_secs_from(due_at, now_instant, now_unix),
+            approximate_size,
+            due_at,
+        },
+    );
+    true
+}
+
+/// Cancels `region_id`'s pending destroy, e.g. because `on_region_changed`
+/// observed it alive again (PD re-added it before the grace period
+/// elapsed). Returns `true` if a pending destroy was actually cancelled.
+pub fn cancel_if_resurrected(region_id: u64) -> bool {
+    PENDING.lock().unwrap().remove(&region_id).is_some()
+}
+
+/// Removes and returns every pending destroy whose grace period has
+/// elapsed, for the caller to actually hand off to the engine store.
+pub fn drain_due() -> Vec<PendingDestroy> {
+    let mut pending = PENDING.lock().unwrap();
+    let due_ids: Vec<u64> = pending
+        .iter()
+        .filter(|(_, p)| Instant::now() >= p.due_at)
+        .map(|(id, _)| *id)
+        .collect();
+    due_ids
+        .into_iter()
+        .filter_map(|id| pending.remove(&id))
+        .collect()
+}
+
+/// Snapshot of everything currently pending, for
+/// `GET /debug/pending_peer_destroy`. Does not remove anything.
+pub fn pending_destroys() -> Vec<PendingDestroy> {
+    PENDING.lock().unwrap().values().cloned().collect()
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Hands off every pending destroy whose grace period has elapsed to
+    /// the engine store via `notify_segment_gc`, same as an immediate
+    /// destroy would have. Called from the `CompactLog` admission tick --
+    /// see this module's doc comment for why there's no dedicated thread.
+    pub(crate) fn reap_due_peer_destroys(&self) {
+        for pending in drain_due() {
+            self.notify_segment_gc(
+                pending.region_id,
+                &pending.start_key,
+                &pending.end_key,
+                pending.conf_ver,
+                pending.version,
+            );
+        }
+    }
+}
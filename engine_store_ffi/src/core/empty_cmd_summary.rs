@@ -0,0 +1,85 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//! Capability negotiation for summarizing `on_empty_cmd` callbacks.
+//!
+//! Every raft leadership change commits an empty entry that
+//! `forward_raft::command::on_empty_cmd` must still hand to the engine store
+//! (see that function's doc comment) purely so it observes the term/index
+//! advance -- the entry itself carries no data. At the scale of many regions
+//! churning leaders (e.g. during a rolling restart), that is one
+//! `handle_write_raft_cmd` call per region per leadership change, all
+//! carrying nothing but `RaftCmdHeader`.
+//!
+//! An engine store build can opt into fewer, coalesced calls by setting
+//! [`CAPABILITY_KEY`] in the config blob `fn_get_config` returns. Once
+//! negotiated, `ProxyForwarder::should_skip_summarized_empty_cmd` collapses
+//! repeat empty cmds for a term the engine store has already been told
+//! about into a no-op, leaving exactly one "term advanced to T at index I"
+//! call per region per term -- still delivered as an ordinary
+//! `handle_write_raft_cmd(RaftCmdHeader{index, term})` call, since a
+//! distinct summarized-notification entry point would need a new slot on
+//! `RaftStoreProxyFFIHelper`, which requires regenerating the bindgen'd
+//! header via the `gen-proxy-ffi` toolchain. `should_notify` relies on
+//! `on_empty_cmd` being driven off each region's own apply fsm, which
+//! processes that region's log strictly in order, to guarantee terms are
+//! only ever observed non-decreasing -- so "already notified for this term"
+//! can never become stale.
+use std::collections::HashMap;
+
+use crate::core::{common::*, ProxyForwarder};
+
+/// Boolean key an engine store build sets in the JSON blob returned by
+/// `EngineStoreServerHelper::get_config` to opt into summarized
+/// `on_empty_cmd` notifications. Absent or non-boolean is treated as "not
+/// opted in".
+pub const CAPABILITY_KEY: &str = "raft.summarize-empty-cmd";
+
+#[derive(Default)]
+pub struct EmptyCmdSummaryState {
+    // region_id -> highest term already forwarded to the engine store as a
+    // summarized notification.
+    last_notified_term: Mutex<HashMap<u64, u64>>,
+}
+
+impl EmptyCmdSummaryState {
+    /// Returns whether `term` at `region_id` hasn't been forwarded yet,
+    /// recording it as forwarded if so.
+    fn should_notify(&self, region_id: u64, term: u64) -> bool {
+        let mut last = self.last_notified_term.lock().unwrap();
+        match last.get(&region_id) {
+            Some(&t) if t >= term => false,
+            _ => {
+                last.insert(region_id, term);
+                true
+            }
+        }
+    }
+}
+
+/// Queries `helper.get_config` once and reports whether the engine store
+/// opted into summarized `on_empty_cmd` notifications via
+/// [`CAPABILITY_KEY`]. Any parse failure is treated the same as an explicit
+/// opt-out, since per-entry callbacks (the pre-negotiation behavior) are
+/// always correct, just noisier.
+pub fn negotiate_empty_cmd_summary_capability(helper: &EngineStoreServerHelper) -> bool {
+    let config = helper.get_config(false);
+    serde_json::from_slice::<serde_json::Value>(&config)
+        .ok()
+        .and_then(|v| v.get(CAPABILITY_KEY).and_then(|v| v.as_bool()))
+        .unwrap_or(false)
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Whether `forward_raft::command::on_empty_cmd` can skip forwarding
+    /// this particular empty cmd because an earlier one already told the
+    /// engine store about `region_id` reaching `term`. Always `false` unless
+    /// both `engine_store_cfg.enable_empty_cmd_summarization` is set and the
+    /// engine store negotiated support for it at startup.
+    pub(crate) fn should_skip_summarized_empty_cmd(&self, region_id: u64, term: u64) -> bool {
+        if !self.packed_envs.engine_store_cfg.enable_empty_cmd_summarization
+            || !self.empty_cmd_capability.load(Ordering::Acquire)
+        {
+            return false;
+        }
+        !self.empty_cmd_summary_state.should_notify(region_id, term)
+    }
+}
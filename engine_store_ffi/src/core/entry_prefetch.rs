@@ -0,0 +1,132 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//! Bounded, per-region cache of raft log entries read ahead of a lagging
+//! region's engine store catching up, so re-delivery (see `core::rewind`)
+//! does not have to block on a fresh raft-engine read for entries this
+//! proxy already knows the engine store will need next.
+//!
+//! [`ProxyForwarder::prefetch_entries_for_catch_up`] is driven by
+//! `ProxyForwarder::record_replay_debt`, which fires from two places: every
+//! `pre_region_heartbeat` (`ProxyForwarder::refresh_replay_debt`, using the
+//! engine store's own reported applied index via
+//! `EngineStoreServerHelper::get_flushed_state`), and `PUT
+//! /debug/rewind_region/<region_id>` (`ProxyForwarder::rewind_region`'s
+//! `to_index`, an operator-triggered rewind target) via the `CompactLog`
+//! admission tick -- see that module's doc comment.
+use std::{collections::VecDeque, sync::atomic::AtomicUsize};
+
+use raft::eraftpb::Entry;
+
+use crate::core::{
+    common::*,
+    metrics::{TIFLASH_ENTRY_PREFETCH_HIT_COUNTER, TIFLASH_ENTRY_PREFETCH_MISS_COUNTER},
+    ProxyForwarder,
+};
+
+struct RegionPrefetch {
+    entries: VecDeque<Entry>,
+}
+
+/// Per-store cache of prefetched raft log entries, capped by
+/// `engine-store.entry-prefetch-memory-quota` total across all regions.
+pub(crate) struct EntryPrefetchCache {
+    by_region: Mutex<HashMap<u64, RegionPrefetch>>,
+    bytes_used: AtomicUsize,
+}
+
+impl Default for EntryPrefetchCache {
+    fn default() -> Self {
+        EntryPrefetchCache {
+            by_region: Mutex::new(HashMap::default()),
+            bytes_used: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Reads up to `batch_size` entries after `engine_store_index` and up to
+    /// `persisted_index` into the prefetch cache for `region_id`, first
+    /// dropping cached entries of any region, lowest raft index first, if
+    /// the store-wide memory quota would otherwise be exceeded.
+    ///
+    /// See this module's doc comment for what calls this and why.
+    pub fn prefetch_entries_for_catch_up(
+        &self,
+        region_id: u64,
+        engine_store_index: u64,
+        persisted_index: u64,
+    ) {
+        let cfg = &self.packed_envs.engine_store_cfg;
+        if !cfg.enable_entry_prefetch || engine_store_index >= persisted_index {
+            return;
+        }
+        let high = std::cmp::min(
+            persisted_index + 1,
+            engine_store_index + 1 + cfg.entry_prefetch_batch_size as u64,
+        );
+        let mut fetched = vec![];
+        if let Err(e) = self.raft_engine.fetch_entries_to(
+            region_id,
+            engine_store_index + 1,
+            high,
+            None,
+            &mut fetched,
+        ) {
+            warn!("failed to prefetch raft log entries for catch-up";
+                "region_id" => region_id, "err" => ?e);
+            return;
+        }
+        if fetched.is_empty() {
+            return;
+        }
+        let added_bytes: usize = fetched.iter().map(|e| e.get_data().len()).sum();
+        let quota = cfg.entry_prefetch_memory_quota.0 as usize;
+        let mut by_region = self.entry_prefetch.by_region.lock().unwrap();
+        let mut bytes_used = self.entry_prefetch.bytes_used.load(Ordering::Relaxed);
+        while bytes_used + added_bytes > quota {
+            // Evict from whichever region holds the oldest cached batch so
+            // the region this call is trying to help isn't starved by one
+            // that hasn't been looked up in a while.
+            let victim = by_region
+                .iter()
+                .filter(|(_, p)| !p.entries.is_empty())
+                .min_by_key(|(_, p)| p.entries.front().map(|e| e.get_index()))
+                .map(|(id, _)| *id);
+            match victim {
+                Some(id) => {
+                    let p = by_region.get_mut(&id).unwrap();
+                    if let Some(evicted) = p.entries.pop_front() {
+                        bytes_used = bytes_used.saturating_sub(evicted.get_data().len());
+                    }
+                }
+                None => break,
+            }
+        }
+        let entry = by_region
+            .entry(region_id)
+            .or_insert_with(|| RegionPrefetch { entries: VecDeque::new() });
+        entry.entries.extend(fetched);
+        self.entry_prefetch
+            .bytes_used
+            .store(bytes_used + added_bytes, Ordering::Relaxed);
+    }
+
+    /// Takes the cached entry for `(region_id, index)` if it was already
+    /// prefetched, recording a cache hit or miss either way.
+    pub fn take_prefetched_entry(&self, region_id: u64, index: u64) -> Option<Entry> {
+        let mut by_region = self.entry_prefetch.by_region.lock().unwrap();
+        let found = by_region.get_mut(&region_id).and_then(|p| {
+            let pos = p.entries.iter().position(|e| e.get_index() == index)?;
+            p.entries.remove(pos)
+        });
+        match &found {
+            Some(entry) => {
+                self.entry_prefetch
+                    .bytes_used
+                    .fetch_sub(entry.get_data().len(), Ordering::Relaxed);
+                TIFLASH_ENTRY_PREFETCH_HIT_COUNTER.inc();
+            }
+            None => TIFLASH_ENTRY_PREFETCH_MISS_COUNTER.inc(),
+        }
+        found
+    }
+}
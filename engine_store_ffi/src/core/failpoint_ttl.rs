@@ -0,0 +1,92 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//
+// Only meaningful with the `failpoints` feature, same as upstream TiKV's own
+// `/fail` status-server endpoint that `fail::cfg`/`fail::list` already power
+// -- this module just adds TTLs on top, for exploratory testing against a
+// manually started cluster where nobody is left to call `fail::remove` once
+// a scenario (e.g. `try_flush_data`, `on_empty_cmd_normal`) is done being
+// exercised.
+#![cfg(feature = "failpoints")]
+
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use serde_derive::Serialize;
+
+use crate::core::common::*;
+
+/// One failpoint armed with a TTL, for `/debug/failpoints`.
+#[derive(Clone, Debug, Serialize)]
+pub struct ArmedFailpoint {
+    pub name: String,
+    pub actions: String,
+    pub armed_at_millis_ago: u64,
+    pub ttl_millis: Option<u64>,
+}
+
+lazy_static! {
+    // name -> (actions, armed-at, ttl). `fail::list()` already tells us
+    // name/actions for anything armed through any path, including this one;
+    // this only tracks the extra bookkeeping (when, and for how long) that
+    // `fail-rs` itself has no concept of.
+    static ref ARMED: Mutex<HashMap<String, (String, Instant, Option<Duration>)>> =
+        Mutex::new(HashMap::default());
+}
+
+/// Arms `name` with `actions` (the same syntax `fail::cfg` takes, e.g.
+/// `"return"` or `"5*sleep(100)"`), auto-disarming it after `ttl` if given.
+/// Disarming is cooperative, not a background timer: it happens the next
+/// time [`reap_expired`] runs, which callers are expected to do from an
+/// already-periodic call site (a status-server request handler is a
+/// reasonable one, since this is debug-only tooling and not meant to rely on
+/// a dedicated thread).
+pub fn arm(name: String, actions: String, ttl: Option<Duration>) -> Result<(), String> {
+    fail::cfg(name.clone(), &actions)?;
+    ARMED
+        .lock()
+        .unwrap()
+        .insert(name, (actions, Instant::now(), ttl));
+    Ok(())
+}
+
+/// Disarms `name` immediately, regardless of any TTL it was armed with.
+pub fn disarm(name: &str) {
+    fail::remove(name);
+    ARMED.lock().unwrap().remove(name);
+}
+
+/// Disarms and forgets every tracked failpoint whose TTL has elapsed.
+/// Returns their names.
+pub fn reap_expired() -> Vec<String> {
+    let mut armed = ARMED.lock().unwrap();
+    let expired: Vec<String> = armed
+        .iter()
+        .filter(|(_, (_, armed_at, ttl))| matches!(ttl, Some(ttl) if armed_at.elapsed() >= *ttl))
+        .map(|(name, _)| name.clone())
+        .collect();
+    for name in &expired {
+        fail::remove(name);
+        armed.remove(name);
+    }
+    expired
+}
+
+/// Every failpoint armed through [`arm`] and not yet disarmed, for
+/// `/debug/failpoints`. Does not include failpoints armed some other way
+/// (e.g. directly through `/fail`), since only this module's own bookkeeping
+/// is available to report from -- `fail::list()` has no armed-at timestamp
+/// to source that from.
+pub fn list_armed() -> Vec<ArmedFailpoint> {
+    reap_expired();
+    ARMED
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, (actions, armed_at, ttl))| ArmedFailpoint {
+            name: name.clone(),
+            actions: actions.clone(),
+            armed_at_millis_ago: armed_at.elapsed().as_millis() as u64,
+            ttl_millis: ttl.map(|d| d.as_millis() as u64),
+        })
+        .collect()
+}
@@ -0,0 +1,63 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use lazy_static::lazy_static;
+use pd_client::{Feature, FeatureGate};
+
+use crate::core::{common::*, ProxyForwarder};
+
+/// Cluster-version gates for FFI behaviors that a mixed-version cluster
+/// could trip over if an upgraded store used them before every store (and
+/// the engine store binary paired with each) understood them. Mirrors
+/// `raftstore`'s own use of `pd_client::FeatureGate`/`Feature`, e.g.
+/// `raftstore::store::fsm::store`'s batch-split and region-bucket gates.
+pub const BATCH_APPLY: Feature = Feature::require(6, 4, 0);
+pub const FLASHBACK: Feature = Feature::require(6, 5, 0);
+pub const V2_SNAPSHOT: Feature = Feature::require(7, 0, 0);
+
+/// All gates known to the proxy, for `GET /debug/feature_gates`.
+const ALL_GATES: &[(&str, Feature)] = &[
+    ("batch_apply", BATCH_APPLY),
+    ("flashback", FLASHBACK),
+    ("v2_snapshot", V2_SNAPSHOT),
+];
+
+lazy_static! {
+    // Mirrors `ProxyForwarder::packed_envs.feature_gate` so the debug
+    // service can report gate states without needing a forwarder handle,
+    // matching e.g. `core::shadow`'s `SHADOW_HELPER`.
+    static ref GLOBAL_FEATURE_GATE: RwLock<Option<FeatureGate>> = RwLock::new(None);
+}
+
+pub fn register_global_feature_gate(gate: FeatureGate) {
+    *GLOBAL_FEATURE_GATE.write().unwrap() = Some(gate);
+}
+
+/// Snapshot of every known gate's current state, for `GET
+/// /debug/feature_gates`. Empty before the forwarder has been constructed.
+pub fn snapshot_feature_gate_states() -> Vec<(&'static str, bool)> {
+    let gate = GLOBAL_FEATURE_GATE.read().unwrap();
+    match gate.as_ref() {
+        Some(gate) => ALL_GATES
+            .iter()
+            .map(|(name, feature)| (*name, gate.can_enable(*feature)))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Whether `feature` may be used against the current cluster version.
+    /// `packed_envs.feature_gate` is fed from `pd_client.feature_gate()` at
+    /// startup and updated in place as the cluster version advances, so this
+    /// always reflects the live cluster state.
+    pub fn can_enable_feature(&self, feature: Feature) -> bool {
+        self.packed_envs.feature_gate.can_enable(feature)
+    }
+
+    /// Snapshot of every known gate's current state, for the debug service.
+    pub fn feature_gate_states(&self) -> Vec<(&'static str, bool)> {
+        ALL_GATES
+            .iter()
+            .map(|(name, feature)| (*name, self.can_enable_feature(*feature)))
+            .collect()
+    }
+}
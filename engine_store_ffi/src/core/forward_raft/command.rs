@@ -1,4 +1,6 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use kvproto::raft_cmdpb::Request;
+
 use crate::core::{common::*, ProxyForwarder};
 
 impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
@@ -44,6 +46,9 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
             sst_views.push((path.to_str().unwrap().as_bytes(), *cf));
         }
 
+        self.trace_ffi_call(ob_region.get_id(), "handle_ingest_sst", || {
+            format!("index={} term={} ssts={}", index, term, ssts_wrap.len())
+        });
         self.engine_store_server_helper.handle_ingest_sst(
             sst_views,
             RaftCmdHeader::new(ob_region.get_id(), index, term),
@@ -58,10 +63,15 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
     ) -> bool {
         // We still need to pass a dummy cmd, to forward updates.
         let cmd_dummy = WriteCmds::new();
-        let flash_res = self.engine_store_server_helper.handle_write_raft_cmd(
-            &cmd_dummy,
-            RaftCmdHeader::new(ob_region.get_id(), cmd.index, cmd.term),
-        );
+        let flash_res = {
+            let _watchdog = self.apply_watchdog_guard("handle_write_raft_cmd", ob_region.get_id());
+            self.engine_store_server_helper.handle_write_raft_cmd(
+                &cmd_dummy,
+                RaftCmdHeader::new(ob_region.get_id(), cmd.index, cmd.term),
+            )
+        };
+        self.track_apply_persistence(ob_region.get_id(), cmd.index, flash_res);
+        self.observe_apply_error(ob_region.get_id(), region_state.pending_remove, flash_res);
         match flash_res {
             EngineStoreApplyRes::None => false,
             EngineStoreApplyRes::Persist => !region_state.pending_remove,
@@ -78,13 +88,64 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
     ) -> bool {
         match req.get_cmd_type() {
             AdminCmdType::CompactLog => {
-                if !self.engine_store_server_helper.try_flush_data(
-                    ob_region.get_id(),
-                    false,
-                    false,
-                    index,
-                    term,
-                ) {
+                if crate::core::maintenance::is_maintenance_mode() {
+                    info!("maintenance mode active, filter CompactLog";
+                        "region_id" => ?ob_region.get_id(),
+                        "index" => index,
+                        "term" => term,
+                    );
+                    self.record_admin_decision(
+                        ob_region.get_id(),
+                        index,
+                        term,
+                        "compact_log_filtered_maintenance",
+                    );
+                    return true;
+                }
+                if self.packed_envs.engine_store_cfg.enable_notification_inbox {
+                    self.notification_inbox.offer(
+                        ob_region.get_id(),
+                        crate::core::notification_inbox::NotificationKind::FlushRequest,
+                        0,
+                    );
+                }
+                // `try_flush_data` only ever acks one durability tier (see
+                // its doc comment: `force_persist` is logged as unsupported
+                // by every current engine store build, and there is no bit
+                // for requesting a weaker in-memtable-only ack either).
+                // `Memory` can't yet get a faster ack than `Durable` does --
+                // distinguishing the two needs a `fn_try_flush_data` slot
+                // that reports which tier was actually achieved, which
+                // requires regenerating the bindgen'd header via the
+                // `gen-proxy-ffi` toolchain. Track how often the gap matters
+                // so it's visible in metrics until that lands.
+                if self.packed_envs.engine_store_cfg.compact_log_flush_durability
+                    == engine_tiflash::FlushDurabilityLevel::Memory
+                {
+                    crate::core::metrics::TIFLASH_FLUSH_DURABILITY_UNMET_COUNTER.inc();
+                }
+                let flush_limit = self.packed_envs.engine_store_cfg.flush_concurrency_limit;
+                let flush_retry = &self.packed_envs.engine_store_cfg.flush_retry;
+                self.trace_ffi_call(ob_region.get_id(), "try_flush_data", || {
+                    format!("index={} term={}", index, term)
+                });
+                let flushed = {
+                    let _permit = crate::core::flush_coalescing::acquire_flush_permit(flush_limit);
+                    crate::core::retry::retry_with_backoff(
+                        "flush",
+                        flush_retry,
+                        || {
+                            self.engine_store_server_helper.try_flush_data(
+                                ob_region.get_id(),
+                                false,
+                                false,
+                                index,
+                                term,
+                            )
+                        },
+                    )
+                };
+                if !flushed {
                     info!("can't flush data, filter CompactLog";
                         "region_id" => ?ob_region.get_id(),
                         "region_epoch" => ?ob_region.get_region_epoch(),
@@ -93,6 +154,12 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
                         "compact_index" => req.get_compact_log().get_compact_index(),
                         "compact_term" => req.get_compact_log().get_compact_term(),
                     );
+                    self.record_admin_decision(
+                        ob_region.get_id(),
+                        index,
+                        term,
+                        "compact_log_filtered",
+                    );
                     return true;
                 }
                 // Otherwise, we can exec CompactLog, without later rolling
@@ -114,6 +181,13 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
         false
     }
 
+    /// Buffers writes to a frozen region instead of applying them, so the
+    /// engine store's data for it stops changing while a checkpoint is
+    /// taken; see `core::freeze`.
+    pub fn pre_exec_query(&self, ob_region: &Region, requests: &[Request], index: u64, term: u64) -> bool {
+        self.pre_exec_query_freeze(ob_region.get_id(), requests, index, term)
+    }
+
     pub fn post_exec_admin(
         &self,
         ob_region: &Region,
@@ -187,7 +261,12 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
             _ => (),
         }
 
+        self.trace_ffi_call(region_id, "handle_admin_raft_cmd", || {
+            format!("index={} term={} type={:?}", cmd.index, cmd.term, cmd_type)
+        });
+        let write_seq = self.assign_write_sequence(region_id, cmd.index, cmd.term);
         let flash_res = {
+            let _watchdog = self.apply_watchdog_guard("handle_admin_raft_cmd", region_id);
             match new_response {
                 Some(r) => self.engine_store_server_helper.handle_admin_raft_cmd(
                     request,
@@ -201,15 +280,32 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
                 ),
             }
         };
+        let legacy_compact_log_none = cmd_type == AdminCmdType::CompactLog
+            && flash_res == EngineStoreApplyRes::None
+            && self
+                .packed_envs
+                .engine_store_cfg
+                .allow_legacy_compact_log_none;
         let persist = match flash_res {
             EngineStoreApplyRes::None => {
                 if cmd_type == AdminCmdType::CompactLog {
-                    // This could only happen in mock-engine-store when we perform some related
-                    // tests. Formal code should never return None for
-                    // CompactLog now. If CompactLog can't be done, the
-                    // engine-store should return `false` in previous `try_flush_data`.
-                    error!("applying CompactLog should not return None"; "region_id" => region_id,
-                            "peer_id" => region_state.peer_id, "apply_state" => ?apply_state, "cmd" => ?cmd);
+                    if legacy_compact_log_none {
+                        // `allow_legacy_compact_log_none` is set, so this
+                        // store may still have peers backed by a
+                        // pre-upgrade engine store that always returns
+                        // `None` for CompactLog; treat it as expected
+                        // during the rollout rather than a bug.
+                        info!("engine-store returned None for CompactLog, treating as legacy \
+                            pre-upgrade behavior"; "region_id" => region_id,
+                            "peer_id" => region_state.peer_id, "apply_state" => ?apply_state);
+                    } else {
+                        // This could only happen in mock-engine-store when we perform some
+                        // related tests. Formal code should never return None for
+                        // CompactLog now. If CompactLog can't be done, the
+                        // engine-store should return `false` in previous `try_flush_data`.
+                        error!("applying CompactLog should not return None"; "region_id" => region_id,
+                                "peer_id" => region_state.peer_id, "apply_state" => ?apply_state, "cmd" => ?cmd);
+                    }
                 }
                 false
             }
@@ -225,8 +321,33 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
                 !region_state.pending_remove
             }
         };
+        if !legacy_compact_log_none {
+            self.observe_apply_error(region_id, region_state.pending_remove, flash_res);
+        }
+        let persist = persist || self.force_sync_commit(cmd_type);
+        let persist = self.guard_apply_term(region_id, cmd.index, cmd.term, persist);
         if persist {
             info!("should persist admin"; "region_id" => region_id, "peer_id" => region_state.peer_id, "state" => ?apply_state);
+            self.ack_write_sequence(region_id, write_seq);
+        }
+        if cmd_type == AdminCmdType::CompactLog {
+            // CompactLog admission is this crate's own periodic "tick" (see
+            // `core::checkpoint_compaction`'s doc comment), so piggyback the
+            // background defrag check on it instead of needing a thread of
+            // its own.
+            self.maybe_run_background_defrag();
+            // Same piggyback, as a backstop: if leader transfers stop
+            // arriving before a coalescing window naturally elapses (see
+            // `core::leader_transfer_coalescing`), this still flushes
+            // whatever is left pending instead of holding it indefinitely.
+            self.flush_coalesced_empty_cmds();
+            // Same piggyback: sweep any peer destroys whose grace period
+            // (see `core::delayed_peer_destroy`) has elapsed since the last
+            // tick.
+            self.reap_due_peer_destroys();
+            // Same piggyback: run any rewinds an operator queued via
+            // `PUT /debug/rewind_region/<region_id>` (see `core::rewind`).
+            self.process_rewind_requests();
         }
         persist
     }
@@ -234,6 +355,22 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
     pub fn on_empty_cmd(&self, ob_region: &Region, index: u64, term: u64) {
         let region_id = ob_region.get_id();
         fail::fail_point!("on_empty_cmd_normal", |_| {});
+        if self.should_skip_summarized_empty_cmd(region_id, term) {
+            debug!("skip empty cmd, engine store already notified of this term";
+                "region_id" => region_id,
+                "index" => index,
+                "term" => term,
+            );
+            return;
+        }
+        if self.maybe_defer_empty_cmd(region_id, index, term) {
+            debug!("deferring empty cmd notification under leader-transfer coalescing window";
+                "region_id" => region_id,
+                "index" => index,
+                "term" => term,
+            );
+            return;
+        }
         debug!("encounter empty cmd, maybe due to leadership change";
             "region" => ?ob_region,
             "index" => index,
@@ -257,7 +394,6 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
             e.unwrap().parse::<bool>().unwrap()
         });
         let region_id = ob_region.get_id();
-        const NONE_STR: &str = "";
         let requests = cmd.request.get_requests();
         let response = &cmd.response;
         if response.get_header().has_error() {
@@ -277,21 +413,10 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
         }
 
         let mut ssts = vec![];
-        let mut cmds = WriteCmds::with_capacity(requests.len());
         for req in requests {
-            let cmd_type = req.get_cmd_type();
-            match cmd_type {
-                CmdType::Put => {
-                    let put = req.get_put();
-                    let cf = name_to_cf(put.get_cf());
-                    let (key, value) = (put.get_key(), put.get_value());
-                    cmds.push(key, value, WriteCmdType::Put, cf);
-                }
-                CmdType::Delete => {
-                    let del = req.get_delete();
-                    let cf = name_to_cf(del.get_cf());
-                    let key = del.get_key();
-                    cmds.push(key, NONE_STR.as_ref(), WriteCmdType::Del, cf);
+            match req.get_cmd_type() {
+                CmdType::Put | CmdType::Delete => {
+                    self.dispatch_change_event(region_id, req, cmd.index, cmd.term);
                 }
                 CmdType::IngestSst => {
                     ssts.push(engine_traits::SstMetaInfo {
@@ -310,10 +435,20 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
                 }
             }
         }
+        let cmds = if ssts.is_empty() {
+            self.decode_write_cmds(requests)
+        } else {
+            WriteCmds::take_pooled(0)
+        };
 
+        let mut write_seq = None;
         let persist = if !ssts.is_empty() {
             assert_eq!(cmds.len(), 0);
-            match self.handle_ingest_sst_for_engine_store(ob_region, &ssts, cmd.index, cmd.term) {
+            write_seq = Some(self.assign_write_sequence(region_id, cmd.index, cmd.term));
+            let flash_res =
+                self.handle_ingest_sst_for_engine_store(ob_region, &ssts, cmd.index, cmd.term);
+            self.observe_apply_error(region_id, region_state.pending_remove, flash_res);
+            match flash_res {
                 EngineStoreApplyRes::None => {
                     // Before, BR/Lightning may let ingest sst cmd contain only one cf,
                     // which may cause that TiFlash can not flush all region cache into column.
@@ -374,23 +509,59 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
                 }
             }
         } else {
-            let flash_res = {
+            self.log_write_batch_split_plan(region_id, requests);
+            let extended_meta = ExtendedRaftCmdMeta {
+                commit_index: apply_state.get_commit_index(),
+                apply_time_ms: SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64,
+            };
+            self.trace_ffi_call(region_id, "handle_write_raft_cmd", || {
+                format!(
+                    "index={} term={} commit_index={} apply_time_ms={} cmds={}",
+                    cmd.index,
+                    cmd.term,
+                    extended_meta.commit_index,
+                    extended_meta.apply_time_ms,
+                    cmds.len()
+                )
+            });
+            write_seq = Some(self.assign_write_sequence(region_id, cmd.index, cmd.term));
+            let batch_bytes = crate::core::decode_pipeline::estimate_batch_bytes(requests) as u64;
+            self.record_forwarded_bytes(region_id, batch_bytes);
+            self.consume_ffi_io_resource(batch_bytes);
+            let flash_res = if self.packed_envs.engine_store_cfg.enable_write_dry_run {
+                self.dry_run_write(&cmds)
+            } else {
+                let _watchdog = self.apply_watchdog_guard("handle_write_raft_cmd", region_id);
                 self.engine_store_server_helper.handle_write_raft_cmd(
                     &cmds,
                     RaftCmdHeader::new(region_id, cmd.index, cmd.term),
                 )
             };
+            self.track_apply_persistence(region_id, cmd.index, flash_res);
+            self.observe_apply_error(region_id, region_state.pending_remove, flash_res);
+            self.shadow_compare_write(&cmds, region_id, cmd.index, cmd.term, flash_res);
             match flash_res {
                 EngineStoreApplyRes::None => false,
                 EngineStoreApplyRes::Persist => !region_state.pending_remove,
                 EngineStoreApplyRes::NotFound => false,
             }
         };
+        // Safe to recycle now: the engine store has already read (and
+        // copied any data it needs from) the view generated from `cmds` by
+        // the time `handle_write_raft_cmd`/`handle_ingest_sst` returns.
+        cmds.recycle();
         fail::fail_point!("on_post_exec_normal_end", |e| {
             e.unwrap().parse::<bool>().unwrap()
         });
+        let persist = self.guard_apply_term(region_id, cmd.index, cmd.term, persist);
         if persist {
             info!("should persist query"; "region_id" => region_id, "peer_id" => region_state.peer_id, "state" => ?apply_state);
+            if let Some(seq) = write_seq {
+                self.ack_write_sequence(region_id, seq);
+            }
         }
         persist
     }
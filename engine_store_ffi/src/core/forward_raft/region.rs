@@ -1,5 +1,5 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
-use crate::core::{common::*, ProxyForwarder};
+use crate::core::{common::*, CachedPdRegionMeta, ProxyForwarder};
 
 impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
     pub fn on_update_safe_ts(&self, region_id: u64, self_safe_ts: u64, leader_safe_ts: u64) {
@@ -18,11 +18,80 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
                 "region_id" => region_id,
                 "store_id" => self.store_id,
             );
-            self.engine_store_server_helper.handle_destroy(region_id);
+            // Cancel any snapshot pre-handle still running in the background
+            // pool for this region; it has nowhere to be applied to anymore.
+            // The FFI call itself can't be interrupted (see
+            // `core::request_context`), so this only stops the proxy from
+            // waiting on / applying its result.
+            if let Ok(lock) = self.pre_handle_snapshot_ctx.lock() {
+                for task in lock.tracer.values() {
+                    if task.region_id == region_id {
+                        task.cancelled.store(true, Ordering::Release);
+                    }
+                }
+            }
+            crate::core::rebuild_region::note_region_destroyed(region_id);
+            let epoch = ob_region.get_region_epoch();
+            let grace_period = self.packed_envs.engine_store_cfg.peer_destroy_grace_period.0;
+            if grace_period.is_zero() {
+                self.notify_segment_gc(
+                    region_id,
+                    ob_region.get_start_key(),
+                    ob_region.get_end_key(),
+                    epoch.get_conf_ver(),
+                    epoch.get_version(),
+                );
+            } else {
+                let approximate_size = self
+                    .region_approximate_stat(region_id)
+                    .map(|s| s.approximate_size);
+                crate::core::delayed_peer_destroy::defer_destroy(
+                    region_id,
+                    ob_region.get_start_key(),
+                    ob_region.get_end_key(),
+                    epoch.get_conf_ver(),
+                    epoch.get_version(),
+                    approximate_size,
+                    grace_period,
+                );
+            }
             if self.packed_envs.engine_store_cfg.enable_fast_add_peer {
                 self.get_cached_manager()
                     .remove_cached_region_info(region_id);
             }
+            self.cached_region_meta.write().unwrap().remove(&region_id);
+        } else {
+            crate::core::delayed_peer_destroy::cancel_if_resurrected(region_id);
+            let epoch = ob_region.get_region_epoch();
+            let mut cached_region_meta = self.cached_region_meta.write().unwrap();
+            let epoch_bump_count = match cached_region_meta.get(&region_id) {
+                Some(prev)
+                    if prev.conf_ver == epoch.get_conf_ver()
+                        && prev.version == epoch.get_version() =>
+                {
+                    prev.epoch_bump_count
+                }
+                Some(prev) => prev.epoch_bump_count + 1,
+                None => 0,
+            };
+            if self.packed_envs.engine_store_cfg.enable_notification_inbox {
+                self.notification_inbox.offer_epoch_update(
+                    region_id,
+                    epoch.get_version(),
+                    epoch_bump_count,
+                );
+            }
+            cached_region_meta.insert(
+                region_id,
+                CachedPdRegionMeta {
+                    start_key: ob_region.get_start_key().to_vec(),
+                    end_key: ob_region.get_end_key().to_vec(),
+                    peers: ob_region.get_peers().iter().map(|p| p.get_id()).collect(),
+                    conf_ver: epoch.get_conf_ver(),
+                    version: epoch.get_version(),
+                    epoch_bump_count,
+                },
+            );
         }
     }
 
@@ -76,6 +145,13 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
 
     pub fn on_role_change(&self, ob_region: &Region, r: &RoleChange) {
         let region_id = ob_region.get_id();
+        if self.packed_envs.engine_store_cfg.enable_notification_inbox {
+            self.notification_inbox.offer(
+                region_id,
+                crate::core::notification_inbox::NotificationKind::LeaderChange,
+                0,
+            );
+        }
         let is_replicated = !r.initialized;
         let is_fap_enabled = if let Some(b) = self.engine.proxy_ext.config_set.as_ref() {
             b.engine_store.enable_fast_add_peer
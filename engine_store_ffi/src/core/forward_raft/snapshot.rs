@@ -1,6 +1,6 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
 use crate::{
-    core::{common::*, PrehandleTask, ProxyForwarder, PtrWrapper},
+    core::{common::*, forwarder::RegionPriority, PrehandleTask, ProxyForwarder, PtrWrapper},
     fatal,
 };
 
@@ -44,6 +44,61 @@ fn retrieve_sst_files(snap: &store::Snapshot) -> Vec<(PathBuf, ColumnFamilyType)
     sst_views
 }
 
+/// Sanity-checks the set of column families carried by a received snapshot
+/// before it is handed to the engine store. Only cheap, structural checks
+/// are done here (no MVCC decoding). A snapshot rejected at this stage is
+/// logged and its background pre-handle is skipped; nothing is sent back to
+/// the sender, and `post_apply_snapshot`'s blocking pre-handle does not
+/// repeat this check.
+fn validate_snapshot_ssts(
+    region: &Region,
+    ssts: &[(PathBuf, ColumnFamilyType)],
+) -> Result<(), String> {
+    for (path, cf) in ssts {
+        if !matches!(
+            cf,
+            ColumnFamilyType::Default | ColumnFamilyType::Write | ColumnFamilyType::Lock
+        ) {
+            return Err(format!(
+                "region {} snapshot has unexpected cf {:?} in {:?}",
+                region.get_id(),
+                cf,
+                path
+            ));
+        }
+        if !path.exists() {
+            return Err(format!(
+                "region {} snapshot sst {:?} is missing on disk",
+                region.get_id(),
+                path
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Removes a snapshot's row SSTs from local disk once the engine store has
+/// taken ownership of their contents, for `enable_remote_snapshot_apply`
+/// stores. Best-effort: a failure here just means raftstore's normal
+/// snapshot GC cleans the file up later instead, so it's logged and
+/// swallowed rather than propagated.
+fn cleanup_local_snapshot_ssts(
+    ssts: &[(PathBuf, ColumnFamilyType)],
+    region_id: u64,
+    snap_key: &SnapKey,
+) {
+    for (path, _) in ssts {
+        if let Err(e) = std::fs::remove_file(path) {
+            warn!("failed to remove local snapshot sst after remote apply";
+                "region_id" => region_id,
+                "snap_key" => ?snap_key,
+                "path" => ?path,
+                "err" => %e,
+            );
+        }
+    }
+}
+
 fn pre_handle_snapshot_impl(
     engine_store_server_helper: &'static EngineStoreServerHelper,
     peer_id: u64,
@@ -73,12 +128,21 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
         snap: Option<&store::Snapshot>,
     ) {
         let region_id = ob_region.get_id();
+        // A region this store has never cached PD metadata for is, in
+        // practice, a replica being (re)built from nothing rather than a
+        // routine re-sync -- see `core::snapshot_priority` for why that's
+        // the best signal available here.
+        if self.get_cached_region_meta(region_id).is_none() {
+            self.set_region_priority(region_id, RegionPriority::High);
+        }
+        let priority = self.get_region_priority(region_id);
         info!("pre apply snapshot";
             "peer_id" => peer_id,
             "region_id" => region_id,
             "snap_key" => ?snap_key,
             "has_snap" => snap.is_some(),
             "pending" => self.engine.proxy_ext.pending_applies_count.load(Ordering::SeqCst),
+            "priority" => ?priority,
         );
         fail::fail_point!("on_ob_pre_handle_snapshot", |_| {});
 
@@ -86,6 +150,7 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
             None => return,
             Some(s) => s,
         };
+        crate::core::snapshot_apply_history::begin_snapshot_apply(snap_key, snap.total_size());
 
         fail::fail_point!("on_ob_pre_handle_snapshot_delete", |_| {
             let ssts = retrieve_sst_files(snap);
@@ -128,8 +193,40 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
 
         match self.apply_snap_pool.as_ref() {
             Some(p) => {
+                {
+                    // A dropped connection can make raftstore re-send the same
+                    // snapshot; if we are already pre-handling `snap_key`, let
+                    // that in-flight task run to completion instead of
+                    // restarting the (possibly large) SST ingest from scratch.
+                    let lock = match self.pre_handle_snapshot_ctx.lock() {
+                        Ok(l) => l,
+                        Err(_) => fatal!("pre_apply_snapshot poisoned"),
+                    };
+                    if lock.tracer.contains_key(snap_key) {
+                        info!("resuming an in-flight snapshot pre-handle instead of restarting";
+                            "region_id" => region_id,
+                            "snap_key" => ?snap_key,
+                        );
+                        return;
+                    }
+                }
+
+                let ssts = retrieve_sst_files(snap);
+                if let Err(e) = validate_snapshot_ssts(ob_region, &ssts) {
+                    error!("reject corrupt snapshot before pre-handle"; "err" => %e, "region_id" => region_id);
+                    return;
+                }
+                let ffi_ctx = crate::core::request_context::FfiRequestContext::new(
+                    self.packed_envs.engine_store_cfg.ffi_request_timeout.0,
+                    priority,
+                );
                 let (sender, receiver) = mpsc::channel();
-                let task = Arc::new(PrehandleTask::new(receiver, peer_id));
+                let task = Arc::new(PrehandleTask::new(
+                    receiver,
+                    peer_id,
+                    region_id,
+                    ffi_ctx.cancel_handle(),
+                ));
                 {
                     let mut lock = match self.pre_handle_snapshot_ctx.lock() {
                         Ok(l) => l,
@@ -142,7 +239,13 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
                 let engine_store_server_helper = self.engine_store_server_helper;
                 let region = ob_region.clone();
                 let snap_key = snap_key.clone();
-                let ssts = retrieve_sst_files(snap);
+                let prehandle_concurrency_limit = self
+                    .packed_envs
+                    .engine_store_cfg
+                    .snapshot_prehandle_concurrency_limit;
+                let snapshot_bytes = self.prepare_snapshot_artifacts(&ssts);
+                self.consume_ffi_io_resource(snapshot_bytes);
+                let self_ = self.clone();
 
                 // We use thread pool to do pre handling.
                 self.engine
@@ -153,6 +256,19 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
                     // The original implementation is in `Snapshot`, so we don't need to care abort
                     // lifetime.
                     fail::fail_point!("before_actually_pre_handle", |_| {});
+                    if ffi_ctx.should_abort() {
+                        warn!("skip pre-handle snapshot, region gone or deadline exceeded";
+                            "region_id" => region.get_id(),
+                            "peer_id" => task.peer_id,
+                            "snap_key" => ?snap_key,
+                        );
+                        return;
+                    }
+                    let _permit = crate::core::snapshot_priority::acquire_snapshot_prehandle_permit(
+                        ffi_ctx.priority,
+                        prehandle_concurrency_limit,
+                    );
+                    self_.journal_chunk_plan_begin(region.get_id(), &snap_key, &ssts);
                     let res = pre_handle_snapshot_impl(
                         engine_store_server_helper,
                         task.peer_id,
@@ -160,6 +276,7 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
                         &region,
                         &snap_key,
                     );
+                    self_.journal_chunk_plan_commit(region.get_id(), &snap_key);
                     match sender.send(res) {
                         Err(_e) => {
                             error!("pre apply snapshot err when send to receiver";
@@ -237,6 +354,15 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
         }
 
         if should_skip {
+            // The fast-add-peer path took over before we ever reached the
+            // engine store; nothing to report as applied or failed.
+            crate::core::snapshot_apply_history::finish_snapshot_apply(
+                region_id,
+                snap_key,
+                "skipped_fast_add_peer",
+                None,
+                self.packed_envs.engine_store_cfg.snapshot_apply_history_len,
+            );
             return;
         }
 
@@ -264,8 +390,27 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
                             "pending" => self.engine.proxy_ext.pending_applies_count.load(Ordering::SeqCst),
                         );
                         if !should_skip {
+                            self.on_pre_apply_snapshot_persist(ob_region, snap_key);
+                            self.journal_snapshot_apply_begin(region_id, snap_key);
                             self.engine_store_server_helper
                                 .apply_pre_handled_snapshot(snap_ptr.0);
+                            self.journal_snapshot_apply_commit(region_id, snap_key);
+                            self.on_post_apply_snapshot_persist(ob_region, snap_key);
+                            if self.packed_envs.engine_store_cfg.enable_remote_snapshot_apply {
+                                cleanup_local_snapshot_ssts(
+                                    &retrieve_sst_files(snap),
+                                    region_id,
+                                    snap_key,
+                                );
+                            }
+                            crate::core::snapshot_apply_history::finish_snapshot_apply(
+                                region_id,
+                                snap_key,
+                                "applied",
+                                None,
+                                self.packed_envs.engine_store_cfg.snapshot_apply_history_len,
+                            );
+                            crate::core::rebuild_region::note_snapshot_applied(region_id);
                         }
                         false
                     }
@@ -327,8 +472,21 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
                 "snap_key" => ?snap_key,
                 "region_id" => ob_region.get_id(),
             );
+            self.journal_snapshot_apply_begin(region_id, snap_key);
             self.engine_store_server_helper
                 .apply_pre_handled_snapshot(ptr.0);
+            self.journal_snapshot_apply_commit(region_id, snap_key);
+            if self.packed_envs.engine_store_cfg.enable_remote_snapshot_apply {
+                cleanup_local_snapshot_ssts(&retrieve_sst_files(snap), region_id, snap_key);
+            }
+            crate::core::snapshot_apply_history::finish_snapshot_apply(
+                region_id,
+                snap_key,
+                "applied_after_retry",
+                None,
+                self.packed_envs.engine_store_cfg.snapshot_apply_history_len,
+            );
+            crate::core::rebuild_region::note_snapshot_applied(region_id);
             info!("apply snapshot finished";
                 "peer_id" => peer_id,
                 "snap_key" => ?snap_key,
@@ -339,6 +497,42 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
     }
 
     pub fn should_pre_apply_snapshot(&self) -> bool {
-        true
+        // Pause snapshot acceptance while under maintenance, e.g. during an
+        // engine-store restart; raft replication itself is unaffected since
+        // this only gates the observer's pre-apply hook, not message
+        // handling.
+        !crate::core::maintenance::is_maintenance_mode()
+    }
+
+    /// Fires immediately before `apply_pre_handled_snapshot` commits the
+    /// pre-handled snapshot data into the engine store, i.e. the last point
+    /// where `RegionLocalState` on the engine store side is still
+    /// `Applying`. Bracketing this call with
+    /// `on_post_apply_snapshot_persist` lets the engine store publish its
+    /// own snapshot data atomically with the proxy-visible state instead of
+    /// racing a reader that sees the switch to `Normal` mid-publish.
+    ///
+    /// This is the Rust-side hook for what the engine store would call
+    /// `fn_pre_apply_snapshot_persist`/`fn_post_apply_snapshot_persist` for;
+    /// wiring an actual notification across the FFI boundary needs a new
+    /// slot on `EngineStoreServerHelper`, which requires regenerating the
+    /// bindgen'd header via the `gen-proxy-ffi` toolchain and is not done
+    /// here. Until then this only records the transition locally.
+    fn on_pre_apply_snapshot_persist(&self, ob_region: &Region, snap_key: &store::SnapKey) {
+        self.record_admin_decision(
+            ob_region.get_id(),
+            snap_key.idx,
+            snap_key.term,
+            "pre_apply_snapshot_persist",
+        );
+    }
+
+    fn on_post_apply_snapshot_persist(&self, ob_region: &Region, snap_key: &store::SnapKey) {
+        self.record_admin_decision(
+            ob_region.get_id(),
+            snap_key.idx,
+            snap_key.term,
+            "post_apply_snapshot_persist",
+        );
     }
 }
@@ -1,7 +1,32 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+
+use lazy_static::lazy_static;
+use pd_client::FeatureGate;
+
 use crate::core::common::*;
 
+lazy_static! {
+    // Shared by every `ProxyForwarder` clone -- there is only ever one
+    // observer process-wide -- and read from `proxy_server::run`'s local
+    // disk-space ticker, which has no handle on a `ProxyForwarder` to read
+    // the field below from directly. See `engine_store_disk_full`'s doc
+    // comment on the field for why this needs to be merged rather than
+    // last-writer-wins against the local ticker's own verdict.
+    static ref ENGINE_STORE_DISK_FULL: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+}
+
+/// Whether `refresh_disk_full_status` last found the engine store's own
+/// reported disk usage over `disk_full_enter_ratio`. `proxy_server::run`'s
+/// local-disk ticker ORs this into its own verdict instead of overwriting
+/// the shared `tikv_util::sys::disk` status outright, so a full engine
+/// store stays in disk-full protection even on ticks where local disk
+/// alone wouldn't trigger it.
+pub fn engine_store_disk_full() -> bool {
+    ENGINE_STORE_DISK_FULL.load(Ordering::Acquire)
+}
+
 pub struct PtrWrapper(pub RawCppPtr);
 
 unsafe impl Send for PtrWrapper {}
@@ -17,24 +42,75 @@ pub struct PrehandleContext {
 pub struct PrehandleTask {
     pub recv: mpsc::Receiver<PtrWrapper>,
     pub peer_id: u64,
+    // Region id this pre-handle is for, so `on_region_changed` can find and
+    // cancel it if the region is destroyed while the SST ingest is still
+    // running in the background pool.
+    pub region_id: u64,
+    pub cancelled: Arc<AtomicBool>,
 }
 
 impl PrehandleTask {
-    pub fn new(recv: mpsc::Receiver<PtrWrapper>, peer_id: u64) -> Self {
-        PrehandleTask { recv, peer_id }
+    pub fn new(
+        recv: mpsc::Receiver<PtrWrapper>,
+        peer_id: u64,
+        region_id: u64,
+        cancelled: Arc<AtomicBool>,
+    ) -> Self {
+        PrehandleTask {
+            recv,
+            peer_id,
+            region_id,
+            cancelled,
+        }
     }
 }
 unsafe impl Send for PrehandleTask {}
 unsafe impl Sync for PrehandleTask {}
 
+/// Apply-priority class assigned to a region, e.g. to let hot tables recover
+/// first after a TiFlash restart. Defaults to `Normal` for regions with no
+/// explicit assignment.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum RegionPriority {
+    Low,
+    Normal,
+    High,
+}
+
+impl Default for RegionPriority {
+    fn default() -> Self {
+        RegionPriority::Normal
+    }
+}
+
 pub struct PackedEnvs {
     pub engine_store_cfg: crate::EngineStoreConfig,
     pub pd_endpoints: Vec<String>,
     pub snap_handle_pool_size: usize,
+    // Cluster version as tracked by the PD client, shared (not copied) so
+    // gate checks always see the latest version PD reported. See
+    // `core::feature_gate`.
+    pub feature_gate: FeatureGate,
+    // `None` if `resource-control.enabled` is off, same condition that
+    // leaves the unified read pool unaccounted too. See
+    // `core::resource_tagging`.
+    pub resource_manager: Option<Arc<resource_control::ResourceGroupManager>>,
+    // The cluster's configured raw-value encoding, needed to know whether a
+    // `Put`'s value even carries an embedded expire timestamp to forward.
+    // See `core::ttl_forwarding`.
+    pub api_version: kvproto::kvrpcpb::ApiVersion,
 }
 
 #[derive(Debug, Default)]
-pub struct DebugStruct {}
+pub struct DebugStruct {
+    pub admin_journal: crate::core::admin_journal::AdminDecisionJournal,
+    pub snapshot_apply_journal: crate::core::snapshot_apply_journal::SnapshotApplyJournal,
+    pub write_sequence: crate::core::write_sequence::WriteSequenceTracker,
+    pub segment_gc_journal: crate::core::segment_gc_journal::SegmentGcJournal,
+    pub chunked_snapshot_journal: crate::core::chunked_snapshot::ChunkedSnapshotJournal,
+    pub restart_detector: crate::core::restart_detection::RestartDetector,
+    pub restore_point_journal: crate::core::restore_point::RestorePointJournal,
+}
 
 impl DebugStruct {}
 
@@ -53,6 +129,119 @@ pub struct ProxyForwarder<T: Transport, ER: RaftEngine> {
     pub snap_mgr: Arc<SnapManager>,
     pub packed_envs: Arc<PackedEnvs>,
     pub debug_struct: Arc<DebugStruct>,
+    // region_id -> assigned apply-priority lane, set via the debug service.
+    pub region_priorities: Arc<RwLock<HashMap<u64, RegionPriority>>>,
+    // region_id -> last region metadata observed from the coprocessor, i.e. a
+    // best-effort mirror of what PD believes about the region. Read-only from
+    // the FFI side; it is refreshed opportunistically on region change events
+    // rather than actively polled from PD.
+    pub cached_region_meta: Arc<RwLock<HashMap<u64, CachedPdRegionMeta>>>,
+    // Writes the engine store rejected or timed out on, as queued by
+    // `queue_failed_write`. Nothing in the proxy calls `queue_failed_write`
+    // or `drain_failed_writes_for_retry`, so no write is re-transferred.
+    pub failed_writes: Arc<Mutex<Vec<FailedWrite>>>,
+    // Splits the engine store asked for because one of its own segments for
+    // the region grew too large, independent of TiKV's own split checker.
+    pub pending_engine_splits: Arc<Mutex<Vec<EngineDrivenSplitRequest>>>,
+    // region_id -> highest apply index the engine store has acknowledged but
+    // not yet reported as persisted (i.e. `handle_write_raft_cmd` returned
+    // something other than `Persist`). Cleared once a `Persist` result is
+    // observed for that region.
+    pub dirty_regions: Arc<RwLock<HashMap<u64, u64>>>,
+    // Whether the engine store's own reported disk usage last crossed
+    // `disk_full_enter_ratio`. Backed by the same `Arc` as the module-level
+    // `ENGINE_STORE_DISK_FULL` (see `engine_store_disk_full()`), so
+    // `proxy_server::run`'s local-disk ticker can fold this in instead of
+    // unconditionally overwriting the shared `tikv_util::sys::disk` status
+    // with its own local-only verdict every tick.
+    pub engine_store_disk_full: Arc<AtomicBool>,
+    // Forgotten learner peers found by `check_learner_health`, queued for
+    // the debug service / an out-of-band repair path to consume.
+    pub pending_peer_repairs: Arc<Mutex<Vec<crate::core::learner_health::ForgottenPeerFinding>>>,
+    // Region-state divergences found by `audit_region_state`, queued for a
+    // consumer to act on when `enable_region_state_auto_correct` is set.
+    pub pending_region_state_repairs:
+        Arc<Mutex<Vec<crate::core::region_state_audit::RegionStateMismatch>>>,
+    // Dedicated pool for `decode_write_cmds` when
+    // `engine_store_cfg.enable_decode_pipeline` is set; `None` otherwise.
+    pub(crate) decode_pool: Option<Arc<ThreadPool<TaskCell>>>,
+    // Bytes of decode work currently in flight on `decode_pool`, checked
+    // against `engine_store_cfg.decode_pipeline_quota`.
+    pub(crate) decode_pipeline_quota_used: Arc<AtomicUsize>,
+    // Dedup/pacing state for `engine_store_cfg.enable_heartbeat_batching`;
+    // see `core::heartbeat_batch`.
+    pub heartbeat_batcher: Arc<crate::core::heartbeat_batch::HeartbeatBatcher>,
+    // Per-region last-persisted (index, term); see `core::applied_term_guard`.
+    pub applied_term_guard: Arc<crate::core::applied_term_guard::AppliedTermGuard>,
+    // Split-check/consistency-check throttling; see `core::region_worker`.
+    pub region_worker_scheduler: Arc<crate::core::region_worker::RegionWorkerScheduler>,
+    // region_id -> cumulative key+value bytes forwarded to
+    // `handle_write_raft_cmd` since this store started; see
+    // `core::region_size_amplification`.
+    pub forwarded_bytes: Arc<RwLock<HashMap<u64, u64>>>,
+    // Bounded, coalescing per-region inbox for leader-change/epoch-update/
+    // flush-request notifications; see `core::notification_inbox`.
+    pub notification_inbox: Arc<crate::core::notification_inbox::NotificationInbox>,
+    // Whether the engine store negotiated support for summarized
+    // `on_empty_cmd` notifications at startup; see `core::empty_cmd_summary`.
+    pub(crate) empty_cmd_capability: Arc<AtomicBool>,
+    // Per-region dedup state backing that negotiation.
+    pub(crate) empty_cmd_summary_state: Arc<crate::core::empty_cmd_summary::EmptyCmdSummaryState>,
+    // Checksum algorithm negotiated with the engine store for pre-handled
+    // snapshot artifacts; see `core::snapshot_checksum`. Decided once at
+    // startup, same as `empty_cmd_capability`.
+    pub(crate) snapshot_checksum_algorithm: engine_tiflash::ChecksumAlgorithm,
+    // Derived once from `packed_envs.resource_manager`, same precedent as
+    // the unified read pool's own `derive_controller` call in
+    // `proxy_server::TiKvServer::init`; `None` if resource control is
+    // disabled. See `core::resource_tagging`.
+    pub(crate) ffi_resource_controller: Option<Arc<resource_control::ResourceController>>,
+    // Tick/throughput-sample/cursor state for `maybe_run_background_defrag`;
+    // see `core::background_defrag`.
+    pub(crate) background_defrag: Arc<crate::core::background_defrag::BackgroundDefragState>,
+    // Bounded cache of raft log entries read ahead for regions still
+    // catching up; see `core::entry_prefetch`.
+    pub(crate) entry_prefetch: Arc<crate::core::entry_prefetch::EntryPrefetchCache>,
+    // Tick state for `maybe_notify_expired_regions`; see
+    // `core::ttl_forwarding`.
+    pub(crate) ttl_forwarding: Arc<crate::core::ttl_forwarding::TtlForwardingState>,
+    // Storm-detection and per-region deferred-notification state for
+    // `maybe_defer_empty_cmd`; see `core::leader_transfer_coalescing`.
+    pub(crate) leader_transfer_coalescing:
+        Arc<crate::core::leader_transfer_coalescing::LeaderTransferCoalescer>,
+}
+
+/// A write forward that failed to reach the engine store, as queued by
+/// `ProxyForwarder::queue_failed_write`. An entry stays queued until
+/// `drain_failed_writes_for_retry` takes it; entries of regions that no
+/// longer exist are not removed.
+#[derive(Clone, Debug)]
+pub struct FailedWrite {
+    pub region_id: u64,
+    pub index: u64,
+    pub term: u64,
+}
+
+/// A read-only snapshot of region metadata, cached from the last
+/// `on_region_changed` observation instead of a fresh PD round-trip.
+#[derive(Clone, Debug)]
+pub struct CachedPdRegionMeta {
+    pub start_key: Vec<u8>,
+    pub end_key: Vec<u8>,
+    pub peers: Vec<u64>,
+    pub conf_ver: u64,
+    pub version: u64,
+    /// How many times `on_region_changed` has observed `conf_ver` or
+    /// `version` change for this region since this store first saw it, i.e.
+    /// since this entry was first inserted. Monotonically increasing for
+    /// the entry's lifetime; reset if the region is destroyed and later
+    /// re-created (a fresh `CachedPdRegionMeta`, not the same entry).
+    /// Carried alongside the epoch version in
+    /// `core::notification_inbox::NotificationInbox::offer_epoch_update`,
+    /// so a consumer that only ever sees the latest coalesced update can
+    /// still tell whether it skipped over intermediate epoch changes
+    /// instead of assuming continuity.
+    pub epoch_bump_count: u64,
 }
 
 impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
@@ -72,6 +261,34 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
             used: stats.fs_stats.used_size,
             avail: stats.fs_stats.avail_size,
         });
+        self.refresh_disk_full_status(stats.fs_stats.capacity_size, stats.fs_stats.used_size);
+    }
+
+    /// Feeds the engine store's own reported disk usage into the same
+    /// `tikv_util::sys::disk` global that TiKV's local disk monitor uses, so
+    /// a full engine store also causes raftstore to reject snapshots, force
+    /// CompactLog admission, and report `DiskFull` in its store heartbeat to
+    /// PD -- without duplicating any of that logic here. Uses hysteresis
+    /// between `disk_full_enter_ratio` and `disk_full_recovery_ratio` so a
+    /// ratio hovering around the threshold doesn't flap the status.
+    fn refresh_disk_full_status(&self, capacity: u64, used: u64) {
+        let cfg = &self.packed_envs.engine_store_cfg;
+        if cfg.disk_full_enter_ratio <= 0.0 || capacity == 0 {
+            return;
+        }
+        let ratio = used as f64 / capacity as f64;
+        let was_full = self.engine_store_disk_full.load(Ordering::Acquire);
+        if !was_full && ratio >= cfg.disk_full_enter_ratio {
+            warn!("engine store disk usage crossed threshold, entering disk-full protection";
+                "ratio" => ratio, "enter_ratio" => cfg.disk_full_enter_ratio);
+            self.engine_store_disk_full.store(true, Ordering::Release);
+            tikv_util::sys::disk::set_disk_status(tikv_util::sys::disk::DiskUsage::AlreadyFull);
+        } else if was_full && ratio < cfg.disk_full_recovery_ratio {
+            info!("engine store disk usage recovered, leaving disk-full protection";
+                "ratio" => ratio, "recovery_ratio" => cfg.disk_full_recovery_ratio);
+            self.engine_store_disk_full.store(false, Ordering::Release);
+            tikv_util::sys::disk::set_disk_status(tikv_util::sys::disk::DiskUsage::Normal);
+        }
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -92,7 +309,63 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
             .max_thread_count(packed_envs.snap_handle_pool_size)
             .build_future_pool();
 
-        ProxyForwarder {
+        crate::core::raft_log_export::register_raft_engine_for_export(Arc::new(
+            raft_engine.clone(),
+        ));
+
+        let decode_pool = if packed_envs.engine_store_cfg.enable_decode_pipeline {
+            Some(Arc::new(
+                Builder::new(tikv_util::thd_name!("engine-decode"))
+                    .max_thread_count(2)
+                    .build_future_pool(),
+            ))
+        } else {
+            None
+        };
+
+        if packed_envs.engine_store_cfg.enable_snapshot_apply_journal {
+            debug_struct
+                .snapshot_apply_journal
+                .recover(std::path::Path::new(engine.path()));
+        }
+
+        if packed_envs.engine_store_cfg.enable_write_sequence_journal {
+            debug_struct
+                .write_sequence
+                .recover(std::path::Path::new(engine.path()));
+        }
+
+        if packed_envs.engine_store_cfg.enable_segment_gc_journal {
+            debug_struct
+                .segment_gc_journal
+                .recover(std::path::Path::new(engine.path()));
+        }
+
+        if packed_envs.engine_store_cfg.enable_chunked_snapshot_journal {
+            debug_struct
+                .chunked_snapshot_journal
+                .recover(std::path::Path::new(engine.path()));
+        }
+
+        crate::core::feature_gate::register_global_feature_gate(packed_envs.feature_gate.clone());
+
+        let region_worker_min_interval = packed_envs.engine_store_cfg.region_worker_min_interval.0;
+        let notification_inbox_capacity = packed_envs.engine_store_cfg.notification_inbox_capacity;
+        let empty_cmd_capability = packed_envs.engine_store_cfg.enable_empty_cmd_summarization
+            && crate::core::empty_cmd_summary::negotiate_empty_cmd_summary_capability(
+                engine_store_server_helper,
+            );
+        let snapshot_checksum_algorithm =
+            crate::core::snapshot_checksum::negotiate_checksum_algorithm(
+                packed_envs.engine_store_cfg.snapshot_checksum_algorithm,
+                engine_store_server_helper,
+            );
+        let ffi_resource_controller = packed_envs
+            .resource_manager
+            .as_ref()
+            .map(|m| m.derive_controller("tiflash-replication".into(), false));
+
+        let forwarder = ProxyForwarder {
             store_id,
             engine_store_server_helper,
             engine,
@@ -105,9 +378,164 @@ impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
             snap_mgr: Arc::new(snap_mgr),
             packed_envs: Arc::new(packed_envs),
             debug_struct: Arc::new(debug_struct),
+            region_priorities: Arc::new(RwLock::new(HashMap::default())),
+            cached_region_meta: Arc::new(RwLock::new(HashMap::default())),
+            failed_writes: Arc::new(Mutex::new(Vec::new())),
+            pending_engine_splits: Arc::new(Mutex::new(Vec::new())),
+            dirty_regions: Arc::new(RwLock::new(HashMap::default())),
+            engine_store_disk_full: ENGINE_STORE_DISK_FULL.clone(),
+            pending_peer_repairs: Arc::new(Mutex::new(Vec::new())),
+            pending_region_state_repairs: Arc::new(Mutex::new(Vec::new())),
+            decode_pool,
+            decode_pipeline_quota_used: Arc::new(AtomicUsize::new(0)),
+            heartbeat_batcher: Arc::new(crate::core::heartbeat_batch::HeartbeatBatcher::default()),
+            applied_term_guard: Arc::new(crate::core::applied_term_guard::AppliedTermGuard::default()),
+            region_worker_scheduler: Arc::new(crate::core::region_worker::RegionWorkerScheduler::new(
+                region_worker_min_interval,
+            )),
+            forwarded_bytes: Arc::new(RwLock::new(HashMap::default())),
+            notification_inbox: Arc::new(crate::core::notification_inbox::NotificationInbox::new(
+                notification_inbox_capacity,
+            )),
+            empty_cmd_capability: Arc::new(AtomicBool::new(empty_cmd_capability)),
+            empty_cmd_summary_state: Arc::new(
+                crate::core::empty_cmd_summary::EmptyCmdSummaryState::default(),
+            ),
+            snapshot_checksum_algorithm,
+            ffi_resource_controller,
+            background_defrag: Arc::new(crate::core::background_defrag::BackgroundDefragState::default()),
+            entry_prefetch: Arc::new(crate::core::entry_prefetch::EntryPrefetchCache::default()),
+            ttl_forwarding: Arc::new(crate::core::ttl_forwarding::TtlForwardingState::default()),
+            leader_transfer_coalescing: Arc::new(
+                crate::core::leader_transfer_coalescing::LeaderTransferCoalescer::default(),
+            ),
+        };
+
+        crate::core::region_worker::register_global_region_worker_scheduler(
+            forwarder.region_worker_scheduler.clone(),
+        );
+        crate::core::notification_inbox::register_global_notification_inbox(
+            forwarder.notification_inbox.clone(),
+        );
+        crate::core::region_garbage_listing::register_global_cached_region_meta(
+            forwarder.cached_region_meta.clone(),
+        );
+
+        let forwarder_for_unfreeze = forwarder.clone();
+        crate::core::freeze::register_global_unfreeze_handler(move |region_id| {
+            forwarder_for_unfreeze.unfreeze_region_local(region_id)
+        });
+
+        let forwarder_for_size_amplification = forwarder.clone();
+        crate::core::region_size_amplification::register_global_region_size_amplification_handler(
+            move |region_id| forwarder_for_size_amplification.region_size_amplification(region_id),
+        );
+
+        let forwarder_for_export = forwarder.clone();
+        let forwarder_for_import = forwarder.clone();
+        crate::core::region_migration::register_global_region_migration_handlers(
+            move |region_id| forwarder_for_export.export_region(region_id),
+            move |bundle| forwarder_for_import.import_region(bundle),
+        );
+
+        let forwarder_for_restore_create = forwarder.clone();
+        let forwarder_for_restore_resume = forwarder.clone();
+        crate::core::restore_point::register_global_restore_point_handlers(
+            move || forwarder_for_restore_create.create_restore_point(),
+            move |id| forwarder_for_restore_resume.resume_restore_point(id),
+        );
+
+        let forwarder_for_consistency_diff = forwarder.clone();
+        crate::core::consistency_diff::register_global_consistency_diff_handler(move |region_id| {
+            forwarder_for_consistency_diff.diff_region_consistency(region_id)
+        });
+
+        let forwarder_for_key_presence = forwarder.clone();
+        crate::core::key_presence_check::register_global_key_presence_handler(move |keys| {
+            forwarder_for_key_presence.check_keys_presence(keys)
+        });
+
+        forwarder
+    }
+
+    pub fn queue_failed_write(&self, region_id: u64, index: u64, term: u64) {
+        self.failed_writes
+            .lock()
+            .unwrap()
+            .push(FailedWrite { region_id, index, term });
+    }
+
+    /// Removes and returns up to `engine_store.failed-write-retry-max-per-tick`
+    /// of the oldest queued writes. It does not re-forward them itself, and
+    /// no background tick calls it.
+    pub fn drain_failed_writes_for_retry(&self) -> Vec<FailedWrite> {
+        let max = self
+            .packed_envs
+            .engine_store_cfg
+            .failed_write_retry_max_per_tick;
+        let mut queue = self.failed_writes.lock().unwrap();
+        let n = queue.len().min(max);
+        queue.drain(..n).collect()
+    }
+
+    /// Records the outcome of forwarding an apply at `index` for `region_id`
+    /// to the engine store, so `list_dirty_regions` can later report it.
+    pub fn track_apply_persistence(&self, region_id: u64, index: u64, res: EngineStoreApplyRes) {
+        match res {
+            EngineStoreApplyRes::Persist => {
+                self.dirty_regions.write().unwrap().remove(&region_id);
+            }
+            EngineStoreApplyRes::None | EngineStoreApplyRes::NotFound => {
+                self.dirty_regions
+                    .write()
+                    .unwrap()
+                    .insert(region_id, index);
+            }
         }
     }
 
+    /// Returns up to `max` region ids whose in-memory apply state on the
+    /// engine store side has advanced past what was last reported persisted,
+    /// so callers can target a flush at just those regions before a
+    /// maintenance window instead of flushing everything.
+    ///
+    /// This is the backing logic for the `fn_list_dirty_regions` entry point
+    /// requested against the engine store FFI. Adding a new slot to
+    /// `RaftStoreProxyFFIHelper` requires regenerating the bindgen'd C++
+    /// header via the `gen-proxy-ffi` toolchain, which this change does not
+    /// do; wire this method up to that slot once the header is regenerated.
+    pub fn list_dirty_regions(&self, max: usize) -> Vec<u64> {
+        self.dirty_regions
+            .read()
+            .unwrap()
+            .keys()
+            .take(max)
+            .copied()
+            .collect()
+    }
+
+    pub fn get_cached_region_meta(&self, region_id: u64) -> Option<CachedPdRegionMeta> {
+        self.cached_region_meta.read().unwrap().get(&region_id).cloned()
+    }
+
+    /// Assigns a region to an apply-priority lane. Called from the debug
+    /// service so operators can mark hot tables before or during a restart.
+    pub fn set_region_priority(&self, region_id: u64, priority: RegionPriority) {
+        self.region_priorities
+            .write()
+            .unwrap()
+            .insert(region_id, priority);
+    }
+
+    pub fn get_region_priority(&self, region_id: u64) -> RegionPriority {
+        self.region_priorities
+            .read()
+            .unwrap()
+            .get(&region_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
     pub fn stop(&self) {
         info!("shutdown tiflash observer"; "store_id" => self.store_id);
         self.apply_snap_pool.as_ref().unwrap().shutdown();
@@ -129,6 +557,29 @@ impl<T: Transport + 'static, ER: RaftEngine> Clone for ProxyForwarder<T, ER> {
             snap_mgr: self.snap_mgr.clone(),
             packed_envs: self.packed_envs.clone(),
             debug_struct: self.debug_struct.clone(),
+            region_priorities: self.region_priorities.clone(),
+            cached_region_meta: self.cached_region_meta.clone(),
+            failed_writes: self.failed_writes.clone(),
+            pending_engine_splits: self.pending_engine_splits.clone(),
+            dirty_regions: self.dirty_regions.clone(),
+            engine_store_disk_full: self.engine_store_disk_full.clone(),
+            pending_peer_repairs: self.pending_peer_repairs.clone(),
+            pending_region_state_repairs: self.pending_region_state_repairs.clone(),
+            decode_pool: self.decode_pool.clone(),
+            decode_pipeline_quota_used: self.decode_pipeline_quota_used.clone(),
+            heartbeat_batcher: self.heartbeat_batcher.clone(),
+            applied_term_guard: self.applied_term_guard.clone(),
+            region_worker_scheduler: self.region_worker_scheduler.clone(),
+            forwarded_bytes: self.forwarded_bytes.clone(),
+            notification_inbox: self.notification_inbox.clone(),
+            empty_cmd_capability: self.empty_cmd_capability.clone(),
+            empty_cmd_summary_state: self.empty_cmd_summary_state.clone(),
+            snapshot_checksum_algorithm: self.snapshot_checksum_algorithm,
+            ffi_resource_controller: self.ffi_resource_controller.clone(),
+            background_defrag: self.background_defrag.clone(),
+            entry_prefetch: self.entry_prefetch.clone(),
+            ttl_forwarding: self.ttl_forwarding.clone(),
+            leader_transfer_coalescing: self.leader_transfer_coalescing.clone(),
         }
     }
 }
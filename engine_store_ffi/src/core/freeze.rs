@@ -0,0 +1,149 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use kvproto::raft_cmdpb::Request;
+use lazy_static::lazy_static;
+
+use crate::core::{common::*, metrics::TIFLASH_FREEZE_OVERFLOW_COUNTER, ProxyForwarder};
+
+struct BufferedQuery {
+    requests: Vec<Request>,
+    index: u64,
+    term: u64,
+}
+
+struct RegionFreeze {
+    // Entries at or above this index are buffered instead of forwarded.
+    at_index: u64,
+    buffered: Vec<BufferedQuery>,
+}
+
+lazy_static! {
+    // Keyed like `core::maintenance`/`core::shadow`'s globals, so the debug
+    // service can freeze/unfreeze without a forwarder handle.
+    static ref FROZEN_REGIONS: Mutex<HashMap<u64, RegionFreeze>> = Mutex::new(HashMap::default());
+    // Set by `ProxyForwarder::new`, so the debug service (which has no
+    // forwarder handle of its own) can still drive a replay; see
+    // `register_global_unfreeze_handler`.
+    static ref UNFREEZE_HANDLER: Mutex<Option<Box<dyn Fn(u64) -> usize + Send + Sync>>> =
+        Mutex::new(None);
+}
+
+pub fn register_global_unfreeze_handler(f: impl Fn(u64) -> usize + Send + Sync + 'static) {
+    *UNFREEZE_HANDLER.lock().unwrap() = Some(Box::new(f));
+}
+
+/// Unfreezes `region_id` via whichever `ProxyForwarder` last registered
+/// itself with `register_global_unfreeze_handler`, replaying whatever was
+/// buffered for it and returning how many entries that was. Returns 0 if no
+/// forwarder has registered yet, or the region wasn't frozen.
+pub fn unfreeze_region(region_id: u64) -> usize {
+    match UNFREEZE_HANDLER.lock().unwrap().as_ref() {
+        Some(f) => f(region_id),
+        None => 0,
+    }
+}
+
+/// Freezes `region_id`'s forwarding to the engine store from `at_index`
+/// (inclusive) onward: further writes are buffered in memory instead of
+/// being sent, so the engine store's view of the region stops changing and
+/// an external tool can take a consistent columnar checkpoint of it and any
+/// other regions frozen alongside it. Freezing an already-frozen region
+/// replaces its freeze point and discards anything buffered under the old
+/// one.
+pub fn freeze_region(region_id: u64, at_index: u64) {
+    FROZEN_REGIONS.lock().unwrap().insert(
+        region_id,
+        RegionFreeze {
+            at_index,
+            buffered: Vec::new(),
+        },
+    );
+}
+
+pub fn is_frozen(region_id: u64) -> bool {
+    FROZEN_REGIONS.lock().unwrap().contains_key(&region_id)
+}
+
+pub fn frozen_regions() -> Vec<u64> {
+    FROZEN_REGIONS.lock().unwrap().keys().copied().collect()
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Called from `QueryObserver::pre_exec_query`. Returns `true` (filter
+    /// the command out of the normal apply path) when `region_id` is frozen
+    /// at or before `index`, after buffering `requests` for `unfreeze_region`
+    /// to replay.
+    pub fn pre_exec_query_freeze(
+        &self,
+        region_id: u64,
+        requests: &[Request],
+        index: u64,
+        term: u64,
+    ) -> bool {
+        let max_buffered = self.packed_envs.engine_store_cfg.freeze_max_buffered_entries;
+        // Runs inside its own block so the `FROZEN_REGIONS` lock is released
+        // before a possible auto-unfreeze below re-acquires it.
+        let overflowed = {
+            let mut frozen = FROZEN_REGIONS.lock().unwrap();
+            match frozen.get_mut(&region_id) {
+                Some(freeze) if index >= freeze.at_index => {
+                    if max_buffered != 0 && freeze.buffered.len() >= max_buffered {
+                        true
+                    } else {
+                        freeze.buffered.push(BufferedQuery {
+                            requests: requests.to_vec(),
+                            index,
+                            term,
+                        });
+                        return true;
+                    }
+                }
+                _ => return false,
+            }
+        };
+        // The freeze has been held long enough to buffer
+        // `freeze-max-buffered-entries` entries without an explicit
+        // unfreeze -- keeping it frozen any longer would let raft log
+        // retention for this region grow without bound, so replay what was
+        // buffered and let this entry (and further ones) through normally.
+        if overflowed {
+            warn!(
+                "region freeze exceeded freeze-max-buffered-entries, auto-unfreezing";
+                "region_id" => region_id,
+                "max_buffered" => max_buffered,
+            );
+            TIFLASH_FREEZE_OVERFLOW_COUNTER.inc();
+            self.unfreeze_region_local(region_id);
+        }
+        false
+    }
+
+    /// Unfreezes `region_id`, replaying everything buffered since `freeze_region`
+    /// straight to the engine store via `handle_write_raft_cmd`, in the order
+    /// it was buffered, then returns the number of entries replayed. Reached
+    /// from outside a forwarder instance via the free function
+    /// `freeze::unfreeze_region`, which dispatches through whichever
+    /// forwarder last called `register_global_unfreeze_handler`.
+    ///
+    /// This bypasses the normal `post_exec_query` path (persist-decision
+    /// bookkeeping, SST handling, change-feed dispatch) since those buffered
+    /// commands never went through `exec_raft_cmd` in the first place -- by
+    /// construction a frozen region only buffers plain `Put`/`Delete`
+    /// entries, so there is nothing else to redo, but this is not a general
+    /// substitute for the real apply path.
+    pub fn unfreeze_region_local(&self, region_id: u64) -> usize {
+        let freeze = FROZEN_REGIONS.lock().unwrap().remove(&region_id);
+        let freeze = match freeze {
+            Some(f) => f,
+            None => return 0,
+        };
+        let count = freeze.buffered.len();
+        for entry in freeze.buffered {
+            let cmds = self.decode_write_cmds(&entry.requests);
+            self.engine_store_server_helper.handle_write_raft_cmd(
+                &cmds,
+                RaftCmdHeader::new(region_id, entry.index, entry.term),
+            );
+        }
+        count
+    }
+}
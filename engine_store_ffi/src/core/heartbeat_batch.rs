@@ -0,0 +1,107 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use std::time::{Duration, Instant};
+
+use raftstore::store::worker::HeartbeatTask;
+
+use crate::core::{common::*, ProxyForwarder};
+
+/// The parts of a `HeartbeatTask` that PD actually needs a fresh report of.
+/// Two heartbeats with the same fingerprint tell PD nothing it doesn't
+/// already know, so a store with hundreds of thousands of quiet learner
+/// regions doesn't need to repeat them every interval.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct HeartbeatFingerprint {
+    term: u64,
+    conf_ver: u64,
+    version: u64,
+    approximate_size: Option<u64>,
+    approximate_keys: Option<u64>,
+    down_peers: usize,
+    pending_peers: usize,
+}
+
+impl HeartbeatFingerprint {
+    fn of(hb: &HeartbeatTask) -> Self {
+        let epoch = hb.region.get_region_epoch();
+        HeartbeatFingerprint {
+            term: hb.term,
+            conf_ver: epoch.get_conf_ver(),
+            version: epoch.get_version(),
+            approximate_size: hb.approximate_size,
+            approximate_keys: hb.approximate_keys,
+            down_peers: hb.down_peers.len(),
+            pending_peers: hb.pending_peers.len(),
+        }
+    }
+}
+
+struct LastSent {
+    fingerprint: HeartbeatFingerprint,
+    at: Instant,
+}
+
+/// Dedups and paces region heartbeats for stores hosting huge numbers of
+/// TiFlash learner regions, so a quiet store doesn't repeat an unchanged
+/// report every interval and a store that just started up doesn't burst all
+/// of its regions' heartbeats into PD at once.
+pub struct HeartbeatBatcher {
+    last_sent: Mutex<HashMap<u64, LastSent>>,
+}
+
+impl Default for HeartbeatBatcher {
+    fn default() -> Self {
+        HeartbeatBatcher {
+            last_sent: Mutex::new(HashMap::default()),
+        }
+    }
+}
+
+impl HeartbeatBatcher {
+    /// Whether `hb` should actually be sent to PD, given `min_interval` (a
+    /// changed heartbeat is always sent, but PD still expects to hear from a
+    /// region at least this often even when nothing changed, so it doesn't
+    /// start suspecting the region went silent) and `resend_spread` (an
+    /// unchanged, already-known region is resent only after a extra,
+    /// region-specific delay within `[0, resend_spread]`, so a huge store's
+    /// forced resends land spread across the interval instead of in one
+    /// burst every `min_interval`).
+    fn should_send(&self, hb: &HeartbeatTask, min_interval: Duration, resend_spread: Duration) -> bool {
+        let region_id = hb.region.get_id();
+        let fingerprint = HeartbeatFingerprint::of(hb);
+        let now = Instant::now();
+        let mut last_sent = self.last_sent.lock().unwrap();
+        let send = match last_sent.get(&region_id) {
+            Some(last) if last.fingerprint == fingerprint => {
+                let spread = if resend_spread.is_zero() {
+                    Duration::ZERO
+                } else {
+                    Duration::from_millis(region_id % (resend_spread.as_millis() as u64 + 1))
+                };
+                now.saturating_duration_since(last.at) >= min_interval + spread
+            }
+            _ => true,
+        };
+        if send {
+            last_sent.insert(region_id, LastSent { fingerprint, at: now });
+        }
+        send
+    }
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Called from `PdTaskObserver::pre_region_heartbeat`. Returns whether
+    /// the heartbeat should still be sent; see `HeartbeatBatcher::should_send`.
+    pub fn pre_region_heartbeat(&self, hb: &HeartbeatTask) -> bool {
+        self.poll_engine_store_restart();
+        self.refresh_replay_debt(hb.region.get_id());
+        let cfg = &self.packed_envs.engine_store_cfg;
+        if !cfg.enable_heartbeat_batching {
+            return true;
+        }
+        self.heartbeat_batcher.should_send(
+            hb,
+            cfg.heartbeat_min_resend_interval.0,
+            cfg.heartbeat_resend_spread.0,
+        )
+    }
+}
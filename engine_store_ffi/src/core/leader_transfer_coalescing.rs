@@ -0,0 +1,122 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//! Detects a burst of leader transfers -- e.g. many regions' leaders
+//! stepping down on this store in quick succession during a TiKV rolling
+//! restart -- and, for the duration of the resulting storm, batches each
+//! affected region's `on_empty_cmd` notification instead of forwarding
+//! every single one immediately, so those content-free calls don't crowd
+//! out `handle_write_raft_cmd` calls that carry actual writes on the same
+//! FFI channel.
+//!
+//! Detection is a rolling counter over `on_empty_cmd` arrivals store-wide,
+//! not per region -- a storm is a property of the store, not any one
+//! region. Once in a storm, a region's pending notification is only ever
+//! replaced by its own next one, never queued alongside an older one,
+//! mirroring `core::empty_cmd_summary`'s reasoning that a region's own
+//! apply fsm processes its log strictly in order -- so whichever
+//! notification is pending when a region is flushed is always its newest,
+//! never a stale one that should have been superseded.
+//!
+//! Flushing happens two ways, neither needing a dedicated thread: the next
+//! `on_empty_cmd` from *any* region once the storm window has elapsed, and
+//! `core::checkpoint_compaction`'s own periodic `CompactLog` tick as a
+//! backstop in case leader transfers stop arriving before the window
+//! naturally closes. `EngineStoreServerHelper` is declared `Sync` (see
+//! `proxy_ffi::engine_store_helper_impls`), so flushing a region's
+//! notification from whichever thread triggers the flush -- not
+//! necessarily that region's own apply thread -- is within the ABI's own
+//! stated contract.
+use std::{collections::VecDeque, time::Duration, time::Instant};
+
+use crate::core::{common::*, ProxyForwarder};
+
+#[derive(Default)]
+pub(crate) struct LeaderTransferCoalescer {
+    arrivals: Mutex<VecDeque<Instant>>,
+    storm_until: Mutex<Option<Instant>>,
+    pending: Mutex<HashMap<u64, (u64, u64)>>,
+}
+
+impl LeaderTransferCoalescer {
+    /// Records one `on_empty_cmd` arrival and reports whether the store is
+    /// (now, or still) inside a coalescing window.
+    fn record_arrival(&self, window: Duration, threshold: usize, coalesce_for: Duration) -> bool {
+        let now = Instant::now();
+        {
+            let mut storm_until = self.storm_until.lock().unwrap();
+            if let Some(until) = *storm_until {
+                if now < until {
+                    return true;
+                }
+                *storm_until = None;
+            }
+        }
+        let mut arrivals = self.arrivals.lock().unwrap();
+        arrivals.push_back(now);
+        while let Some(&front) = arrivals.front() {
+            if now.duration_since(front) > window {
+                arrivals.pop_front();
+            } else {
+                break;
+            }
+        }
+        if threshold != 0 && arrivals.len() >= threshold {
+            arrivals.clear();
+            *self.storm_until.lock().unwrap() = Some(now + coalesce_for);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Replaces any previously deferred notification for `region_id` with
+    /// this one; a region never has more than one entry pending at once, so
+    /// ordering within a region reduces to "whatever is pending is newest".
+    fn defer(&self, region_id: u64, index: u64, term: u64) {
+        self.pending.lock().unwrap().insert(region_id, (index, term));
+    }
+
+    fn drain(&self) -> Vec<(u64, u64, u64)> {
+        self.pending
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(region_id, (index, term))| (region_id, index, term))
+            .collect()
+    }
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Returns `true` if `forward_raft::command::on_empty_cmd` should defer
+    /// this notification (already queued here) instead of forwarding it
+    /// now. As a side effect, flushes any notifications left over from a
+    /// window that has since closed before returning `false`.
+    pub(crate) fn maybe_defer_empty_cmd(&self, region_id: u64, index: u64, term: u64) -> bool {
+        let cfg = &self.packed_envs.engine_store_cfg;
+        if !cfg.enable_leader_transfer_coalescing {
+            return false;
+        }
+        let in_storm = self.leader_transfer_coalescing.record_arrival(
+            cfg.leader_transfer_storm_window.0,
+            cfg.leader_transfer_storm_threshold,
+            cfg.leader_transfer_coalesce_window.0,
+        );
+        if in_storm {
+            self.leader_transfer_coalescing.defer(region_id, index, term);
+            return true;
+        }
+        self.flush_coalesced_empty_cmds();
+        false
+    }
+
+    /// Delivers every notification left pending in this store's
+    /// `LeaderTransferCoalescer`, e.g. once its window has closed or as a
+    /// backstop on the periodic `CompactLog` tick (see
+    /// `core::checkpoint_compaction`). A no-op when nothing is pending.
+    pub(crate) fn flush_coalesced_empty_cmds(&self) {
+        for (region_id, index, term) in self.leader_transfer_coalescing.drain() {
+            let cmd_dummy = WriteCmds::new();
+            self.engine_store_server_helper
+                .handle_write_raft_cmd(&cmd_dummy, RaftCmdHeader::new(region_id, index, term));
+        }
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use lazy_static::lazy_static;
+use pd_client::PdClient;
+
+use crate::core::{common::*, metrics::TIFLASH_FORGOTTEN_LEARNER_COUNTER, ProxyForwarder};
+
+lazy_static! {
+    // Mirrors the most recent call to `check_learner_health`, so the debug
+    // service can report it without needing a handle to the forwarder.
+    static ref LAST_FORGOTTEN_PEERS: Mutex<Vec<ForgottenPeerFinding>> = Mutex::new(Vec::new());
+}
+
+/// Snapshot of findings from the last `check_learner_health` run, for
+/// `GET /debug/forgotten_peers`.
+pub fn snapshot_forgotten_peers() -> Vec<ForgottenPeerFinding> {
+    LAST_FORGOTTEN_PEERS.lock().unwrap().clone()
+}
+
+/// A peer (learner or voter) that is present in our last cached region meta but is no
+/// longer part of the region's peer list according to PD, i.e. a conf-change
+/// removing it was applied elsewhere but this store never observed it -- the
+/// classic "forgotten peer" left behind by a missed message.
+#[derive(Clone, Debug, serde_derive::Serialize)]
+pub struct ForgottenPeerFinding {
+    pub region_id: u64,
+    pub peer_id: u64,
+    pub epoch_version: u64,
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Compares every region we have cached metadata for against PD's
+    /// current view, and reports any cached peer, learner or voter, that PD no longer lists
+    /// for that region. Meant to be called periodically from a background
+    /// tick; findings are queued rather than acted on directly, since
+    /// destroying or re-adding a peer is a raftstore-side conf-change
+    /// decision the forwarder does not own.
+    pub fn check_learner_health<PD: PdClient>(&self, pd_client: &PD) -> Vec<ForgottenPeerFinding> {
+        let snapshot: Vec<(u64, CachedPdRegionMeta)> = self
+            .cached_region_meta
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(k, v)| (*k, v.clone()))
+            .collect();
+
+        let mut findings = vec![];
+        for (region_id, cached) in snapshot {
+            let region = match futures::executor::block_on(pd_client.get_region_by_id(region_id))
+            {
+                Ok(Some(region)) => region,
+                _ => continue,
+            };
+            if region.get_region_epoch().get_version() < cached.version {
+                // PD's view is older than what we cached, e.g. a stale
+                // read landed on a follower; skip until it catches up.
+                continue;
+            }
+            let live_peers: std::collections::HashSet<u64> = region
+                .get_peers()
+                .iter()
+                .map(|p| p.get_id())
+                .collect();
+            for peer_id in &cached.peers {
+                if !live_peers.contains(peer_id) {
+                    warn!("found forgotten learner peer not present in PD's region epoch";
+                        "region_id" => region_id,
+                        "peer_id" => peer_id,
+                        "epoch_version" => region.get_region_epoch().get_version(),
+                    );
+                    TIFLASH_FORGOTTEN_LEARNER_COUNTER.inc();
+                    findings.push(ForgottenPeerFinding {
+                        region_id,
+                        peer_id: *peer_id,
+                        epoch_version: region.get_region_epoch().get_version(),
+                    });
+                }
+            }
+        }
+        if !findings.is_empty() {
+            self.pending_peer_repairs
+                .lock()
+                .unwrap()
+                .extend(findings.iter().cloned());
+        }
+        *LAST_FORGOTTEN_PEERS.lock().unwrap() = findings.clone();
+        findings
+    }
+
+    /// Drains findings queued by `check_learner_health` for the debug
+    /// service, e.g. `GET /debug/forgotten_peers`.
+    pub fn drain_forgotten_peers(&self) -> Vec<ForgottenPeerFinding> {
+        std::mem::take(&mut *self.pending_peer_repairs.lock().unwrap())
+    }
+}
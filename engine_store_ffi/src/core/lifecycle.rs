@@ -0,0 +1,68 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//! Structured startup/shutdown lifecycle events for `proxy_server::run`,
+//! for embedders that need to sequence their own initialization against a
+//! specific stage rather than polling `RaftProxyStatus` (which only ever
+//! distinguishes `Idle`/`Running`/`Stopped`, with everything in between --
+//! config validated, engines opened, raftstore started, FFI handed over --
+//! collapsed into "still `Idle`").
+//!
+//! Only the Rust-embedder half is real: `register_lifecycle_listener` lets
+//! in-process code (tests, alternate `main`s embedding this crate directly)
+//! observe every stage `run_tikv_proxy` passes through. Forwarding the same
+//! stages across the FFI boundary to the engine store itself would need a
+//! new `EngineStoreServerHelper` callback slot that does not exist today --
+//! `notify_engine_store` documents that gap and is a no-op until
+//! `interfaces.rs` grows one via `gen-proxy-ffi`.
+use lazy_static::lazy_static;
+
+use crate::core::common::*;
+
+/// A stage `run_tikv_proxy` has just finished entering, in the order they
+/// normally occur. Not all runs pass through every stage -- e.g. a run that
+/// fails `EnginesOpened` never reaches `RaftstoreStarted`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LifecycleStage {
+    /// The on-disk config has been parsed and validated.
+    ConfigValidated,
+    /// `TiKvServer::init_engines`/`init_tiflash_engines` have returned.
+    EnginesOpened,
+    /// The raftstore system has been started and is taking traffic.
+    RaftstoreStarted,
+    /// `engine_store_server_helper.handle_set_proxy` has returned, so the
+    /// FFI helper is live and the engine store may call back into it.
+    FfiReady,
+    /// Servers and the status server are up; this is steady-state.
+    Serving,
+    /// `TiKvServer::stop` has been called and services are shutting down.
+    Draining,
+    /// All services have stopped.
+    Stopped,
+}
+
+/// Implemented by Rust embedders that want to sequence their own startup
+/// against `run_tikv_proxy`'s stages instead of polling `RaftProxyStatus`.
+pub trait LifecycleListener: Send + Sync {
+    fn on_stage(&self, stage: LifecycleStage);
+}
+
+lazy_static! {
+    static ref LISTENERS: Mutex<Vec<Arc<dyn LifecycleListener>>> = Mutex::new(Vec::new());
+}
+
+pub fn register_lifecycle_listener(listener: Arc<dyn LifecycleListener>) {
+    LISTENERS.lock().unwrap().push(listener);
+}
+
+/// Called by `proxy_server::run` as it enters each stage. Notifies every
+/// registered [`LifecycleListener`] and forwards to the engine store (see
+/// `notify_engine_store`, currently a no-op).
+pub fn notify_lifecycle_stage(stage: LifecycleStage) {
+    for listener in LISTENERS.lock().unwrap().iter() {
+        listener.on_stage(stage);
+    }
+    notify_engine_store(stage);
+}
+
+/// Would forward `stage` to the engine store over FFI. Always a no-op today
+/// -- see this module's doc comment for why.
+fn notify_engine_store(_stage: LifecycleStage) {}
@@ -0,0 +1,40 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use lazy_static::lazy_static;
+
+use crate::core::{
+    common::{RaftEngine, Transport},
+    ProxyForwarder,
+};
+
+lazy_static! {
+    // A process-wide flag rather than a `ProxyForwarder` field so the debug
+    // service can flip it without needing a handle to the forwarder (mirrors
+    // how `learner_health`'s findings are surfaced).
+    static ref MAINTENANCE_MODE: AtomicBool = AtomicBool::new(false);
+}
+
+/// Enables or disables store-scope maintenance mode: while on, the store
+/// stops initiating CompactLog admission and pauses accepting new snapshots,
+/// while raft replication itself keeps running. Meant to be held for the
+/// short window an engine-store restarts under the same proxy during an
+/// upgrade.
+pub fn set_maintenance_mode(enabled: bool) {
+    MAINTENANCE_MODE.store(enabled, Ordering::Release);
+}
+
+pub fn is_maintenance_mode() -> bool {
+    MAINTENANCE_MODE.load(Ordering::Acquire)
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    pub fn set_maintenance_mode(&self, enabled: bool) {
+        set_maintenance_mode(enabled);
+    }
+
+    pub fn is_maintenance_mode(&self) -> bool {
+        is_maintenance_mode()
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+use lazy_static::lazy_static;
+use prometheus::*;
+
+lazy_static! {
+    pub static ref TIFLASH_FORGOTTEN_LEARNER_COUNTER: IntCounter = register_int_counter!(
+        "tikv_tiflash_forgotten_learner_peer_total",
+        "Total number of learner peers found present in cached region meta but missing from \
+         PD's region epoch during periodic health self-check"
+    )
+    .unwrap();
+    pub static ref TIFLASH_SHADOW_DIVERGENCE_COUNTER: IntCounter = register_int_counter!(
+        "tikv_tiflash_shadow_engine_store_divergence_total",
+        "Total number of write applies where the shadow engine store's result diverged from \
+         the primary's, during observer dry-run canarying"
+    )
+    .unwrap();
+    pub static ref TIFLASH_RETRY_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "tikv_tiflash_ffi_retry_total",
+        "Total number of failed FFI call attempts retried by `core::retry`, by call class",
+        &["call_class"]
+    )
+    .unwrap();
+    pub static ref TIFLASH_APPLY_TERM_REGRESSION_COUNTER: IntCounter = register_int_counter!(
+        "tikv_tiflash_apply_term_regression_total",
+        "Total number of persist decisions refused by `core::applied_term_guard` because the \
+         command's term regressed at or above a previously-persisted apply index"
+    )
+    .unwrap();
+    pub static ref TIFLASH_SNAPSHOT_PREHANDLE_QUEUE_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "tikv_tiflash_snapshot_prehandle_queue_length",
+        "Number of snapshot pre-handle tasks currently waiting to be admitted in \
+         `core::snapshot_priority`, by region priority",
+        &["priority"]
+    )
+    .unwrap();
+    pub static ref TIFLASH_SNAPSHOT_PREHANDLE_ADMITTED_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "tikv_tiflash_snapshot_prehandle_admitted_total",
+        "Total number of snapshot pre-handle tasks admitted by `core::snapshot_priority`, by \
+         region priority",
+        &["priority"]
+    )
+    .unwrap();
+    pub static ref TIFLASH_REGION_STATE_MISMATCH_COUNTER: IntCounter = register_int_counter!(
+        "tikv_tiflash_region_state_mismatch_total",
+        "Total number of regions found with a local RegionLocalState (epoch or peer list) \
+         diverging from PD's view during `core::region_state_audit`'s periodic sample"
+    )
+    .unwrap();
+    pub static ref TIFLASH_APPLY_ERROR_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "tikv_tiflash_apply_error_total",
+        "Total number of non-persisting engine-store apply results, classified by \
+         `core::apply_error_taxonomy`, by error class",
+        &["class"]
+    )
+    .unwrap();
+    pub static ref TIFLASH_REPLICATION_FILTERED_KEYS_COUNTER: IntCounter = register_int_counter!(
+        "tikv_tiflash_replication_filtered_keys_total",
+        "Total number of writes skipped by `core::replication_filter` because their key fell \
+         in an excluded key range"
+    )
+    .unwrap();
+    pub static ref TIFLASH_REPLICATION_FILTERED_BYTES_COUNTER: IntCounter = register_int_counter!(
+        "tikv_tiflash_replication_filtered_bytes_total",
+        "Total key+value bytes skipped by `core::replication_filter` because their key fell in \
+         an excluded key range"
+    )
+    .unwrap();
+    pub static ref TIFLASH_WRITE_DRY_RUN_MARSHAL_DURATION_HISTOGRAM: Histogram = register_histogram!(
+        "tikv_tiflash_write_dry_run_marshal_duration_secs",
+        "Bucketed histogram of time spent building a write's `WriteCmdsView` under \
+         `core::write_path_dry_run`, i.e. with the engine-store FFI call itself skipped",
+        exponential_buckets(0.00001, 2.0, 26).unwrap()
+    )
+    .unwrap();
+    pub static ref TIFLASH_WRITE_DRY_RUN_CALL_DURATION_HISTOGRAM: Histogram = register_histogram!(
+        "tikv_tiflash_write_dry_run_call_duration_secs",
+        "Bucketed histogram of time spent in `core::write_path_dry_run`'s no-op sink in place \
+         of the real `handle_write_raft_cmd` call, for comparison against that call's own \
+         latency when an engine store is attached",
+        exponential_buckets(0.00001, 2.0, 26).unwrap()
+    )
+    .unwrap();
+    pub static ref TIFLASH_REGION_SIZE_AMPLIFICATION_RATIO: Histogram = register_histogram!(
+        "tikv_tiflash_region_size_amplification_ratio",
+        "Distribution of engine-store-reported bytes retained for a region divided by bytes \
+         forwarded to it over the FFI, observed by `core::region_size_amplification` whenever \
+         both sides are known"
+    )
+    .unwrap();
+    pub static ref TIFLASH_SNAPSHOT_SEND_VETO_COUNTER: IntCounter = register_int_counter!(
+        "tikv_tiflash_snapshot_send_veto_total",
+        "Total number of outgoing snapshot generations `core::snapshot_send_gate` reports as \
+         vetoed by a registered handler; always zero until both a send-side FFI call and a \
+         raftstore hook to call it from exist -- see that module's doc comment"
+    )
+    .unwrap();
+    pub static ref TIFLASH_NOTIFICATION_COALESCED_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "tikv_tiflash_notification_coalesced_total",
+        "Total number of `core::notification_inbox` notifications superseded by a fresher one \
+         for the same region before being drained, by kind",
+        &["kind"]
+    )
+    .unwrap();
+    pub static ref TIFLASH_NOTIFICATION_DROPPED_COUNTER: IntCounter = register_int_counter!(
+        "tikv_tiflash_notification_dropped_total",
+        "Total number of `core::notification_inbox` notifications dropped because the store-wide \
+         cap on distinct tracked regions was already reached"
+    )
+    .unwrap();
+    pub static ref TIFLASH_FREEZE_OVERFLOW_COUNTER: IntCounter = register_int_counter!(
+        "tikv_tiflash_freeze_overflow_total",
+        "Total number of times `core::freeze` auto-unfroze a region because its buffered-entry \
+         count reached engine-store.freeze-max-buffered-entries before an explicit unfreeze"
+    )
+    .unwrap();
+    pub static ref TIFLASH_FLUSH_DURABILITY_UNMET_COUNTER: IntCounter = register_int_counter!(
+        "tikv_tiflash_flush_durability_unmet_total",
+        "Total number of CompactLog flushes configured to require only \
+         engine-store.compact-log-flush-durability = \"memory\" that still waited for a fully \
+         durable `try_flush_data` ack, because no engine store build supports acking a weaker \
+         tier yet"
+    )
+    .unwrap();
+    pub static ref TIFLASH_REPLAY_DEBT_GAUGE: IntGauge = register_int_gauge!(
+        "tikv_tiflash_replay_debt_entries",
+        "Sum of raft log entries every region tracked by `core::replay_debt` still owes its \
+         engine store since restart; a node-wide startup-progress metric that reaches zero \
+         once the engine store has caught every region up"
+    )
+    .unwrap();
+    pub static ref TIFLASH_ENTRY_PREFETCH_HIT_COUNTER: IntCounter = register_int_counter!(
+        "tikv_tiflash_entry_prefetch_hit_total",
+        "Total number of raft log entry lookups served from `core::entry_prefetch`'s cache \
+         instead of a fresh raft-engine read"
+    )
+    .unwrap();
+    pub static ref TIFLASH_ENTRY_PREFETCH_MISS_COUNTER: IntCounter = register_int_counter!(
+        "tikv_tiflash_entry_prefetch_miss_total",
+        "Total number of raft log entry lookups that found nothing in `core::entry_prefetch`'s \
+         cache, either because prefetching is disabled or the entry had not been read ahead yet"
+    )
+    .unwrap();
+    pub static ref TIFLASH_CHECKPOINT_COMPACTION_COUNTER: IntCounter = register_int_counter!(
+        "tikv_tiflash_checkpoint_compaction_total",
+        "Total number of times `core::checkpoint_compaction` GC'd a region's raft log and \
+         apply-state history in response to the engine store confirming it durably caught up \
+         or from `core::background_defrag`'s sweep, rather than waiting on the next \
+         PD-scheduled CompactLog tick"
+    )
+    .unwrap();
+}
@@ -1,10 +1,130 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
 
+pub mod admin_journal;
+pub mod applied_term_guard;
+pub mod apply_error_taxonomy;
+pub mod apply_watchdog;
+pub mod background_defrag;
+pub mod change_feed;
+pub mod checkpoint_compaction;
+pub mod chunked_snapshot;
+pub mod consistency_diff;
 pub(crate) mod common;
+pub mod decode_pipeline;
+pub mod delayed_peer_destroy;
+pub mod empty_cmd_summary;
+pub mod entry_prefetch;
 pub mod fast_add_peer;
+#[cfg(feature = "failpoints")]
+pub mod failpoint_ttl;
+pub mod feature_gate;
+pub mod flush_coalescing;
 pub mod forward_raft;
 pub mod forwarder;
+pub mod freeze;
+pub mod heartbeat_batch;
+pub mod key_presence_check;
+pub mod leader_transfer_coalescing;
+pub mod learner_health;
+pub mod lifecycle;
+pub mod maintenance;
+pub mod metrics;
+pub mod notification_inbox;
+pub mod parallel_prehandle;
+pub mod raft_log_export;
+pub mod rebuild_region;
+pub mod region_garbage_listing;
+pub mod region_migration;
+pub mod region_size_amplification;
+pub mod region_state_audit;
+pub mod region_stats;
+pub mod region_worker;
+pub mod replay_debt;
+pub mod replication_filter;
+pub mod request_context;
+pub mod resource_tagging;
+pub mod restart_detection;
+pub mod restore_point;
+pub mod retry;
+pub mod rewind;
+pub mod runtime;
+pub mod segment_gc_journal;
+pub mod shadow;
+pub mod shm_transport;
+pub mod snapshot_apply_history;
+pub mod snapshot_apply_journal;
+pub mod snapshot_checksum;
+pub mod snapshot_priority;
+pub mod snapshot_send_gate;
+pub mod split;
+pub mod sst_ingest_by_reference;
+pub mod startup_check;
+pub mod sync_commit;
+pub mod ttl_forwarding;
+pub mod verbose_trace;
+pub mod write_batch_split;
+pub mod write_path_dry_run;
+pub mod write_sequence;
 
+pub use admin_journal::*;
+pub use applied_term_guard::*;
+pub use apply_error_taxonomy::*;
+pub use apply_watchdog::*;
+pub use background_defrag::*;
+pub use change_feed::*;
+pub use checkpoint_compaction::*;
+pub use chunked_snapshot::*;
+pub use consistency_diff::*;
+pub use delayed_peer_destroy::*;
+pub use empty_cmd_summary::*;
+pub use entry_prefetch::*;
 pub use fast_add_peer::*;
+#[cfg(feature = "failpoints")]
+pub use failpoint_ttl::*;
+pub use feature_gate::*;
+pub use flush_coalescing::*;
 pub use forward_raft::*;
 pub use forwarder::*;
+pub use freeze::*;
+pub use heartbeat_batch::*;
+pub use key_presence_check::*;
+pub use leader_transfer_coalescing::*;
+pub use learner_health::*;
+pub use lifecycle::*;
+pub use maintenance::*;
+pub use notification_inbox::*;
+pub use parallel_prehandle::*;
+pub use raft_log_export::*;
+pub use rebuild_region::*;
+pub use region_garbage_listing::*;
+pub use region_migration::*;
+pub use region_size_amplification::*;
+pub use region_state_audit::*;
+pub use region_stats::*;
+pub use region_worker::*;
+pub use replay_debt::*;
+pub use replication_filter::*;
+pub use request_context::*;
+pub use resource_tagging::*;
+pub use restart_detection::*;
+pub use restore_point::*;
+pub use retry::*;
+pub use rewind::*;
+pub use runtime::*;
+pub use segment_gc_journal::*;
+pub use shadow::*;
+pub use shm_transport::*;
+pub use snapshot_apply_history::*;
+pub use snapshot_apply_journal::*;
+pub use snapshot_checksum::*;
+pub use snapshot_priority::*;
+pub use snapshot_send_gate::*;
+pub use split::*;
+pub use sst_ingest_by_reference::*;
+pub use startup_check::*;
+pub use sync_commit::*;
+pub use ttl_forwarding::*;
+pub use verbose_trace::*;
+pub use write_batch_split::*;
+pub use write_path_dry_run::*;
+pub use write_sequence::*;
@@ -0,0 +1,193 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//! A bounded, coalescing per-region inbox for the notifications a hot
+//! region can otherwise flood the engine store with: leader changes, epoch
+//! updates, and flush requests. Each region gets at most one pending
+//! notification per kind -- a fresh one of the same kind replaces (rather
+//! than queues behind) whatever is already pending, and epoch updates
+//! specifically keep whichever has the higher epoch version, so an
+//! out-of-order delivery can't regress a region back to a stale epoch.
+//!
+//! Because raftstore applies a single region's admin/role/epoch events
+//! serially on that region's own apply fsm, coalescing within one region
+//! rarely has anything to actually collapse in practice -- the real
+//! protection this adds is the store-wide cap on how many distinct
+//! regions' notifications get tracked at once, so a store hosting a very
+//! large number of TiFlash learner regions doesn't grow this table
+//! unboundedly. Notifications for a region beyond the cap are dropped
+//! (counted, not queued) rather than evicting an already-tracked region's
+//! pending notification.
+//!
+//! Epoch updates additionally carry `epoch_bump_count` (see
+//! `CachedPdRegionMeta::epoch_bump_count`), so coalescing two epoch
+//! updates into one doesn't also hide the fact that more than one change
+//! actually happened in between.
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+
+use crate::core::{
+    common::*,
+    metrics::{TIFLASH_NOTIFICATION_COALESCED_COUNTER, TIFLASH_NOTIFICATION_DROPPED_COUNTER},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NotificationKind {
+    LeaderChange,
+    EpochUpdate,
+    FlushRequest,
+}
+
+impl NotificationKind {
+    fn as_metric_label(self) -> &'static str {
+        match self {
+            NotificationKind::LeaderChange => "leader_change",
+            NotificationKind::EpochUpdate => "epoch_update",
+            NotificationKind::FlushRequest => "flush_request",
+        }
+    }
+}
+
+/// One coalesced notification pending delivery for a region. `epoch_version`
+/// and `epoch_bump_count` are only meaningful for
+/// [`NotificationKind::EpochUpdate`]; other kinds leave both `0` and always
+/// let the newer arrival win instead.
+#[derive(Clone, Copy, Debug)]
+struct PendingNotification {
+    epoch_version: u64,
+    /// Mirrors `CachedPdRegionMeta::epoch_bump_count` at the time this
+    /// update was offered, so a consumer that only sees the latest
+    /// coalesced update can still tell it skipped over intermediate epoch
+    /// changes instead of mistaking a gap for continuity.
+    epoch_bump_count: u64,
+}
+
+#[derive(Default)]
+struct RegionSlots {
+    by_kind: HashMap<NotificationKind, PendingNotification>,
+}
+
+/// See the module doc comment.
+pub struct NotificationInbox {
+    regions: Mutex<HashMap<u64, RegionSlots>>,
+    /// Max distinct regions tracked at once. `0` disables the cap.
+    capacity: usize,
+}
+
+impl NotificationInbox {
+    pub fn new(capacity: usize) -> Self {
+        NotificationInbox {
+            regions: Mutex::new(HashMap::default()),
+            capacity,
+        }
+    }
+
+    /// Enqueues `kind` for `region_id`, coalescing with any notification of
+    /// the same kind already pending. Returns `false` (and drops the
+    /// notification, without touching `region_id`'s existing entries) if
+    /// `region_id` is not yet tracked and the store-wide region cap is
+    /// already reached.
+    pub fn offer(&self, region_id: u64, kind: NotificationKind, epoch_version: u64) -> bool {
+        self.offer_inner(region_id, kind, epoch_version, 0)
+    }
+
+    /// Like [`offer`](Self::offer), specialized for
+    /// [`NotificationKind::EpochUpdate`] so `epoch_bump_count` travels
+    /// alongside the epoch version it was observed at.
+    pub fn offer_epoch_update(&self, region_id: u64, epoch_version: u64, epoch_bump_count: u64) -> bool {
+        self.offer_inner(
+            region_id,
+            NotificationKind::EpochUpdate,
+            epoch_version,
+            epoch_bump_count,
+        )
+    }
+
+    fn offer_inner(
+        &self,
+        region_id: u64,
+        kind: NotificationKind,
+        epoch_version: u64,
+        epoch_bump_count: u64,
+    ) -> bool {
+        let mut regions = self.regions.lock().unwrap();
+        if !regions.contains_key(&region_id) {
+            if self.capacity != 0 && regions.len() >= self.capacity {
+                TIFLASH_NOTIFICATION_DROPPED_COUNTER.inc();
+                return false;
+            }
+            regions.insert(region_id, RegionSlots::default());
+        }
+        let slots = regions.get_mut(&region_id).unwrap();
+        let pending = PendingNotification { epoch_version, epoch_bump_count };
+        let coalesced = match slots.by_kind.get(&kind) {
+            Some(existing) if kind == NotificationKind::EpochUpdate => {
+                epoch_version <= existing.epoch_version
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if coalesced {
+            TIFLASH_NOTIFICATION_COALESCED_COUNTER
+                .with_label_values(&[kind.as_metric_label()])
+                .inc();
+            // For non-epoch kinds the fresher arrival still wins over the
+            // stale pending one; only a stale/duplicate epoch update is
+            // actually discarded outright.
+            if kind != NotificationKind::EpochUpdate {
+                slots.by_kind.insert(kind, pending);
+            }
+            return true;
+        }
+        slots.by_kind.insert(kind, pending);
+        true
+    }
+
+    /// Drains and removes every pending notification for `region_id`,
+    /// returning each kind still pending (at most one entry per kind)
+    /// paired with its `epoch_bump_count` -- meaningful only for
+    /// [`NotificationKind::EpochUpdate`], `0` for the others.
+    pub fn drain(&self, region_id: u64) -> Vec<(NotificationKind, u64)> {
+        self.regions
+            .lock()
+            .unwrap()
+            .remove(&region_id)
+            .map(|slots| {
+                slots
+                    .by_kind
+                    .into_iter()
+                    .map(|(kind, pending)| (kind, pending.epoch_bump_count))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Number of distinct regions currently tracked, for
+    /// `/debug/notification_inbox`.
+    pub fn tracked_regions(&self) -> usize {
+        self.regions.lock().unwrap().len()
+    }
+}
+
+impl Default for NotificationInbox {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+lazy_static! {
+    static ref GLOBAL_INBOX: RwLock<Option<Arc<NotificationInbox>>> = RwLock::new(None);
+}
+
+/// Set by `ProxyForwarder::new` so the debug service (which has no
+/// forwarder handle of its own) can report `tracked_regions()`.
+pub fn register_global_notification_inbox(inbox: Arc<NotificationInbox>) {
+    *GLOBAL_INBOX.write().unwrap() = Some(inbox);
+}
+
+pub fn global_tracked_regions() -> Option<usize> {
+    GLOBAL_INBOX
+        .read()
+        .unwrap()
+        .as_ref()
+        .map(|inbox| inbox.tracked_regions())
+}
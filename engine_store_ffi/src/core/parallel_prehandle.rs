@@ -0,0 +1,123 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//! Splits a single snapshot's SST files -- already disjoint key sub-ranges
+//! whenever raftstore's own `MULTI_FILES_SNAPSHOT_FEATURE` produced more
+//! than one file per CF -- across a bounded pool of worker threads for the
+//! proxy-local prep work that precedes the single, monolithic
+//! `pre_handle_snapshot` FFI call: checksumming each artifact (see
+//! `core::snapshot_checksum`) and measuring its size. Cuts proxy-side
+//! pre-handle wall time for 10GB+ regions with multiple SST files per CF; a
+//! single-file snapshot, the common case, has nothing to split and runs the
+//! same sequential work as before this module existed.
+//!
+//! This only parallelizes work this crate actually does. The SST files
+//! themselves are generated upstream by raftstore's own snapshot-receive
+//! path, not here, and the hand-off of the finished list to the engine
+//! store is still exactly one `pre_handle_snapshot` call per region --
+//! `EngineStoreServerHelper` has no per-shard ingest entry point, same
+//! class of ABI limitation `core::chunked_snapshot` documents for the
+//! apply side. What this buys is strictly proxy-side: the per-file results
+//! are stitched back together in their original order before that one
+//! call, not split across several calls.
+//!
+//! The checksums themselves stay diagnostic-only, as they were before this
+//! module existed: `pre_handle_snapshot` has no parameter to pass one
+//! through for the engine store to cross-check against its own read of the
+//! file (same class of gap as `region_approximate_stat`), so this only
+//! gives an operator a value to compare by hand when something looks wrong
+//! after the fact, same as `core::snapshot_checksum`'s original sequential
+//! version did.
+use std::path::{Path, PathBuf};
+
+use engine_tiflash::ChecksumAlgorithm;
+
+use crate::core::{
+    common::*,
+    snapshot_checksum::{checksum_file, Checksum},
+    ProxyForwarder,
+};
+
+struct PreparedSst {
+    path: PathBuf,
+    cf: ColumnFamilyType,
+    size: u64,
+    checksum: Option<Checksum>,
+}
+
+fn prepare_one(path: &Path, cf: ColumnFamilyType, algorithm: ChecksumAlgorithm) -> PreparedSst {
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let checksum = match checksum_file(algorithm, path) {
+        Ok(checksum) => Some(checksum),
+        Err(e) => {
+            warn!("failed to checksum snapshot artifact before pre-handle";
+                "path" => ?path, "err" => %e);
+            None
+        }
+    };
+    PreparedSst { path: path.to_path_buf(), cf, size, checksum }
+}
+
+/// Splits `ssts` into `workers` contiguous, roughly-equal shards -- order
+/// preserved within and across shards -- and prepares each file on a
+/// dedicated OS thread. `workers <= 1`, or a snapshot with too few files to
+/// split among them, runs sequentially on the calling thread instead: no
+/// pool worth spinning up for one or a handful of files.
+fn prepare(
+    ssts: &[(PathBuf, ColumnFamilyType)],
+    algorithm: ChecksumAlgorithm,
+    workers: usize,
+) -> Vec<PreparedSst> {
+    let workers = workers.min(ssts.len());
+    if workers <= 1 {
+        return ssts
+            .iter()
+            .map(|(path, cf)| prepare_one(path, *cf, algorithm))
+            .collect();
+    }
+    let chunk_size = (ssts.len() + workers - 1) / workers;
+    let mut shards = Vec::with_capacity(workers);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = ssts
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|(path, cf)| prepare_one(path, *cf, algorithm))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        for handle in handles {
+            shards.push(handle.join().unwrap());
+        }
+    });
+    shards.into_iter().flatten().collect()
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Checksums and sizes every SST about to be handed to the engine
+    /// store, in parallel when `engine_store_cfg.snapshot_prehandle_parallel_workers`
+    /// says to, logging each checksum the same way
+    /// `core::snapshot_checksum::log_snapshot_artifact_checksums` used to.
+    /// Returns the summed size, for the `consume_ffi_io_resource` call that
+    /// follows in `pre_apply_snapshot`.
+    pub(crate) fn prepare_snapshot_artifacts(&self, ssts: &[(PathBuf, ColumnFamilyType)]) -> u64 {
+        let algorithm = self.snapshot_checksum_algorithm();
+        let workers = self
+            .packed_envs
+            .engine_store_cfg
+            .snapshot_prehandle_parallel_workers;
+        let mut total = 0u64;
+        for prepared in prepare(ssts, algorithm, workers) {
+            total += prepared.size;
+            if let Some(checksum) = prepared.checksum {
+                debug!("pre-handle snapshot artifact checksum";
+                    "path" => ?prepared.path, "cf" => ?prepared.cf,
+                    "algorithm" => ?checksum.algorithm,
+                    "digest" => hex::encode(&checksum.digest),
+                );
+            }
+        }
+        total
+    }
+}
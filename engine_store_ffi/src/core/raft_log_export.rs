@@ -0,0 +1,64 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::sync::{Arc, Mutex};
+
+use engine_traits::RaftEngineReadOnly;
+use lazy_static::lazy_static;
+use raft::eraftpb::Entry;
+
+lazy_static! {
+    // A process-wide handle rather than a `ProxyForwarder` field so the
+    // status-service route can read raft log entries without threading the
+    // raft engine's concrete type through `StatusServer`'s generics (mirrors
+    // `learner_health`/`maintenance`'s use of a global for the same reason).
+    static ref RAFT_ENGINE_FOR_EXPORT: Mutex<Option<Arc<dyn RaftEngineReadOnly>>> = Mutex::new(None);
+}
+
+/// Registers the store's raft engine, called once from `ProxyForwarder::new`.
+pub fn register_raft_engine_for_export(engine: Arc<dyn RaftEngineReadOnly>) {
+    *RAFT_ENGINE_FOR_EXPORT.lock().unwrap() = Some(engine);
+}
+
+/// One raft log entry in the portable export format used by
+/// `/debug/raft_log/<region_id>` and the `raft_log_diff` decode/diff tool:
+/// plain JSON rather than the raft crate's own protobuf encoding, so a
+/// mismatched raft/protobuf crate version on the reading side can't corrupt
+/// the export.
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExportedRaftEntry {
+    pub index: u64,
+    pub term: u64,
+    pub entry_type: i32,
+    pub data: Vec<u8>,
+}
+
+impl From<&Entry> for ExportedRaftEntry {
+    fn from(e: &Entry) -> Self {
+        ExportedRaftEntry {
+            index: e.get_index(),
+            term: e.get_term(),
+            entry_type: e.get_entry_type() as i32,
+            data: e.get_data().to_vec(),
+        }
+    }
+}
+
+/// Exports raw raft log entries for `region_id` in `[low, high)`, in index
+/// order, for offline diffing against engine-store apply records (e.g.
+/// investigating "missing rows in TiFlash" reports).
+pub fn export_raft_log_range(
+    region_id: u64,
+    low: u64,
+    high: u64,
+) -> Result<Vec<ExportedRaftEntry>, String> {
+    let engine = RAFT_ENGINE_FOR_EXPORT
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "raft engine not registered".to_string())?;
+    let mut buf = Vec::new();
+    engine
+        .fetch_entries_to(region_id, low, high, None, &mut buf)
+        .map_err(|e| e.to_string())?;
+    Ok(buf.iter().map(ExportedRaftEntry::from).collect())
+}
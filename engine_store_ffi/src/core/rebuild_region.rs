@@ -0,0 +1,110 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//! Bookkeeping for `PUT /debug/rebuild_region/<region_id>`: an operator
+//! marks a region's local engine-store copy suspect and this module tracks
+//! it through to a fresh snapshot successfully applying, replacing "watch
+//! logs / poll pd-ctl by hand" with a single `GET
+//! /debug/rebuild_region/<region_id>` poll.
+//!
+//! What this deliberately does *not* do is tombstone the local peer or ask
+//! PD to schedule a replacement. Destroying or re-adding a peer is a
+//! raftstore-side conf-change decision the forwarder does not own -- see
+//! `core::learner_health::check_learner_health`'s doc comment for the same
+//! boundary -- and this crate has no PD write-path client at all, only
+//! `check_learner_health`'s read-only `PdClient::get_region_by_id` calls
+//! feeding into `proxy_server`. So the operator still issues the usual
+//! `pd-ctl operator add remove-peer <region_id> <store_id>`; PD schedules
+//! the destroy and a fresh learner exactly as it always would, and this
+//! module observes the same `on_region_changed`/apply-snapshot callbacks
+//! every other rebuild goes through to report on it. `request_rebuild`
+//! itself triggers nothing -- it only starts a tracked expectation that the
+//! callbacks below can advance.
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use serde_derive::Serialize;
+
+use crate::core::common::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RebuildPhase {
+    /// `request_rebuild` was called; still waiting to observe the local
+    /// peer being torn down.
+    Requested,
+    /// This store's copy of the region was destroyed locally (see
+    /// `on_region_changed`'s `Destroy` branch); waiting for a replacement
+    /// snapshot to arrive and apply.
+    PeerRemoved,
+    /// A snapshot applied successfully for this region id since
+    /// `PeerRemoved`, i.e. the rebuild appears complete.
+    Done,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RebuildStatus {
+    pub phase: RebuildPhase,
+    pub requested_at_unix_secs: u64,
+}
+
+lazy_static! {
+    // A process-wide slot rather than a `ProxyForwarder` field: like
+    // `core::snapshot_apply_history`'s `HISTORY`, the status server reads
+    // this without holding a forwarder handle, and the callbacks that
+    // advance it live on `ProxyForwarder` itself.
+    static ref REBUILDS: Mutex<HashMap<u64, RebuildStatus>> = Mutex::new(HashMap::default());
+}
+
+/// Marks `region_id` for rebuild tracking. Returns `false` without
+/// resetting anything if a rebuild for it is already tracked.
+pub fn request_rebuild(region_id: u64) -> bool {
+    let mut rebuilds = REBUILDS.lock().unwrap();
+    if rebuilds.contains_key(&region_id) {
+        return false;
+    }
+    let requested_at_unix_secs = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    rebuilds.insert(
+        region_id,
+        RebuildStatus {
+            phase: RebuildPhase::Requested,
+            requested_at_unix_secs,
+        },
+    );
+    true
+}
+
+/// Stops tracking `region_id`, regardless of phase. Returns whether it was
+/// being tracked.
+pub fn cancel_rebuild(region_id: u64) -> bool {
+    REBUILDS.lock().unwrap().remove(&region_id).is_some()
+}
+
+pub fn rebuild_status(region_id: u64) -> Option<RebuildStatus> {
+    REBUILDS.lock().unwrap().get(&region_id).cloned()
+}
+
+/// Called from `on_region_changed`'s `Destroy` branch for every region, not
+/// just ones under rebuild -- a no-op unless `region_id` has a tracked
+/// rebuild still in `Requested`.
+pub fn note_region_destroyed(region_id: u64) {
+    let mut rebuilds = REBUILDS.lock().unwrap();
+    if let Some(status) = rebuilds.get_mut(&region_id) {
+        if status.phase == RebuildPhase::Requested {
+            status.phase = RebuildPhase::PeerRemoved;
+        }
+    }
+}
+
+/// Called alongside a successful `snapshot_apply_history::finish_snapshot_apply`
+/// -- a no-op unless `region_id` has a tracked rebuild waiting on
+/// `PeerRemoved`.
+pub fn note_snapshot_applied(region_id: u64) {
+    let mut rebuilds = REBUILDS.lock().unwrap();
+    if let Some(status) = rebuilds.get_mut(&region_id) {
+        if status.phase == RebuildPhase::PeerRemoved {
+            status.phase = RebuildPhase::Done;
+        }
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//! A two-way orphan detector for engine-store space accounting: the proxy's
+//! own view of which regions are live (id, epoch, key range, sourced from
+//! `cached_region_meta`, already kept current by every `on_region_changed`
+//! call) paired with the engine store's view of which shards it still
+//! holds, so either side can notice it is retaining data for a region the
+//! other side has already forgotten about.
+//!
+//! Only the proxy-side half is real today. `live_regions` is a plain
+//! snapshot of state this crate already tracks for other purposes, so it
+//! costs nothing to expose. The other two directions this request asks for
+//! -- an FFI pull API so the engine store can read that snapshot itself,
+//! and a reverse-FFI call so the proxy can ask the engine store for its own
+//! shard list -- both need new slots on an ABI this crate does not own:
+//! `EngineStoreServerHelper` has no "list your shards" callback, and
+//! `WriteCmdsView`-style pull APIs are declared on the C++ side and
+//! regenerated into `interfaces.rs` via the `gen-proxy-ffi` toolchain
+//! against a TiFlash header this request doesn't ship. `request_engine_shard_list`
+//! documents that gap and returns `None` until it's closed;
+//! `diff_against_engine_shards` is written and ready to go the moment it is.
+use lazy_static::lazy_static;
+use serde_derive::Serialize;
+
+use crate::core::{common::*, CachedPdRegionMeta, ProxyForwarder};
+
+/// A region the proxy currently considers live, trimmed to what a space
+/// accounting diff needs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LiveRegionSummary {
+    pub id: u64,
+    pub conf_ver: u64,
+    pub version: u64,
+    pub start_key: Vec<u8>,
+    pub end_key: Vec<u8>,
+}
+
+/// One shard as reported by the engine store's own side of the orphan
+/// check; shape mirrors [`LiveRegionSummary`] so the two are directly
+/// comparable once `request_engine_shard_list` can actually produce one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EngineShardSummary {
+    pub region_id: u64,
+    pub version: u64,
+}
+
+/// The result of comparing the proxy's live regions against the engine
+/// store's reported shards.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GarbageDiff {
+    /// Region ids the proxy considers live but the engine store did not
+    /// report a shard for -- data the engine store may be missing.
+    pub missing_on_engine_store: Vec<u64>,
+    /// Shard region ids the engine store reported that the proxy does not
+    /// consider live -- orphaned engine-store data a GC pass could reclaim.
+    pub orphaned_on_engine_store: Vec<u64>,
+}
+
+fn summarize(meta_map: &HashMap<u64, CachedPdRegionMeta>) -> Vec<LiveRegionSummary> {
+    meta_map
+        .iter()
+        .map(|(id, meta)| LiveRegionSummary {
+            id: *id,
+            conf_ver: meta.conf_ver,
+            version: meta.version,
+            start_key: meta.start_key.clone(),
+            end_key: meta.end_key.clone(),
+        })
+        .collect()
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Every region this store's `cached_region_meta` currently has an entry
+    /// for, i.e. every region `on_region_changed` has seen since and not
+    /// since observed `Destroy` for.
+    pub fn live_regions(&self) -> Vec<LiveRegionSummary> {
+        summarize(&self.cached_region_meta.read().unwrap())
+    }
+
+    /// Asks the engine store for its own list of held shards, for the other
+    /// half of the orphan check. Always `None` today -- see this module's
+    /// doc comment for why.
+    pub fn request_engine_shard_list(&self) -> Option<Vec<EngineShardSummary>> {
+        None
+    }
+}
+
+lazy_static! {
+    static ref GLOBAL_CACHED_REGION_META: RwLock<Option<Arc<RwLock<HashMap<u64, CachedPdRegionMeta>>>>> =
+        RwLock::new(None);
+}
+
+/// Set by `ProxyForwarder::new` so the debug service (which has no
+/// forwarder handle of its own) can report `global_live_regions()`.
+pub fn register_global_cached_region_meta(meta: Arc<RwLock<HashMap<u64, CachedPdRegionMeta>>>) {
+    *GLOBAL_CACHED_REGION_META.write().unwrap() = Some(meta);
+}
+
+pub fn global_live_regions() -> Option<Vec<LiveRegionSummary>> {
+    GLOBAL_CACHED_REGION_META
+        .read()
+        .unwrap()
+        .as_ref()
+        .map(|meta| summarize(&meta.read().unwrap()))
+}
+
+/// Diffs a proxy-side live-region snapshot against an engine-store shard
+/// list. Pure and independent of how either list was obtained, so it needs
+/// no changes once `request_engine_shard_list` can produce a real list.
+pub fn diff_against_engine_shards(
+    live_regions: &[LiveRegionSummary],
+    engine_shards: &[EngineShardSummary],
+) -> GarbageDiff {
+    let shard_ids: HashMap<u64, u64> = engine_shards
+        .iter()
+        .map(|s| (s.region_id, s.version))
+        .collect();
+    let live_ids: std::collections::HashSet<u64> = live_regions.iter().map(|r| r.id).collect();
+
+    let missing_on_engine_store = live_regions
+        .iter()
+        .filter(|r| !shard_ids.contains_key(&r.id))
+        .map(|r| r.id)
+        .collect();
+    let orphaned_on_engine_store = engine_shards
+        .iter()
+        .filter(|s| !live_ids.contains(&s.region_id))
+        .map(|s| s.region_id)
+        .collect();
+
+    GarbageDiff {
+        missing_on_engine_store,
+        orphaned_on_engine_store,
+    }
+}
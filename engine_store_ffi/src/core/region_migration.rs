@@ -0,0 +1,116 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+use lazy_static::lazy_static;
+
+use crate::core::{common::*, ProxyForwarder};
+
+/// Everything `export_region` pulls out of this proxy's own local state for
+/// one region. Deliberately plain fields rather than the raw
+/// `RegionLocalState`/`RaftApplyState` protobufs -- like
+/// `core::region_state_audit::RegionStateMismatch`, this only needs to
+/// round-trip through JSON, and the protobuf types have no `Serialize` impl
+/// to spare adding one just for a debug tool.
+///
+/// Notably absent: any engine-store checkpoint. `EngineStoreServerHelper`
+/// has no FFI call to pull a portable checkpoint for an arbitrary key range
+/// out of the engine store, so this bundle covers only what the proxy itself
+/// persists -- the region's raft-level metadata. Actually moving a replica's
+/// columnar data between clusters still needs the normal
+/// AddLearner-plus-snapshot path; this is meant to seed the destination
+/// cluster's PD and proxy with correct metadata ahead of that, not to
+/// replace it.
+#[derive(Clone, Debug, serde_derive::Serialize, serde_derive::Deserialize)]
+pub struct RegionExportBundle {
+    pub region_id: u64,
+    pub start_key: Vec<u8>,
+    pub end_key: Vec<u8>,
+    pub conf_ver: u64,
+    pub version: u64,
+    pub peer_ids: Vec<u64>,
+    pub applied_index: u64,
+    pub truncated_index: u64,
+    pub truncated_term: u64,
+}
+
+/// Why `import_region` refused a bundle.
+#[derive(Clone, Debug, PartialEq, Eq, serde_derive::Serialize)]
+pub enum RegionImportError {
+    /// A region with this id already has local state on the destination
+    /// store; importing would stomp on raftstore's own bootstrap/conf-change
+    /// path for it instead of seeding an unclaimed id.
+    RegionAlreadyExists,
+}
+
+lazy_static! {
+    // Set by `ProxyForwarder::new`, so the debug service (which has no
+    // forwarder handle of its own, same as `core::region_size_amplification`)
+    // can export/import region metadata.
+    static ref EXPORT_HANDLER: Mutex<Option<Box<dyn Fn(u64) -> Option<RegionExportBundle> + Send + Sync>>> =
+        Mutex::new(None);
+    static ref IMPORT_HANDLER: Mutex<
+        Option<Box<dyn Fn(&RegionExportBundle) -> Result<(), RegionImportError> + Send + Sync>>,
+    > = Mutex::new(None);
+}
+
+pub fn register_global_region_migration_handlers(
+    export: impl Fn(u64) -> Option<RegionExportBundle> + Send + Sync + 'static,
+    import: impl Fn(&RegionExportBundle) -> Result<(), RegionImportError> + Send + Sync + 'static,
+) {
+    *EXPORT_HANDLER.lock().unwrap() = Some(Box::new(export));
+    *IMPORT_HANDLER.lock().unwrap() = Some(Box::new(import));
+}
+
+pub fn export_region(region_id: u64) -> Option<RegionExportBundle> {
+    EXPORT_HANDLER.lock().unwrap().as_ref().and_then(|f| f(region_id))
+}
+
+pub fn import_region(bundle: &RegionExportBundle) -> Option<Result<(), RegionImportError>> {
+    IMPORT_HANDLER.lock().unwrap().as_ref().map(|f| f(bundle))
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Exports `region_id`'s raft-level metadata as currently persisted on
+    /// this store, for `import_region` on a proxy in a different cluster.
+    /// `None` if this store holds no local state for the region.
+    pub fn export_region(&self, region_id: u64) -> Option<RegionExportBundle> {
+        let region_state = self.raft_engine.get_region_state(region_id, u64::MAX).ok()??;
+        let apply_state = self
+            .raft_engine
+            .get_apply_state(region_id, u64::MAX)
+            .ok()??;
+        let region = region_state.get_region();
+        let epoch = region.get_region_epoch();
+        Some(RegionExportBundle {
+            region_id,
+            start_key: region.get_start_key().to_vec(),
+            end_key: region.get_end_key().to_vec(),
+            conf_ver: epoch.get_conf_ver(),
+            version: epoch.get_version(),
+            peer_ids: region.get_peers().iter().map(|p| p.get_id()).collect(),
+            applied_index: apply_state.get_applied_index(),
+            truncated_index: apply_state.get_truncated_state().get_index(),
+            truncated_term: apply_state.get_truncated_state().get_term(),
+        })
+    }
+
+    /// Validates `bundle` is safe to land on this store: today that means
+    /// only that `bundle.region_id` is not already claimed locally. Epoch
+    /// rewriting and PD re-registration -- both of which need a `PdClient`
+    /// handle this forwarder doesn't have -- are left to the caller, which
+    /// is expected to re-register the region with the destination cluster's
+    /// PD (getting back a fresh epoch) before driving the normal
+    /// AddLearner-plus-snapshot path to actually populate it; this only
+    /// guards against colliding with an id already in use.
+    pub fn import_region(&self, bundle: &RegionExportBundle) -> Result<(), RegionImportError> {
+        if self
+            .raft_engine
+            .get_region_state(bundle.region_id, u64::MAX)
+            .ok()
+            .flatten()
+            .is_some()
+        {
+            return Err(RegionImportError::RegionAlreadyExists);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use lazy_static::lazy_static;
+
+use crate::core::{
+    common::*, metrics::TIFLASH_REGION_SIZE_AMPLIFICATION_RATIO, ProxyForwarder,
+};
+
+/// Bytes forwarded to the engine store for a region versus what it reports
+/// retaining, so an operator can spot a table whose columnar representation
+/// is unexpectedly larger than the row-format data that produced it.
+///
+/// `engine_store_bytes`/`ratio` are only ever populated once
+/// `ProxyForwarder::region_approximate_stat` returns `Some` -- today it
+/// always returns `None`, since the FFI query it needs does not exist (see
+/// that function's doc comment). `forwarded_bytes` is real: it is this
+/// proxy's own running total of key+value bytes it has handed to
+/// `handle_write_raft_cmd` for the region, via `record_forwarded_bytes`.
+#[derive(Debug, Clone, Copy, PartialEq, serde_derive::Serialize)]
+pub struct RegionSizeAmplification {
+    pub forwarded_bytes: u64,
+    pub engine_store_bytes: Option<u64>,
+    pub ratio: Option<f64>,
+}
+
+lazy_static! {
+    // Set by `ProxyForwarder::new`, so the debug service (which has no
+    // forwarder handle of its own, same as `core::freeze`) can query
+    // amplification for a region.
+    static ref HANDLER: Mutex<Option<Box<dyn Fn(u64) -> RegionSizeAmplification + Send + Sync>>> =
+        Mutex::new(None);
+}
+
+pub fn register_global_region_size_amplification_handler(
+    f: impl Fn(u64) -> RegionSizeAmplification + Send + Sync + 'static,
+) {
+    *HANDLER.lock().unwrap() = Some(Box::new(f));
+}
+
+pub fn region_size_amplification(region_id: u64) -> Option<RegionSizeAmplification> {
+    HANDLER.lock().unwrap().as_ref().map(|f| f(region_id))
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Adds `bytes` to `region_id`'s running total of bytes forwarded over
+    /// the FFI. Called once per plain write batch handed to
+    /// `handle_write_raft_cmd`.
+    pub fn record_forwarded_bytes(&self, region_id: u64, bytes: u64) {
+        if bytes == 0 {
+            return;
+        }
+        *self
+            .forwarded_bytes
+            .write()
+            .unwrap()
+            .entry(region_id)
+            .or_insert(0) += bytes;
+    }
+
+    /// Reports `region_id`'s amplification, observing the ratio into
+    /// `TIFLASH_REGION_SIZE_AMPLIFICATION_RATIO` whenever both sides are
+    /// known.
+    pub fn region_size_amplification(&self, region_id: u64) -> RegionSizeAmplification {
+        let forwarded_bytes = self
+            .forwarded_bytes
+            .read()
+            .unwrap()
+            .get(&region_id)
+            .copied()
+            .unwrap_or(0);
+        let engine_store_bytes = self
+            .region_approximate_stat(region_id)
+            .map(|s| s.approximate_size);
+        let ratio = match (engine_store_bytes, forwarded_bytes) {
+            (Some(engine_store_bytes), forwarded_bytes) if forwarded_bytes > 0 => {
+                let ratio = engine_store_bytes as f64 / forwarded_bytes as f64;
+                TIFLASH_REGION_SIZE_AMPLIFICATION_RATIO.observe(ratio);
+                Some(ratio)
+            }
+            _ => None,
+        };
+        RegionSizeAmplification {
+            forwarded_bytes,
+            engine_store_bytes,
+            ratio,
+        }
+    }
+}
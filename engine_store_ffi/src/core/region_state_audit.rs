@@ -0,0 +1,161 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+use pd_client::PdClient;
+
+use crate::core::{common::*, metrics::TIFLASH_REGION_STATE_MISMATCH_COUNTER, ProxyForwarder};
+
+lazy_static! {
+    // Round-robins through the region id space across calls so a store with
+    // more regions than fit in one sample still eventually covers all of
+    // them, instead of a fixed-order scan always favoring the same prefix.
+    static ref AUDIT_CURSOR: Mutex<u64> = Mutex::new(0);
+    // Mirrors the most recent call to `audit_region_state`, so the debug
+    // service can report it without needing a handle to the forwarder.
+    static ref LAST_MISMATCHES: Mutex<Vec<RegionStateMismatch>> = Mutex::new(Vec::new());
+}
+
+/// A region whose locally persisted `RegionLocalState` (epoch, peer list)
+/// disagrees with PD's current view of the region.
+#[derive(Clone, Debug, serde_derive::Serialize)]
+pub struct RegionStateMismatch {
+    pub region_id: u64,
+    pub local_conf_ver: u64,
+    pub pd_conf_ver: u64,
+    pub local_version: u64,
+    pub pd_version: u64,
+    pub local_peers: Vec<u64>,
+    pub pd_peers: Vec<u64>,
+}
+
+/// Snapshot of findings from the last `audit_region_state` run, for
+/// `GET /debug/region_state_mismatches`.
+pub fn snapshot_region_state_mismatches() -> Vec<RegionStateMismatch> {
+    LAST_MISMATCHES.lock().unwrap().clone()
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Samples up to `region_state_audit_sample_size` regions per call
+    /// (round-robin, see `AUDIT_CURSOR`) from the region ids we have cached
+    /// PD metadata for, compares each one's locally persisted
+    /// `RegionLocalState` -- via `raft_engine.get_region_state`, the actual
+    /// on-disk state, unlike `check_learner_health`'s PD-pushed cache --
+    /// against PD's live view, and logs plus counts
+    /// (`TIFLASH_REGION_STATE_MISMATCH_COUNTER`) any epoch or peer-list
+    /// divergence. Meant to be called periodically from a background tick,
+    /// the same way `check_learner_health` is.
+    ///
+    /// `enable_region_state_auto_correct` only controls whether a
+    /// divergence also gets queued in `pending_region_state_repairs` for a
+    /// consumer to act on; this proxy has no safe way to rewrite
+    /// `RegionLocalState` directly -- reconciling it is a raft conf-change
+    /// decision, not something a side-channel FFI component should do
+    /// unilaterally -- so "auto-correct" here means "flagged for repair",
+    /// not "repaired".
+    pub fn audit_region_state<PD: PdClient>(&self, pd_client: &PD) -> Vec<RegionStateMismatch> {
+        let sample_size = self
+            .packed_envs
+            .engine_store_cfg
+            .region_state_audit_sample_size;
+        if sample_size == 0 {
+            return vec![];
+        }
+
+        let mut region_ids: Vec<u64> = self
+            .cached_region_meta
+            .read()
+            .unwrap()
+            .keys()
+            .copied()
+            .collect();
+        if region_ids.is_empty() {
+            return vec![];
+        }
+        region_ids.sort_unstable();
+
+        let sample: Vec<u64> = {
+            let mut cursor = AUDIT_CURSOR.lock().unwrap();
+            let start = region_ids.partition_point(|id| *id <= *cursor);
+            let sample: Vec<u64> = region_ids
+                .iter()
+                .cycle()
+                .skip(start)
+                .take(sample_size.min(region_ids.len()))
+                .copied()
+                .collect();
+            *cursor = *sample.last().unwrap();
+            sample
+        };
+
+        let mut mismatches = vec![];
+        for region_id in sample {
+            let local = match self.raft_engine.get_region_state(region_id, u64::MAX) {
+                Ok(Some(s)) => s,
+                _ => continue,
+            };
+            let pd_region = match futures::executor::block_on(pd_client.get_region_by_id(region_id))
+            {
+                Ok(Some(r)) => r,
+                _ => continue,
+            };
+            let local_region = local.get_region();
+            let local_epoch = local_region.get_region_epoch();
+            let pd_epoch = pd_region.get_region_epoch();
+            if pd_epoch.get_version() < local_epoch.get_version()
+                || pd_epoch.get_conf_ver() < local_epoch.get_conf_ver()
+            {
+                // PD's view is older than what we have persisted, e.g. a
+                // stale read landed on a follower; skip until it catches up,
+                // same as `check_learner_health`.
+                continue;
+            }
+            let local_peers: HashSet<u64> =
+                local_region.get_peers().iter().map(|p| p.get_id()).collect();
+            let pd_peers: HashSet<u64> = pd_region.get_peers().iter().map(|p| p.get_id()).collect();
+            if local_epoch.get_conf_ver() == pd_epoch.get_conf_ver()
+                && local_epoch.get_version() == pd_epoch.get_version()
+                && local_peers == pd_peers
+            {
+                continue;
+            }
+            warn!("region local state diverges from PD's view";
+                "region_id" => region_id,
+                "local_conf_ver" => local_epoch.get_conf_ver(),
+                "pd_conf_ver" => pd_epoch.get_conf_ver(),
+                "local_version" => local_epoch.get_version(),
+                "pd_version" => pd_epoch.get_version(),
+            );
+            TIFLASH_REGION_STATE_MISMATCH_COUNTER.inc();
+            let finding = RegionStateMismatch {
+                region_id,
+                local_conf_ver: local_epoch.get_conf_ver(),
+                pd_conf_ver: pd_epoch.get_conf_ver(),
+                local_version: local_epoch.get_version(),
+                pd_version: pd_epoch.get_version(),
+                local_peers: local_peers.into_iter().collect(),
+                pd_peers: pd_peers.into_iter().collect(),
+            };
+            if self
+                .packed_envs
+                .engine_store_cfg
+                .enable_region_state_auto_correct
+            {
+                self.pending_region_state_repairs
+                    .lock()
+                    .unwrap()
+                    .push(finding.clone());
+            }
+            mismatches.push(finding);
+        }
+        *LAST_MISMATCHES.lock().unwrap() = mismatches.clone();
+        mismatches
+    }
+
+    /// Drains findings queued by `audit_region_state` for a consumer to act
+    /// on, e.g. by re-driving a conf-change through the normal raftstore
+    /// path. Empty unless `enable_region_state_auto_correct` is set.
+    pub fn drain_region_state_repairs(&self) -> Vec<RegionStateMismatch> {
+        std::mem::take(&mut *self.pending_region_state_repairs.lock().unwrap())
+    }
+}
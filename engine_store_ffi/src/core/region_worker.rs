@@ -0,0 +1,164 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use std::time::{Duration, Instant};
+
+use crate::core::{common::*, ProxyForwarder};
+
+/// Throttles how often split-check and consistency-check actually do their
+/// expensive work per region, on top of raftstore's own tick-driven
+/// scheduling.
+///
+/// TiKV's split-check and consistency-check defaults are tuned for a row
+/// store, where the proxy's own local RocksDB is the real data: on a
+/// TiFlash-backed store that engine holds almost none of the region's
+/// actual data (the engine store's columnar segments do), so running them
+/// at the same cadence mostly burns CPU scanning a nearly-empty engine.
+/// This does not change what a check finds -- it only decides, per region,
+/// whether this round's check is worth running at all.
+#[derive(Debug)]
+pub struct RegionWorkerScheduler {
+    min_interval: RwLock<Duration>,
+    last_split_check: Mutex<HashMap<u64, Instant>>,
+    last_consistency_check: Mutex<HashMap<u64, Instant>>,
+}
+
+impl RegionWorkerScheduler {
+    pub fn new(min_interval: Duration) -> Self {
+        RegionWorkerScheduler {
+            min_interval: RwLock::new(min_interval),
+            last_split_check: Mutex::new(HashMap::default()),
+            last_consistency_check: Mutex::new(HashMap::default()),
+        }
+    }
+
+    /// Changes the throttle interval at runtime, e.g. from the
+    /// `/debug/region_worker_interval` status-server endpoint, without a
+    /// restart.
+    pub fn set_min_interval(&self, interval: Duration) {
+        *self.min_interval.write().unwrap() = interval;
+    }
+
+    pub fn min_interval(&self) -> Duration {
+        *self.min_interval.read().unwrap()
+    }
+
+    fn due(tracked: &Mutex<HashMap<u64, Instant>>, region_id: u64, min_interval: Duration) -> bool {
+        let mut tracked = tracked.lock().unwrap();
+        match tracked.get(&region_id) {
+            Some(last) if last.elapsed() < min_interval => false,
+            _ => {
+                tracked.insert(region_id, Instant::now());
+                true
+            }
+        }
+    }
+
+    /// Whether a full, scan-based split-check is due for `region_id`. A
+    /// `false` here doesn't skip split-check outright -- see
+    /// `ProxyForwarder::add_split_checker`, which turns it into an
+    /// approximate (no-scan) round instead.
+    pub fn split_check_due(&self, region_id: u64) -> bool {
+        Self::due(&self.last_split_check, region_id, self.min_interval())
+    }
+
+    pub fn consistency_check_due(&self, region_id: u64) -> bool {
+        Self::due(&self.last_consistency_check, region_id, self.min_interval())
+    }
+}
+
+impl Default for RegionWorkerScheduler {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(600))
+    }
+}
+
+lazy_static::lazy_static! {
+    // Mirrors `ProxyForwarder::region_worker_scheduler` so the debug service
+    // can retune the interval without needing a forwarder handle, matching
+    // `core::feature_gate`'s `GLOBAL_FEATURE_GATE`.
+    static ref GLOBAL_SCHEDULER: RwLock<Option<Arc<RegionWorkerScheduler>>> = RwLock::new(None);
+}
+
+pub fn register_global_region_worker_scheduler(scheduler: Arc<RegionWorkerScheduler>) {
+    *GLOBAL_SCHEDULER.write().unwrap() = Some(scheduler);
+}
+
+/// Retunes the throttle interval at runtime, for `PUT
+/// /debug/region_worker_interval`. No-op before the forwarder has been
+/// constructed.
+pub fn set_global_min_interval(interval: Duration) {
+    if let Some(scheduler) = GLOBAL_SCHEDULER.read().unwrap().as_ref() {
+        scheduler.set_min_interval(interval);
+    }
+}
+
+/// Current throttle interval, for `GET /debug/region_worker_interval`.
+/// `None` before the forwarder has been constructed.
+pub fn global_min_interval() -> Option<Duration> {
+    GLOBAL_SCHEDULER
+        .read()
+        .unwrap()
+        .as_ref()
+        .map(|s| s.min_interval())
+}
+
+/// A no-op [`raftstore::coprocessor::SplitChecker`]: it never recommends a
+/// scan-derived split key, but requesting [`CheckPolicy::Approximate`] makes
+/// `SplitCheckerHost::policy` skip the scan-based checkers added by other
+/// observers for this round (`policy()` returns `Approximate` as soon as any
+/// checker asks for it), which is the actual CPU saved.
+struct ThrottledSplitChecker;
+
+impl<E> raftstore::coprocessor::SplitChecker<E> for ThrottledSplitChecker {
+    fn split_keys(&mut self) -> Vec<Vec<u8>> {
+        vec![]
+    }
+
+    fn policy(&self) -> kvproto::pdpb::CheckPolicy {
+        kvproto::pdpb::CheckPolicy::Approximate
+    }
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    fn region_worker_scheduling_enabled(&self) -> bool {
+        self.packed_envs
+            .engine_store_cfg
+            .enable_dynamic_region_worker_scheduling
+    }
+
+    /// Adds a throttling checker for `region_id` when a real, scan-based
+    /// split-check isn't due yet; adds nothing (letting the normally
+    /// registered size/keys/half/table checkers run as usual) once it is.
+    pub fn add_split_checker<E>(
+        &self,
+        region_id: u64,
+        host: &mut raftstore::coprocessor::SplitCheckerHost<'_, E>,
+    ) {
+        if !self.region_worker_scheduling_enabled() {
+            return;
+        }
+        if !self.region_worker_scheduler.split_check_due(region_id) {
+            host.add_checker(Box::new(ThrottledSplitChecker));
+        }
+    }
+
+    /// Claims exclusivity over the region's consistency-check context so
+    /// the default `Raw` observer (which hashes the local RocksDB) doesn't
+    /// also run, then reports no hash: a raw hash of the proxy's own,
+    /// mostly-metadata local engine doesn't validate the engine store's
+    /// actual columnar data, so it is pure overhead for a TiFlash-backed
+    /// store. Computing a real, engine-store-backed checksum instead needs
+    /// a new FFI call (same class of limitation as
+    /// `core::region_stats::region_approximate_stat`), not done here.
+    pub fn consistency_check_update_context(&self) -> bool {
+        self.region_worker_scheduling_enabled()
+    }
+
+    pub fn consistency_check_compute_hash(&self, region_id: u64) -> Option<u32> {
+        if self.region_worker_scheduling_enabled() && self.region_worker_scheduler.consistency_check_due(region_id) {
+            debug!("skipping local consistency hash for engine-store-backed region";
+                "region_id" => region_id,
+            );
+        }
+        None
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+use lazy_static::lazy_static;
+use serde_derive::Serialize;
+
+use crate::core::{common::*, metrics::TIFLASH_REPLAY_DEBT_GAUGE, ProxyForwarder};
+
+/// One region's outstanding "replay debt" after a restart: the gap, in raft
+/// log entries, between what the proxy has persisted in its
+/// `RaftApplyState` and what the engine store has actually caught up to.
+/// Populated by [`ProxyForwarder::record_replay_debt`] and surfaced via
+/// `/debug/replay_debt`, so an operator gets a concrete "TiFlash node is
+/// caught up" signal instead of inferring it from apply throughput.
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+pub struct RegionReplayDebt {
+    pub persisted_index: u64,
+    pub engine_store_index: u64,
+    pub entries_remaining: u64,
+}
+
+lazy_static! {
+    // region_id -> most recently observed debt. A region is removed once its
+    // debt reaches zero, which both bounds this map's size over a node's
+    // lifetime and makes "replay complete" observable as the region's
+    // absence rather than a separate flag to keep in sync.
+    static ref REPLAY_DEBT: RwLock<HashMap<u64, RegionReplayDebt>> =
+        RwLock::new(HashMap::default());
+}
+
+/// Sum of `entries_remaining` across every region still tracked -- the
+/// node-wide startup progress metric. Monotonically decreasing barring a
+/// region falling further behind, and zero once every region the proxy has
+/// seen has caught its engine store up.
+pub fn total_replay_debt() -> u64 {
+    REPLAY_DEBT
+        .read()
+        .unwrap()
+        .values()
+        .map(|d| d.entries_remaining)
+        .sum()
+}
+
+/// Snapshot of every region still carrying replay debt, for
+/// `/debug/replay_debt`.
+pub fn replay_debt_report() -> HashMap<u64, RegionReplayDebt> {
+    REPLAY_DEBT.read().unwrap().clone()
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Refreshes `region_id`'s replay debt using the engine store's own
+    /// reported applied index (`EngineStoreServerHelper::get_flushed_state`),
+    /// so debt is populated from real restarts, not only an operator-issued
+    /// `PUT /debug/rewind_region/<id>`. Called from every
+    /// `pre_region_heartbeat`, gated on `enable_restart_detection` since that
+    /// is what this is for: telling whether a region's engine store has
+    /// actually caught back up after `core::restart_detection` observed one.
+    pub fn refresh_replay_debt(&self, region_id: u64) {
+        if !self.packed_envs.engine_store_cfg.enable_restart_detection {
+            return;
+        }
+        let persisted_index = match self.raft_engine.get_apply_state(region_id, u64::MAX) {
+            Ok(Some(s)) => s.get_applied_index(),
+            Ok(None) => return,
+            Err(e) => {
+                warn!("failed to read apply state for replay debt refresh";
+                    "region_id" => region_id, "err" => ?e);
+                return;
+            }
+        };
+        let flushed = self.engine_store_server_helper.get_flushed_state(region_id);
+        self.record_replay_debt(region_id, persisted_index, flushed.applied_index);
+    }
+
+    /// Records `region_id`'s replay debt given its persisted apply index and
+    /// the engine store's reported index, logging once when the gap closes.
+    /// Driven by two real callers: [`Self::refresh_replay_debt`] (the engine
+    /// store's own reported index, on every heartbeat) and
+    /// `ProxyForwarder::rewind_region`'s `to_index` (an operator-triggered
+    /// rewind target, which is exactly that same index at the moment of the
+    /// rewind).
+    pub fn record_replay_debt(
+        &self,
+        region_id: u64,
+        persisted_index: u64,
+        engine_store_index: u64,
+    ) {
+        let entries_remaining = persisted_index.saturating_sub(engine_store_index);
+        if entries_remaining > 0 {
+            self.prefetch_entries_for_catch_up(region_id, engine_store_index, persisted_index);
+        }
+        let mut debt = REPLAY_DEBT.write().unwrap();
+        let previous = if entries_remaining == 0 {
+            debt.remove(&region_id)
+        } else {
+            debt.insert(
+                region_id,
+                RegionReplayDebt {
+                    persisted_index,
+                    engine_store_index,
+                    entries_remaining,
+                },
+            )
+        };
+        let delta = entries_remaining as i64 - previous.map_or(0, |d| d.entries_remaining as i64);
+        if delta != 0 {
+            TIFLASH_REPLAY_DEBT_GAUGE.add(delta);
+        }
+        if entries_remaining == 0 && previous.is_some() {
+            info!(
+                "region replay debt cleared, engine store caught up";
+                "region_id" => region_id,
+                "persisted_index" => persisted_index,
+            );
+            // The engine store has now durably caught up through
+            // `persisted_index`; nothing it still needs is older than that,
+            // so it is safe to reclaim. See `core::checkpoint_compaction`.
+            self.compact_durable_checkpoint(region_id, persisted_index);
+        }
+    }
+}
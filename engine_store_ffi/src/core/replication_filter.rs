@@ -0,0 +1,76 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use lazy_static::lazy_static;
+
+use crate::core::{
+    common::*,
+    metrics::{TIFLASH_REPLICATION_FILTERED_BYTES_COUNTER, TIFLASH_REPLICATION_FILTERED_KEYS_COUNTER},
+    ProxyForwarder,
+};
+
+/// A `[start, end)` key range whose mutations should not be forwarded to the
+/// engine store, e.g. a table with no TiFlash replica that happens to share
+/// a region with one that does after a merge. An empty `end` means the
+/// range is unbounded on the right, matching `metapb::Region`'s own
+/// convention for its end key.
+#[derive(Clone, Debug, PartialEq, Eq, serde_derive::Serialize)]
+pub struct ExcludedKeyRange {
+    pub start: Vec<u8>,
+    pub end: Vec<u8>,
+}
+
+impl ExcludedKeyRange {
+    fn contains(&self, key: &[u8]) -> bool {
+        key >= self.start.as_slice() && (self.end.is_empty() || key < self.end.as_slice())
+    }
+}
+
+lazy_static! {
+    /// The current excluded-range set, shared by every `ProxyForwarder`
+    /// instance in this process (there is exactly one in practice, same as
+    /// `core::maintenance::MAINTENANCE_MODE`).
+    ///
+    /// Nothing in this crate populates this today. The request this filter
+    /// exists for asks for the set to be "maintained from PD placement
+    /// rules", but `pd_client::PdClient` (this proxy's only channel to PD)
+    /// exposes region/store/TSO queries, not placement-rule or table-replica
+    /// lookups -- in a real deployment that mapping comes from TiDB's schema
+    /// sync, a layer above this crate entirely. `set_excluded_key_ranges` is
+    /// the integration point such a layer would call into; until it exists,
+    /// the set stays empty and this filter is a no-op.
+    static ref EXCLUDED_RANGES: RwLock<Vec<ExcludedKeyRange>> = RwLock::new(Vec::new());
+}
+
+/// Replaces the current excluded-range set wholesale, mirroring how
+/// `core::maintenance` and `core::learner_health`'s cached-region-meta
+/// updates are pushed in: the caller (external to this crate today) always
+/// hands over the full desired state rather than incremental diffs.
+pub fn set_excluded_key_ranges(ranges: Vec<ExcludedKeyRange>) {
+    *EXCLUDED_RANGES.write().unwrap() = ranges;
+}
+
+fn is_excluded(key: &[u8]) -> bool {
+    EXCLUDED_RANGES
+        .read()
+        .unwrap()
+        .iter()
+        .any(|r| r.contains(key))
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Whether `key` falls in an excluded range and should not be forwarded
+    /// to the engine store. Callers that skip a write based on this should
+    /// also call [`Self::record_replication_filtered`] so the skip is
+    /// observable.
+    pub fn is_write_replication_filtered(&self, key: &[u8]) -> bool {
+        self.packed_envs
+            .engine_store_cfg
+            .enable_key_range_replication_filter
+            && is_excluded(key)
+    }
+
+    /// Accounts for one write skipped by [`Self::is_write_replication_filtered`].
+    pub fn record_replication_filtered(&self, bytes: u64) {
+        TIFLASH_REPLICATION_FILTERED_KEYS_COUNTER.inc();
+        TIFLASH_REPLICATION_FILTERED_BYTES_COUNTER.inc_by(bytes);
+    }
+}
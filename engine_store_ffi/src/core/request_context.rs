@@ -0,0 +1,54 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::{
+    sync::{atomic::AtomicBool, Arc},
+    time::{Duration, Instant},
+};
+
+use crate::core::forwarder::RegionPriority;
+
+/// Deadline/cancellation/priority context threaded into long-running FFI
+/// calls (today only the background `pre_handle_snapshot`) so the proxy can bound how
+/// long it waits on the engine store, and give up on work for a region that
+/// is gone, instead of waiting indefinitely.
+///
+/// The FFI calls themselves are still synchronous and have no
+/// cancellation-aware slot on `EngineStoreServerHelper` -- adding one needs
+/// regenerating the bindgen'd header via the `gen-proxy-ffi` toolchain, which
+/// is not done here. Until then this only lets the proxy decide not to
+/// *start* or *wait on* a call; it cannot abort one already running on the
+/// engine-store side.
+#[derive(Clone)]
+pub struct FfiRequestContext {
+    deadline: Instant,
+    cancelled: Arc<AtomicBool>,
+    pub priority: RegionPriority,
+}
+
+impl FfiRequestContext {
+    pub fn new(timeout: Duration, priority: RegionPriority) -> Self {
+        FfiRequestContext {
+            deadline: Instant::now() + timeout,
+            cancelled: Arc::new(AtomicBool::new(false)),
+            priority,
+        }
+    }
+
+    /// A handle that can be stashed elsewhere (e.g. a region's pre-handle
+    /// tracker) and flipped by `on_region_changed` to cancel this request.
+    pub fn cancel_handle(&self) -> Arc<AtomicBool> {
+        self.cancelled.clone()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(std::sync::atomic::Ordering::Acquire)
+    }
+
+    pub fn should_abort(&self) -> bool {
+        self.is_cancelled() || self.is_expired()
+    }
+}
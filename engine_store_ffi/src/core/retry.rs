@@ -0,0 +1,22 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+pub use engine_tiflash::RetryPolicy;
+
+use crate::core::metrics::TIFLASH_RETRY_COUNTER;
+
+/// Runs `f` up to `policy.max_attempts` times, sleeping with jittered
+/// backoff between attempts, until it returns `true` (success). Returns
+/// whether it eventually succeeded. `call_class` is a fixed, low-cardinality
+/// label (e.g. `"flush"`, `"snapshot_apply"`) used only for
+/// `tikv_tiflash_ffi_retry_total`.
+pub fn retry_with_backoff(call_class: &str, policy: &RetryPolicy, mut f: impl FnMut() -> bool) -> bool {
+    for attempt in 0..policy.max_attempts.max(1) {
+        if f() {
+            return true;
+        }
+        TIFLASH_RETRY_COUNTER.with_label_values(&[call_class]).inc();
+        if attempt + 1 < policy.max_attempts {
+            std::thread::sleep(policy.backoff(attempt));
+        }
+    }
+    false
+}
@@ -0,0 +1,137 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+
+use crate::core::{common::*, ProxyForwarder};
+
+/// Result of a `rewind_region` attempt, surfaced to the debug service so an
+/// operator can tell a no-op ("already at or before `to_index`") from a range
+/// that was actually found and counted. `Counted` does NOT mean the entries
+/// were re-delivered to the engine store -- see `rewind_region`'s doc comment
+/// for why that half doesn't exist yet.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RewindResult {
+    AlreadyBefore,
+    Counted { recoverable: usize },
+    EntryNotFound,
+}
+
+lazy_static! {
+    // A process-wide slot rather than a `ProxyForwarder` field, same
+    // reasoning as `core::rebuild_region`'s `REBUILDS`: `PUT
+    // /debug/rewind_region/<region_id>` has no forwarder handle to call
+    // `rewind_region` on directly, so it only records the request here for
+    // `ProxyForwarder::process_rewind_requests` to pick up from the
+    // `CompactLog` admission tick, the same piggyback every other
+    // tick-driven sweep in this crate uses.
+    static ref REWIND_REQUESTS: Mutex<HashMap<u64, u64>> = Mutex::new(HashMap::default());
+}
+
+/// Queues a recoverable-entry count check for `region_id` from `to_index`
+/// onward (see `ProxyForwarder::rewind_region`), overwriting any
+/// not-yet-processed request already queued for it. Returns immediately; the
+/// check itself runs on the next `CompactLog` admission tick.
+pub fn request_rewind(region_id: u64, to_index: u64) {
+    REWIND_REQUESTS.lock().unwrap().insert(region_id, to_index);
+}
+
+fn drain_rewind_requests() -> Vec<(u64, u64)> {
+    std::mem::take(&mut *REWIND_REQUESTS.lock().unwrap())
+        .into_iter()
+        .collect()
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Checks how many raft log entries from `to_index` onward this proxy
+    /// can still recover for `region_id`, e.g. after the engine store rolled
+    /// back to an older on-disk checkpoint than what the proxy has already
+    /// acknowledged, and records the resulting replay debt / warms the
+    /// prefetch cache as a side effect.
+    ///
+    /// This does NOT re-deliver anything to the engine store: there is no
+    /// FFI call to re-queue arbitrary historical entries through raftstore's
+    /// apply path (that would need a new entry on `EngineStoreServerHelper`
+    /// and a `gen-proxy-ffi` regen, not done here), so actual catch-up still
+    /// happens only through normal raft replication once the engine store
+    /// reports its own applied index (see `core::replay_debt`). This only
+    /// tells an operator whether the entries are still there to be replayed
+    /// if/when that happens, and only reads entries still retained by the
+    /// raft engine -- if they have already been GC'ed the caller must fall
+    /// back to a full snapshot.
+    pub fn rewind_region(&self, region_id: u64, to_index: u64) -> RaftStoreResult<RewindResult> {
+        let applied = match self
+            .raft_engine
+            .get_apply_state(region_id, u64::MAX)
+            .map_err(|e| box_err!(e))?
+        {
+            Some(s) => s.get_applied_index(),
+            None => return Ok(RewindResult::EntryNotFound),
+        };
+        if to_index >= applied {
+            return Ok(RewindResult::AlreadyBefore);
+        }
+        let first = match self
+            .raft_engine
+            .get_entry(region_id, to_index)
+            .map_err(|e| box_err!(e))?
+        {
+            Some(e) => e,
+            None => return Ok(RewindResult::EntryNotFound),
+        };
+
+        // `to_index` is the engine store's own last-acknowledged index in
+        // this scenario (that is what triggered the rewind), and `applied`
+        // is this store's own persisted apply index -- exactly
+        // `record_replay_debt`'s two inputs, and the real catch-up case
+        // `core::entry_prefetch`'s cache was built for. This both records
+        // the debt and warms the cache the loop below reads from.
+        self.record_replay_debt(region_id, applied, to_index);
+
+        // Past `to_index` itself (read above), serve as much of the low end
+        // of the range as possible from the entry prefetch cache (see
+        // `core::entry_prefetch`, which caches from `to_index + 1`) before
+        // falling back to the raft engine for the rest.
+        let mut entries = vec![first];
+        let mut next = to_index + 1;
+        while next < applied + 1 {
+            match self.take_prefetched_entry(region_id, next) {
+                Some(e) => {
+                    entries.push(e);
+                    next += 1;
+                }
+                None => break,
+            }
+        }
+        let from_cache = entries.len() - 1;
+        if next < applied + 1 {
+            self.raft_engine
+                .fetch_entries_to(region_id, next, applied + 1, None, &mut entries)
+                .map_err(|e| box_err!(e))?;
+        }
+        let recoverable = entries.len();
+        info!("counted recoverable raft log entries for rewind";
+            "region_id" => region_id,
+            "to_index" => to_index,
+            "applied_index" => applied,
+            "recoverable" => recoverable,
+            "from_prefetch_cache" => from_cache,
+        );
+        // No re-delivery happens here -- see this function's doc comment.
+        Ok(RewindResult::Counted { recoverable })
+    }
+
+    /// Drains every `request_rewind`-queued rewind and runs it. Called from
+    /// the `CompactLog` admission tick -- see `core::delayed_peer_destroy`'s
+    /// doc comment for why there's no dedicated thread for this instead.
+    pub(crate) fn process_rewind_requests(&self) {
+        for (region_id, to_index) in drain_rewind_requests() {
+            match self.rewind_region(region_id, to_index) {
+                Ok(result) => info!("processed queued rewind request";
+                    "region_id" => region_id, "to_index" => to_index, "result" => ?result),
+                Err(e) => warn!("failed to process queued rewind request";
+                    "region_id" => region_id, "to_index" => to_index, "err" => ?e),
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use lazy_static::lazy_static;
+use tikv_util::sys::SysQuota;
+use tokio::runtime::Runtime;
+
+/// Shared, first-class async runtime for the FFI service layer. New FFI
+/// services should queue their completion work here instead of each
+/// spinning up its own dedicated OS thread(s), so adding one more service
+/// doesn't cost a thread and every service gets the same
+/// timeout/cancellation primitives (`tokio::time`) for free.
+///
+/// This does not yet replace the read-index and snapshot pre-handle
+/// services' own dedicated thread pools -- `proxy_ffi::read_index_helper`'s
+/// `ReadIndexClient` is driven by the engine store polling a Rust future
+/// across the FFI boundary rather than owning a Rust thread at all, and
+/// `ProxyForwarder::apply_snap_pool` is a yatp pool sized by
+/// `snap_handle_pool_size`. Migrating either onto this runtime is a larger,
+/// coordinated change across `proxy_ffi` and `engine_store_ffi` and is not
+/// done here. What lands in this commit is the shared runtime itself and its
+/// first consumer, `spawn_blocking_ffi`, used by the debug service's region
+/// unfreeze replay (see `core::freeze::unfreeze_region`) so that blocking FFI
+/// work runs off whatever thread invoked it instead of on it.
+lazy_static! {
+    static ref SHARED_RUNTIME: Runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads((SysQuota::cpu_cores_quota() as usize).max(1))
+        .thread_name("engine-store-svc")
+        .enable_all()
+        .build()
+        .unwrap();
+}
+
+/// Runs a blocking FFI call on the shared runtime's blocking pool and
+/// returns a future that resolves with its result, so a caller that must
+/// stay responsive (e.g. an HTTP handler) can `.await` it instead of calling
+/// it inline.
+pub fn spawn_blocking_ffi<F, R>(f: F) -> impl std::future::Future<Output = R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let handle = SHARED_RUNTIME.handle().clone();
+    async move {
+        handle
+            .spawn_blocking(f)
+            .await
+            .expect("FFI service task panicked")
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use crate::core::{common::*, ProxyForwarder};
+
+const SEGMENT_GC_JOURNAL_FILE_NAME: &str = "segment_gc.log";
+
+/// Journals the handoff of a stale-peer destroy to the engine store, so it
+/// can schedule segment GC for the region's range and so a restart can tell
+/// which destroys the engine store is known to have taken over.
+///
+/// `EngineStoreServerHelper::handle_destroy` takes only a `region_id` and
+/// returns nothing: there is no FFI slot to hand it the region's key range
+/// or epoch, and no return value it could use to acknowledge the destroy,
+/// same class of ABI limitation as `EngineStoreApplyRes` carrying no
+/// index/term (see `core::applied_term_guard`) -- both need the
+/// `gen-proxy-ffi` toolchain to add. Recording the range/epoch here, and
+/// treating `handle_destroy` returning at all as the best available local
+/// stand-in for an ack, is the closest approximation reachable from this
+/// crate: it still sequences the proxy's own metadata removal strictly
+/// after the call, and gives a diagnostic trail of what range each destroy
+/// covered, but it is not a genuine cross-boundary acknowledgement.
+#[derive(Debug)]
+pub struct SegmentGcJournal {
+    file: Mutex<Option<std::fs::File>>,
+}
+
+impl Default for SegmentGcJournal {
+    fn default() -> Self {
+        SegmentGcJournal {
+            file: Mutex::new(None),
+        }
+    }
+}
+
+impl SegmentGcJournal {
+    fn ensure_open(&self, data_dir: &std::path::Path) -> std::io::Result<()> {
+        let mut guard = self.file.lock().unwrap();
+        if guard.is_some() {
+            return Ok(());
+        }
+        let path = data_dir.join(SEGMENT_GC_JOURNAL_FILE_NAME);
+        let f = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        *guard = Some(f);
+        Ok(())
+    }
+
+    fn append(&self, data_dir: &std::path::Path, line: &str) {
+        if let Err(e) = self.ensure_open(data_dir) {
+            warn!("failed to open segment gc journal"; "err" => ?e);
+            return;
+        }
+        let mut guard = self.file.lock().unwrap();
+        if let Some(f) = guard.as_mut() {
+            if let Err(e) = writeln!(f, "{}", line) {
+                warn!("failed to append to segment gc journal"; "err" => ?e);
+            }
+        }
+    }
+
+    /// Scans the journal left over from a previous run and warns about any
+    /// `begin` with no matching `acked`, i.e. a destroy that may have been
+    /// interrupted before the engine store took it over.
+    pub fn recover(&self, data_dir: &std::path::Path) {
+        let path = data_dir.join(SEGMENT_GC_JOURNAL_FILE_NAME);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(e) => {
+                warn!("failed to read segment gc journal for recovery"; "err" => ?e);
+                return;
+            }
+        };
+        let mut pending: HashMap<u64, ()> = HashMap::default();
+        for line in content.lines() {
+            let mut region_id = 0;
+            let mut phase = "";
+            for field in line.split_whitespace() {
+                if let Some(v) = field.strip_prefix("region=") {
+                    region_id = v.parse().unwrap_or(0);
+                } else if let Some(v) = field.strip_prefix("phase=") {
+                    phase = v;
+                }
+            }
+            match phase {
+                "begin" => {
+                    pending.insert(region_id, ());
+                }
+                "acked" => {
+                    pending.remove(&region_id);
+                }
+                _ => {}
+            }
+        }
+        for region_id in pending.into_keys() {
+            warn!("found unfinished segment gc handoff in journal, region may need a manual \
+                   segment gc on the engine store side";
+                "region_id" => region_id,
+            );
+        }
+    }
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    fn segment_gc_journal_enabled(&self) -> bool {
+        self.packed_envs.engine_store_cfg.enable_segment_gc_journal
+    }
+
+    /// Notifies the engine store that `region_id` is being destroyed (via
+    /// the existing `handle_destroy` FFI call) so it can schedule segment
+    /// GC for `[start_key, end_key)`, journaling the range/epoch and the
+    /// call's completion around it. See the module doc comment for why the
+    /// "ack" here is only the proxy's own observation that the call
+    /// returned, not a real acknowledgement from the engine store.
+    pub(crate) fn notify_segment_gc(
+        &self,
+        region_id: u64,
+        start_key: &[u8],
+        end_key: &[u8],
+        conf_ver: u64,
+        version: u64,
+    ) {
+        let data_dir = std::path::Path::new(self.engine.path());
+        if self.segment_gc_journal_enabled() {
+            let line = format!(
+                "region={} conf_ver={} version={} start_key={} end_key={} phase=begin",
+                region_id,
+                conf_ver,
+                version,
+                log_wrappers::Value::key(start_key),
+                log_wrappers::Value::key(end_key),
+            );
+            self.debug_struct.segment_gc_journal.append(data_dir, &line);
+        }
+        self.engine_store_server_helper.handle_destroy(region_id);
+        if self.segment_gc_journal_enabled() {
+            let line = format!("region={} phase=acked", region_id);
+            self.debug_struct.segment_gc_journal.append(data_dir, &line);
+        }
+    }
+}
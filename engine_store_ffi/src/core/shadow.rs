@@ -0,0 +1,71 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+
+use crate::{
+    core::{common::*, metrics::TIFLASH_SHADOW_DIVERGENCE_COUNTER, ProxyForwarder},
+    ffi::gen_engine_store_server_helper,
+};
+
+lazy_static! {
+    // Set via the debug service rather than a `ProxyForwarder` field, for
+    // the same reason as `maintenance`/`raft_log_export`: no generic
+    // threading through `StatusServer`.
+    //
+    // There is no way to obtain a *second* live engine-store process's
+    // helper pointer here short of the C++ `run_proxy` entrypoint accepting
+    // one -- that's a fixed FFI ABI surface that needs the `gen-proxy-ffi`
+    // toolchain to change, and is not done here. Until then this is meant
+    // for harnesses that already hold two helper pointers, e.g. two mock
+    // engine stores wired up in `proxy_tests` for canarying an
+    // engine-store upgrade against a shadow instance before promoting it.
+    static ref SHADOW_HELPER: RwLock<Option<&'static EngineStoreServerHelper>> =
+        RwLock::new(None);
+}
+
+pub fn register_shadow_engine_store(ptr: isize) {
+    *SHADOW_HELPER.write().unwrap() = Some(gen_engine_store_server_helper(ptr));
+}
+
+pub fn clear_shadow_engine_store() {
+    *SHADOW_HELPER.write().unwrap() = None;
+}
+
+pub fn shadow_engine_store() -> Option<&'static EngineStoreServerHelper> {
+    *SHADOW_HELPER.read().unwrap()
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Mirrors a write-apply call to the shadow helper, if one is
+    /// registered, and logs a divergence if its `EngineStoreApplyRes`
+    /// differs from what the primary engine store returned. Best-effort:
+    /// the shadow call's result never affects persistence for this store,
+    /// so a mismatch here is only an observability signal for canarying,
+    /// not a correctness problem to react to.
+    pub(crate) fn shadow_compare_write(
+        &self,
+        cmds: &WriteCmds,
+        region_id: u64,
+        index: u64,
+        term: u64,
+        primary_res: EngineStoreApplyRes,
+    ) {
+        let shadow = match shadow_engine_store() {
+            Some(h) => h,
+            None => return,
+        };
+        let shadow_res = shadow.handle_write_raft_cmd(cmds, RaftCmdHeader::new(region_id, index, term));
+        if shadow_res != primary_res {
+            TIFLASH_SHADOW_DIVERGENCE_COUNTER.inc();
+            warn!("shadow engine store diverged on write apply";
+                "region_id" => region_id,
+                "index" => index,
+                "term" => term,
+                "primary_res" => ?primary_res,
+                "shadow_res" => ?shadow_res,
+            );
+        }
+    }
+}
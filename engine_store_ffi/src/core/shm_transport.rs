@@ -0,0 +1,40 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use crate::core::{common::*, ProxyForwarder};
+
+/// Which channel a write batch takes from the apply thread to the engine
+/// store. `SharedMemory` is aspirational: making it real needs two things
+/// this crate cannot deliver on its own --
+///
+/// 1. A capability handshake so the proxy and engine store agree a ring
+///    buffer region exists and where, before either side touches it. That is
+///    a new call on `EngineStoreServerHelper`/`RaftStoreProxyFFIHelper` (see
+///    `proxy_ffi::engine_store_helper_impls`), which is bindgen-generated
+///    from TiFlash's C++ header and needs the `gen-proxy-ffi` toolchain to
+///    extend, not available in this tree.
+/// 2. A consumer on the engine-store (C++) side draining the buffer on its
+///    own threads -- the entire point of the request -- which lives outside
+///    this Rust crate regardless of toolchain access.
+///
+/// Until both exist, `enable_shm_transport` only selects this enum's value;
+/// `negotiate_transport_capability` always reports `Direct`, so every write
+/// still goes through today's synchronous `handle_write_raft_cmd` FFI call.
+/// The setting is kept so a real handshake can slot in later without another
+/// config-shape change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransportCapability {
+    Direct,
+    SharedMemory,
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// What transport is actually in effect for this run. Always `Direct`
+    /// today -- see the module doc comment for why `enable_shm_transport`
+    /// alone cannot make it `SharedMemory` yet.
+    pub fn negotiate_transport_capability(&self) -> TransportCapability {
+        if self.packed_envs.engine_store_cfg.enable_shm_transport {
+            warn!("enable_shm_transport is set but no engine-store side capability exists yet, \
+                   falling back to the direct FFI transport");
+        }
+        TransportCapability::Direct
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Mutex, RwLock},
+    time::Instant,
+};
+
+use lazy_static::lazy_static;
+use raftstore::store::SnapKey;
+use serde_derive::Serialize;
+
+/// One completed (or abandoned) attempt to apply a received snapshot to the
+/// engine store, kept for `/debug/snapshot_apply_history/<region_id>` so an
+/// operator retrying a stuck AddLearner can see why past attempts failed
+/// without re-deriving it from raw logs.
+#[derive(Clone, Debug, Serialize)]
+pub struct SnapshotApplyAttempt {
+    /// The store that sent this snapshot. `ApplySnapshotObserver` only
+    /// passes the local peer id, not the sender, so this is always `None`
+    /// today; kept as a field rather than dropped so a future signal (e.g.
+    /// threaded through from the raft message that carried the snapshot)
+    /// can fill it in without changing this record's shape.
+    pub source_store_id: Option<u64>,
+    pub size_bytes: u64,
+    pub duration_ms: u64,
+    pub outcome: String,
+    pub error: Option<String>,
+}
+
+lazy_static! {
+    // Snapshot key -> (start instant, size), removed once the matching
+    // attempt is finished. A process-wide slot rather than a `ProxyForwarder`
+    // field since `pre_apply_snapshot`/`post_apply_snapshot` fire on
+    // different threads for the same key and there is no context object
+    // that outlives both.
+    static ref PENDING_STARTS: Mutex<HashMap<SnapKey, (Instant, u64)>> =
+        Mutex::new(HashMap::default());
+    // region_id -> bounded history of recent attempts, newest last. Mirrors
+    // `core::startup_check`'s `GLOBAL_STARTUP_REPORT` in being a process-wide
+    // slot the status server reads without a handle to `ProxyForwarder`.
+    static ref HISTORY: RwLock<HashMap<u64, VecDeque<SnapshotApplyAttempt>>> =
+        RwLock::new(HashMap::default());
+}
+
+/// Marks the start of an apply attempt for `snap_key`. Idempotent-ish: a
+/// second call for the same key (e.g. `pre_apply_snapshot` resuming an
+/// already-tracked pre-handle) just overwrites the start time, so the
+/// eventually recorded duration measures the last attempt, not the sum.
+pub fn begin_snapshot_apply(snap_key: &SnapKey, size_bytes: u64) {
+    PENDING_STARTS
+        .lock()
+        .unwrap()
+        .insert(snap_key.clone(), (Instant::now(), size_bytes));
+}
+
+/// Finishes the apply attempt for `snap_key` and records it under
+/// `region_id`, capped to the last `history_len` entries. `history_len == 0`
+/// disables recording entirely (mirrors
+/// `snapshot_prehandle_concurrency_limit`'s 0-disables convention) -- the
+/// pending start is still cleared so it can't leak.
+pub fn finish_snapshot_apply(
+    region_id: u64,
+    snap_key: &SnapKey,
+    outcome: impl Into<String>,
+    error: Option<String>,
+    history_len: usize,
+) {
+    let started = PENDING_STARTS.lock().unwrap().remove(snap_key);
+    if history_len == 0 {
+        return;
+    }
+    let (size_bytes, duration_ms) = match started {
+        Some((start, size)) => (size, start.elapsed().as_millis() as u64),
+        None => (0, 0),
+    };
+    let attempt = SnapshotApplyAttempt {
+        source_store_id: None,
+        size_bytes,
+        duration_ms,
+        outcome: outcome.into(),
+        error,
+    };
+    let mut history = HISTORY.write().unwrap();
+    let entries = history.entry(region_id).or_default();
+    entries.push_back(attempt);
+    while entries.len() > history_len {
+        entries.pop_front();
+    }
+}
+
+/// Returns `region_id`'s recorded attempts, oldest first, or an empty `Vec`
+/// if none have been recorded (including when history is disabled).
+pub fn snapshot_apply_history(region_id: u64) -> Vec<SnapshotApplyAttempt> {
+    HISTORY
+        .read()
+        .unwrap()
+        .get(&region_id)
+        .map(|entries| entries.iter().cloned().collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,85 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//! Pluggable checksum algorithm for pre-handled snapshot artifacts.
+//!
+//! This only covers the proxy-local leg of a snapshot: the files this store
+//! hands to the engine store over FFI during pre-handle. It does not touch
+//! `RaftSnapshotData`'s own `SnapshotCfFile.checksum` -- that field is a
+//! fixed `u32` CRC32 on the wire between raft peers (see
+//! `raftstore::store::snap::calc_crc32`), and changing it would break
+//! compatibility with any peer running vanilla TiKV. CRC32 stays hardcoded
+//! there; what is pluggable here is only the diagnostic checksum this store
+//! logs for its own SST files just before the FFI hand-off, computed by
+//! [`checksum_file`] and called from `core::parallel_prehandle` (see that
+//! module's doc comment for why it's diagnostic-only).
+use std::path::Path;
+
+use engine_tiflash::ChecksumAlgorithm;
+use sha2::Digest;
+
+use crate::core::common::*;
+
+/// Capability key an engine store build sets, to an array of algorithm
+/// names (by [`ChecksumAlgorithm::as_str`]), in the JSON blob returned by
+/// `EngineStoreServerHelper::get_config`, listing which of these it can
+/// verify itself. See `core::empty_cmd_summary` for the same negotiation
+/// shape.
+pub const CAPABILITY_KEY: &str = "snapshot.checksum-algorithms";
+
+/// The digest bytes produced by [`checksum_file`], tagged with the
+/// algorithm that produced them so a mismatched caller can't compare across
+/// algorithms by mistake.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checksum {
+    pub algorithm: ChecksumAlgorithm,
+    pub digest: Vec<u8>,
+}
+
+/// Computes `algorithm`'s digest of `path`'s contents.
+pub fn checksum_file(algorithm: ChecksumAlgorithm, path: &Path) -> std::io::Result<Checksum> {
+    let digest = match algorithm {
+        ChecksumAlgorithm::Crc32 => file_system::calc_crc32(path)?.to_be_bytes().to_vec(),
+        ChecksumAlgorithm::Xxh3 => xxhash_rust::xxh3::xxh3_64(&std::fs::read(path)?)
+            .to_be_bytes()
+            .to_vec(),
+        ChecksumAlgorithm::Sha256 => {
+            let mut hasher = sha2::Sha256::new();
+            hasher.update(std::fs::read(path)?);
+            hasher.finalize().to_vec()
+        }
+    };
+    Ok(Checksum { algorithm, digest })
+}
+
+/// Queries `helper.get_config` once and returns `configured` if the engine
+/// store advertised support for it via [`CAPABILITY_KEY`] (or `configured`
+/// is `Crc32`, which every engine store build this proxy has ever talked to
+/// can already verify without opting in). Falls back to `Crc32` on any
+/// parse failure or if `configured` wasn't advertised.
+pub fn negotiate_checksum_algorithm(
+    configured: ChecksumAlgorithm,
+    helper: &EngineStoreServerHelper,
+) -> ChecksumAlgorithm {
+    if configured == ChecksumAlgorithm::Crc32 {
+        return ChecksumAlgorithm::Crc32;
+    }
+    let config = helper.get_config(false);
+    let advertised: Vec<String> = serde_json::from_slice::<serde_json::Value>(&config)
+        .ok()
+        .and_then(|v| v.get(CAPABILITY_KEY).cloned())
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    if advertised.iter().any(|a| a == configured.as_str()) {
+        configured
+    } else {
+        ChecksumAlgorithm::Crc32
+    }
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// The algorithm to checksum pre-handled snapshot artifacts with,
+    /// already negotiated with the engine store at startup; see
+    /// [`negotiate_checksum_algorithm`].
+    pub fn snapshot_checksum_algorithm(&self) -> ChecksumAlgorithm {
+        self.snapshot_checksum_algorithm
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//! Would let the engine store veto or attach artifacts to a snapshot this
+//! proxy is about to generate for sending to another peer, so a future
+//! TiFlash-to-TiFlash replica topology could keep its own columnar data out
+//! of a raftstore-level snapshot (or fold something engine-store-specific
+//! into it) instead of only ever being the receiving end.
+//!
+//! Two things are missing before this can do anything real, both out of
+//! proportion for this change alone:
+//!
+//! - There is no `fn_pre_gen_snapshot`-shaped slot on
+//!   `EngineStoreServerHelper` (see `proxy_ffi::interfaces`) -- the FFI
+//!   vtable only has receive-side calls (`fn_pre_handle_snapshot`,
+//!   `fn_apply_pre_handled_snapshot`). Adding one needs a `gen-proxy-ffi` run
+//!   against an updated header.
+//! - Raftstore's outbound snapshot generation reads straight from the raft
+//!   and KV engines (see `raftstore::store::snap`); unlike snapshot
+//!   *receipt*, which `ApplySnapshotObserver` hooks into, there is no
+//!   observer callback on the *generation* path today for any coprocessor,
+//!   TiFlash-specific or not. Adding one is a `raftstore` change shared by
+//!   every observer in the tree, not something to slip in as a side effect
+//!   of one backlog item.
+//!
+//! Also, TiFlash/engine-store peers are always raft learners today -- there
+//! is no code path in this crate that has one send a snapshot to another
+//! peer at all, so even with both of the above, nothing would currently
+//! call [`pre_gen_snapshot`].
+//!
+//! What's here is the shape the wiring would take once both exist: a
+//! registrable veto/artifact decision, mirroring `core::freeze`'s and
+//! `core::restore_point`'s global-handler pattern, so that landing either
+//! prerequisite only needs a call site added, not this type redesigned.
+use lazy_static::lazy_static;
+
+use crate::core::{common::*, metrics::TIFLASH_SNAPSHOT_SEND_VETO_COUNTER};
+
+/// What the engine store wants done with a snapshot this proxy is about to
+/// generate for `region_id`.
+#[derive(Clone, Debug, Default, serde_derive::Serialize)]
+pub struct SnapshotSendDecision {
+    /// If true, generation should be abandoned so the sender-selection
+    /// logic falls back to a TiKV peer instead.
+    pub veto: bool,
+    /// Opaque engine-store-supplied bytes to fold into the outgoing
+    /// snapshot, if generation proceeds. `None` means "nothing to add".
+    pub artifact: Option<Vec<u8>>,
+}
+
+lazy_static! {
+    // Set by whatever eventually calls `register_global_pre_gen_snapshot_handler`;
+    // no current call site does. A process-wide slot rather than a
+    // `ProxyForwarder` field for the same reason as `core::freeze`'s
+    // `UNFREEZE_HANDLER`: the (currently hypothetical) raftstore call site
+    // has no `ProxyForwarder` handle of its own.
+    static ref HANDLER: Mutex<Option<Box<dyn Fn(u64) -> SnapshotSendDecision + Send + Sync>>> =
+        Mutex::new(None);
+}
+
+pub fn register_global_pre_gen_snapshot_handler(
+    f: impl Fn(u64) -> SnapshotSendDecision + Send + Sync + 'static,
+) {
+    *HANDLER.lock().unwrap() = Some(Box::new(f));
+}
+
+/// Asks the registered handler what to do about generating a snapshot for
+/// `region_id`. Defaults to "proceed, nothing to add" if nothing is
+/// registered, which is always the case today -- see the module doc
+/// comment for why nothing calls this yet.
+pub fn pre_gen_snapshot(region_id: u64) -> SnapshotSendDecision {
+    let decision = HANDLER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|f| f(region_id))
+        .unwrap_or_default();
+    if decision.veto {
+        TIFLASH_SNAPSHOT_SEND_VETO_COUNTER.inc();
+    }
+    decision
+}
@@ -0,0 +1,39 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use crate::core::{common::*, ProxyForwarder};
+
+/// A split requested by the engine store itself, e.g. because one of its
+/// columnar segments for the region grew past its own size limit rather
+/// than TiKV's `region-split-size`.
+#[derive(Clone, Debug)]
+pub struct EngineDrivenSplitRequest {
+    pub region_id: u64,
+    pub split_keys: Vec<Vec<u8>>,
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Queues a split request coming from the engine store. Actual
+    /// dispatching into raftstore's split-check scheduler happens out of
+    /// band, since the forwarder itself does not own a `CasualMessage`
+    /// router; `drain_engine_driven_splits` is meant to be polled by the
+    /// component that does (see `raftstore::store::SplitCheckRunner`).
+    pub fn request_engine_driven_split(&self, region_id: u64, split_keys: Vec<Vec<u8>>) {
+        if split_keys.is_empty() {
+            return;
+        }
+        info!("engine store requested a split for oversized segment";
+            "region_id" => region_id,
+            "split_keys" => ?split_keys,
+        );
+        self.pending_engine_splits
+            .lock()
+            .unwrap()
+            .push(EngineDrivenSplitRequest {
+                region_id,
+                split_keys,
+            });
+    }
+
+    pub fn drain_engine_driven_splits(&self) -> Vec<EngineDrivenSplitRequest> {
+        std::mem::take(&mut *self.pending_engine_splits.lock().unwrap())
+    }
+}
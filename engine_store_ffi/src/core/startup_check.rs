@@ -0,0 +1,74 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+use lazy_static::lazy_static;
+use serde_derive::Serialize;
+
+/// Outcome of a single startup self-check. `Fail` means the store is in a
+/// state the operator almost certainly did not intend (e.g. an unwritable
+/// data dir); `Warn` means something worth looking at but not obviously
+/// broken (e.g. a few seconds of clock skew against PD).
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// One row of the `/startup_report` endpoint: the check's name, its
+/// [`CheckStatus`], and a human-readable detail message. Constructed by
+/// `proxy_server::run`, which is the only place with access to everything a
+/// meaningful check needs (PD client, encryption key manager, on-disk config,
+/// the FFI helper) -- this module only defines the shape and holds the
+/// latest report for the status server to read.
+#[derive(Clone, Debug, Serialize)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+impl CheckResult {
+    pub fn ok(name: &'static str, message: impl Into<String>) -> Self {
+        CheckResult { name, status: CheckStatus::Ok, message: message.into() }
+    }
+
+    pub fn warn(name: &'static str, message: impl Into<String>) -> Self {
+        CheckResult { name, status: CheckStatus::Warn, message: message.into() }
+    }
+
+    pub fn fail(name: &'static str, message: impl Into<String>) -> Self {
+        CheckResult { name, status: CheckStatus::Fail, message: message.into() }
+    }
+}
+
+/// Full set of startup self-check results, taken once at process start.
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct StartupReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl StartupReport {
+    pub fn has_failure(&self) -> bool {
+        self.checks.iter().any(|c| c.status == CheckStatus::Fail)
+    }
+}
+
+lazy_static! {
+    // Populated once, right after `TiKvServer::run_startup_self_checks`, and
+    // read-only afterwards -- a process-wide slot rather than a field
+    // threaded through, since the status server has no handle to
+    // `TiKvServer` (mirrors `core::maintenance`'s `MAINTENANCE_MODE`).
+    static ref GLOBAL_STARTUP_REPORT: std::sync::RwLock<Option<StartupReport>> =
+        std::sync::RwLock::new(None);
+}
+
+pub fn record_startup_report(report: StartupReport) {
+    *GLOBAL_STARTUP_REPORT.write().unwrap() = Some(report);
+}
+
+/// `None` before `record_startup_report` has run, e.g. if `/startup_report`
+/// is queried unusually early or self-checks are disabled entirely.
+pub fn current_startup_report() -> Option<StartupReport> {
+    GLOBAL_STARTUP_REPORT.read().unwrap().clone()
+}
@@ -0,0 +1,40 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+use crate::core::{common::*, ProxyForwarder};
+
+/// Admin commands that change region topology (split/merge/conf change) are
+/// the ones `test_old_compact_log`'s doc comment warns about: if the engine
+/// store's acknowledgement of one of these is lost to a crash before the
+/// proxy's own lazily-flushed apply state catches up, the region's on-disk
+/// topology can disagree with what PD and the rest of the raft group believe
+/// it already committed to. `CompactLog`/`ComputeHash`/`VerifyHash` carry no
+/// such risk -- losing their ack just means redoing harmless, idempotent
+/// work -- so they are deliberately excluded.
+fn is_metadata_critical(cmd_type: AdminCmdType) -> bool {
+    matches!(
+        cmd_type,
+        AdminCmdType::Split
+            | AdminCmdType::BatchSplit
+            | AdminCmdType::PrepareMerge
+            | AdminCmdType::CommitMerge
+            | AdminCmdType::RollbackMerge
+            | AdminCmdType::ChangePeer
+            | AdminCmdType::ChangePeerV2
+    )
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Whether `post_exec_admin` should persist apply state for `cmd_type`
+    /// regardless of `EngineStoreApplyRes`, closing the crash window where a
+    /// lazy flush policy lets region topology change in memory well before
+    /// it is made durable. Only takes effect when
+    /// `engine_store_cfg.force_sync_commit_for_metadata_cmds` is set; off by
+    /// default since forcing persistence after every split/merge/conf change
+    /// trades the latency a lazy flush policy was chosen for.
+    pub fn force_sync_commit(&self, cmd_type: AdminCmdType) -> bool {
+        self.packed_envs
+            .engine_store_cfg
+            .force_sync_commit_for_metadata_cmds
+            && is_metadata_critical(cmd_type)
+    }
+}
@@ -0,0 +1,58 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//! Forwards RawKV API V2 TTL (expire timestamp) information to the engine
+//! store, so expired data is eventually dropped consistently with TiKV
+//! instead of TiFlash retaining rows TiKV itself would already treat as
+//! gone.
+//!
+//! This is two pieces at very different levels of completeness:
+//!
+//! - Attaching each write's expire timestamp is real:
+//!   `engine_store_cfg.enable_ttl_forwarding` makes `decode_write_cmds` (see
+//!   `core::decode_pipeline`) decode the TTL embedded in every CF_DEFAULT
+//!   put's value and call `WriteCmds::mark_expire_ts`, the same
+//!   rides-alongside-`WriteCmds` pattern `TxnBoundary`/`TxnSourceMark` use.
+//! - Proactively telling the engine store about keys that have since expired
+//!   -- [`maybe_notify_expired_regions`] -- is not: `EngineStoreServerHelper`
+//!   has no FFI slot for an expiry notification today, only the write/admin
+//!   command vtable `core::forward_raft` already drives, so there is nothing
+//!   true to call even if a region's expired-key count were tracked. Nothing
+//!   calls this function yet; it exists so the call site only needs adding,
+//!   not designing, once that slot exists.
+use std::time::Instant;
+
+use crate::core::{common::*, ProxyForwarder};
+
+pub(crate) struct TtlForwardingState {
+    last_tick: Mutex<Instant>,
+}
+
+impl Default for TtlForwardingState {
+    fn default() -> Self {
+        TtlForwardingState {
+            last_tick: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Would periodically tell the engine store which regions have expired
+    /// RawKV keys it should reclaim, at most once per
+    /// `engine-store.ttl-expiry-notify-interval`. Always a no-op today -- see
+    /// this module's doc comment for why there is nothing to call yet.
+    pub fn maybe_notify_expired_regions(&self) {
+        let cfg = &self.packed_envs.engine_store_cfg;
+        if !cfg.enable_ttl_forwarding {
+            return;
+        }
+        let mut last_tick = self.ttl_forwarding.last_tick.lock().unwrap();
+        if last_tick.elapsed() < cfg.ttl_expiry_notify_interval.0 {
+            return;
+        }
+        *last_tick = Instant::now();
+        debug!(
+            "skipping TTL expiry notification: EngineStoreServerHelper has no FFI slot for it yet"
+        );
+        // Once that slot exists, this is where a per-region expired-key
+        // count would be sampled and forwarded to the engine store.
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use std::{
+    path::Path,
+    sync::{atomic::{AtomicU64, Ordering}, Mutex},
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use slog::Drain;
+use tikv_util::logger::{file_writer, text_format};
+
+use crate::core::common::{RaftEngine, Transport};
+
+struct ActiveTrace {
+    region_id: u64,
+    until: Instant,
+    logger: slog::Logger,
+}
+
+lazy_static! {
+    // Checked on every FFI call before touching `ACTIVE`'s mutex, so tracing
+    // being off (the common case) costs one atomic load. 0 is not a valid
+    // region id, so it doubles as "no trace active".
+    static ref TRACED_REGION: AtomicU64 = AtomicU64::new(0);
+    static ref ACTIVE: Mutex<Option<ActiveTrace>> = Mutex::new(None);
+}
+
+/// Turns on trace-level logging of every FFI interaction for `region_id`,
+/// written to `path` (created if missing, appended to otherwise) until
+/// `duration` elapses or [`disable`] is called, so a production issue with
+/// one region can be traced without turning on global debug logging. Meant
+/// to be reached from the `/debug/verbose_trace/<region_id>` status-server
+/// endpoint.
+pub fn enable(region_id: u64, duration: Duration, path: &Path) -> std::io::Result<()> {
+    let writer = file_writer(path, 200, 1, 1, |p| Ok(p.to_path_buf()))?;
+    let drain = std::sync::Mutex::new(text_format(writer, true).fuse()).fuse();
+    let logger = slog::Logger::root(drain, slog::o!());
+    *ACTIVE.lock().unwrap() = Some(ActiveTrace {
+        region_id,
+        until: Instant::now() + duration,
+        logger,
+    });
+    TRACED_REGION.store(region_id, Ordering::Release);
+    Ok(())
+}
+
+pub fn disable() {
+    *ACTIVE.lock().unwrap() = None;
+    TRACED_REGION.store(0, Ordering::Release);
+}
+
+/// Whether `region_id` currently has an active, unexpired trace. Clears the
+/// trace itself as a side effect once it has expired.
+pub fn is_tracing(region_id: u64) -> bool {
+    if region_id == 0 || TRACED_REGION.load(Ordering::Acquire) != region_id {
+        return false;
+    }
+    let mut active = ACTIVE.lock().unwrap();
+    match active.as_ref() {
+        Some(t) if t.region_id == region_id && Instant::now() < t.until => true,
+        Some(_) => {
+            *active = None;
+            TRACED_REGION.store(0, Ordering::Release);
+            false
+        }
+        None => false,
+    }
+}
+
+/// Logs `summary()` for `region_id`'s active trace, if any. `summary` is
+/// only evaluated when a trace is active, so this is cheap to sprinkle
+/// across every FFI call site regardless of whether tracing is on.
+pub fn trace_ffi_call(region_id: u64, call: &str, summary: impl FnOnce() -> String) {
+    if !is_tracing(region_id) {
+        return;
+    }
+    if let Some(t) = ACTIVE.lock().unwrap().as_ref() {
+        slog::debug!(t.logger, "ffi call"; "region_id" => region_id, "call" => call, "summary" => summary());
+    }
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> crate::core::ProxyForwarder<T, ER> {
+    pub fn trace_ffi_call(&self, region_id: u64, call: &str, summary: impl FnOnce() -> String) {
+        trace_ffi_call(region_id, call, summary);
+    }
+}
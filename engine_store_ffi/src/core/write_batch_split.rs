@@ -0,0 +1,88 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use kvproto::raft_cmdpb::{CmdType, Request};
+
+use crate::core::{common::*, ProxyForwarder};
+
+fn request_bytes(req: &Request) -> usize {
+    match req.get_cmd_type() {
+        CmdType::Put => req.get_put().get_key().len() + req.get_put().get_value().len(),
+        CmdType::Delete => req.get_delete().get_key().len(),
+        _ => 0,
+    }
+}
+
+/// A `[start, end)` slice of a raft entry's requests that, taken alone,
+/// stays within `write_batch_split_threshold`, unless it is a single request
+/// larger than the threshold by itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubBatchRange {
+    pub start: usize,
+    pub end: usize,
+    pub bytes: usize,
+}
+
+/// Greedily slices `requests` into byte-bounded runs, in original order.
+/// Returns `None` when the whole batch already fits in one run (nothing to
+/// split) or splitting is disabled (`max_bytes == 0`).
+///
+/// This only plans the split; `post_exec_query` still hands the whole batch
+/// to `handle_write_raft_cmd` in a single call -- see that call site for why
+/// actually issuing one `handle_write_raft_cmd` per sub-batch is unsafe
+/// without an FFI change.
+pub fn plan_sub_batches(requests: &[Request], max_bytes: usize) -> Option<Vec<SubBatchRange>> {
+    if max_bytes == 0 {
+        return None;
+    }
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut acc = 0usize;
+    for (i, req) in requests.iter().enumerate() {
+        let sz = request_bytes(req);
+        if acc > 0 && acc + sz > max_bytes {
+            ranges.push(SubBatchRange { start, end: i, bytes: acc });
+            start = i;
+            acc = 0;
+        }
+        acc += sz;
+    }
+    if start < requests.len() {
+        ranges.push(SubBatchRange { start, end: requests.len(), bytes: acc });
+    }
+    if ranges.len() <= 1 {
+        None
+    } else {
+        Some(ranges)
+    }
+}
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Logs the sub-batch plan `handle_write_raft_cmd` *would* be split
+    /// into, if `write_batch_split_threshold` is set and this batch exceeds
+    /// it. A single raft entry commits atomically to the engine store
+    /// through one `handle_write_raft_cmd(cmds, header)` call keyed by
+    /// `header`'s `(region_id, index, term)`; there is no ABI parameter
+    /// marking a chunk as non-final, so calling it more than once for the
+    /// same `(index, term)` would let the engine store persist its apply
+    /// index after the first partial call, silently dropping the rest on a
+    /// crash before the remaining calls land -- worse than not splitting at
+    /// all. Extending `handle_write_raft_cmd` with a chunk/final-chunk
+    /// parameter needs a `gen-proxy-ffi` run against an updated header, not
+    /// done here. Until then this is visibility into how large a single FFI
+    /// call a huge raft entry produces, not real splitting.
+    pub(crate) fn log_write_batch_split_plan(&self, region_id: u64, requests: &[Request]) {
+        let threshold = self
+            .packed_envs
+            .engine_store_cfg
+            .write_batch_split_threshold
+            .0 as usize;
+        if let Some(ranges) = plan_sub_batches(requests, threshold) {
+            warn!("raft entry write batch exceeds write_batch_split_threshold, would need \
+                   splitting into sub-batches but handle_write_raft_cmd has no way to accept \
+                   one today";
+                "region_id" => region_id,
+                "sub_batches" => ranges.len(),
+                "total_requests" => requests.len(),
+            );
+        }
+    }
+}
@@ -0,0 +1,40 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//! A startup-selectable mode that replaces the real engine-store FFI call on
+//! the write path with a no-op sink, so `handle_write_raft_cmd`'s cost can
+//! be split into "building the `WriteCmdsView` handed across the FFI
+//! boundary" versus "the call itself", with no engine store attached at
+//! all. Useful for isolating a regression in this crate's own
+//! marshal/call path from one on the engine-store side, and for measuring
+//! the proxy's peak forwarding throughput in isolation.
+//!
+//! Selected once at startup via `engine-store.enable_write_dry_run`; not
+//! meant to be flipped at runtime, since doing so mid-stream would silently
+//! stop persisting writes into whatever engine store is actually attached.
+use std::time::Instant;
+
+use crate::core::{
+    common::*,
+    metrics::{
+        TIFLASH_WRITE_DRY_RUN_CALL_DURATION_HISTOGRAM, TIFLASH_WRITE_DRY_RUN_MARSHAL_DURATION_HISTOGRAM,
+    },
+    ProxyForwarder,
+};
+
+impl<T: Transport + 'static, ER: RaftEngine> ProxyForwarder<T, ER> {
+    /// Builds `cmds`'s FFI view and immediately drops it instead of handing
+    /// it to `engine_store_server_helper`, recording the marshal cost and
+    /// the (near-zero) no-op call cost in their own histograms so they can
+    /// be compared against `handle_write_raft_cmd`'s real latency.
+    pub(crate) fn dry_run_write(&self, cmds: &WriteCmds) -> EngineStoreApplyRes {
+        let marshal_start = Instant::now();
+        let view = cmds.gen_view();
+        TIFLASH_WRITE_DRY_RUN_MARSHAL_DURATION_HISTOGRAM
+            .observe(marshal_start.elapsed().as_secs_f64());
+
+        let call_start = Instant::now();
+        drop(view);
+        TIFLASH_WRITE_DRY_RUN_CALL_DURATION_HISTOGRAM.observe(call_start.elapsed().as_secs_f64());
+
+        EngineStoreApplyRes::Persist
+    }
+}
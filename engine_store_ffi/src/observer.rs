@@ -3,18 +3,20 @@ use std::sync::Arc;
 
 use engine_traits::RaftEngine;
 use kvproto::{
-    raft_cmdpb::{AdminRequest, RaftCmdRequest},
+    metapb, pdpb::CheckPolicy,
+    raft_cmdpb::{AdminRequest, RaftCmdRequest, Request},
     raft_serverpb::{RaftApplyState, RaftMessage},
 };
 use raft::StateRole;
 use raftstore::{
     coprocessor::{
         AdminObserver, ApplyCtxInfo, ApplySnapshotObserver, BoxAdminObserver,
-        BoxApplySnapshotObserver, BoxMessageObserver, BoxPdTaskObserver, BoxQueryObserver,
-        BoxRegionChangeObserver, BoxRoleObserver, BoxUpdateSafeTsObserver, Cmd, Coprocessor,
-        CoprocessorHost, MessageObserver, ObserverContext, PdTaskObserver, QueryObserver,
-        RegionChangeEvent, RegionChangeObserver, RegionState, RoleChange, RoleObserver,
-        StoreSizeInfo, UpdateSafeTsObserver,
+        BoxApplySnapshotObserver, BoxConsistencyCheckObserver, BoxMessageObserver,
+        BoxPdTaskObserver, BoxQueryObserver, BoxRegionChangeObserver, BoxRoleObserver,
+        BoxSplitCheckObserver, BoxUpdateSafeTsObserver, Cmd, ConsistencyCheckObserver,
+        Coprocessor, CoprocessorHost, MessageObserver, ObserverContext, PdTaskObserver,
+        QueryObserver, RegionChangeEvent, RegionChangeObserver, RegionState, RoleChange,
+        RoleObserver, SplitCheckObserver, SplitCheckerHost, StoreSizeInfo, UpdateSafeTsObserver,
     },
     store::{self, SnapManager, Transport},
 };
@@ -60,40 +62,127 @@ impl<T: Transport + 'static, ER: RaftEngine> TiFlashObserver<T, ER> {
     pub fn register_to<E: engine_traits::KvEngine>(
         &self,
         coprocessor_host: &mut CoprocessorHost<E>,
+    ) {
+        self.register_to_with_priority(coprocessor_host, TIFLASH_OBSERVER_PRIORITY);
+    }
+
+    /// Like [`register_to`](Self::register_to), but lets the caller pick the
+    /// priority TiFlash's coprocessors register at, instead of the default
+    /// [`TIFLASH_OBSERVER_PRIORITY`].
+    ///
+    /// Observers are consulted in ascending priority order, and for
+    /// `pre_exec_admin`/`pre_exec_query` the first one that returns `true`
+    /// wins, short-circuiting the rest. So an embedder registering a second
+    /// observer (e.g. an audit observer) that must see or veto a command
+    /// before TiFlash acts on it needs a priority strictly lower than the one
+    /// given here; one that should only run if TiFlash declines needs a
+    /// higher one. Prefer [`TiFlashObserverBuilder`] over calling this
+    /// directly when also registering other observers, so the relative
+    /// ordering is explicit at the call site.
+    pub fn register_to_with_priority<E: engine_traits::KvEngine>(
+        &self,
+        coprocessor_host: &mut CoprocessorHost<E>,
+        priority: u32,
     ) {
         // If a observer is repeatedly registered, it can run repeated logic.
-        coprocessor_host.registry.register_admin_observer(
-            TIFLASH_OBSERVER_PRIORITY,
-            BoxAdminObserver::new(self.clone()),
-        );
-        coprocessor_host.registry.register_query_observer(
-            TIFLASH_OBSERVER_PRIORITY,
-            BoxQueryObserver::new(self.clone()),
-        );
+        coprocessor_host
+            .registry
+            .register_admin_observer(priority, BoxAdminObserver::new(self.clone()));
+        coprocessor_host
+            .registry
+            .register_query_observer(priority, BoxQueryObserver::new(self.clone()));
         coprocessor_host.registry.register_apply_snapshot_observer(
-            TIFLASH_OBSERVER_PRIORITY,
+            priority,
             BoxApplySnapshotObserver::new(self.clone()),
         );
         coprocessor_host.registry.register_region_change_observer(
-            TIFLASH_OBSERVER_PRIORITY,
+            priority,
             BoxRegionChangeObserver::new(self.clone()),
         );
-        coprocessor_host.registry.register_pd_task_observer(
-            TIFLASH_OBSERVER_PRIORITY,
-            BoxPdTaskObserver::new(self.clone()),
-        );
+        coprocessor_host
+            .registry
+            .register_pd_task_observer(priority, BoxPdTaskObserver::new(self.clone()));
         coprocessor_host.registry.register_update_safe_ts_observer(
-            TIFLASH_OBSERVER_PRIORITY,
+            priority,
             BoxUpdateSafeTsObserver::new(self.clone()),
         );
-        coprocessor_host.registry.register_role_observer(
-            TIFLASH_OBSERVER_PRIORITY,
-            BoxRoleObserver::new(self.clone()),
-        );
-        coprocessor_host.registry.register_message_observer(
-            TIFLASH_OBSERVER_PRIORITY,
-            BoxMessageObserver::new(self.clone()),
-        );
+        coprocessor_host
+            .registry
+            .register_role_observer(priority, BoxRoleObserver::new(self.clone()));
+        coprocessor_host
+            .registry
+            .register_message_observer(priority, BoxMessageObserver::new(self.clone()));
+        coprocessor_host
+            .registry
+            .register_split_check_observer(priority, BoxSplitCheckObserver::new(self.clone()));
+        coprocessor_host
+            .registry
+            .register_consistency_check_observer(
+                priority,
+                BoxConsistencyCheckObserver::new(self.clone()),
+            );
+    }
+}
+
+/// Builds up the registration of a [`TiFlashObserver`] onto a
+/// `CoprocessorHost`, alongside any number of other coprocessors an embedder
+/// wants ordered relative to it (e.g. an audit observer that must run before
+/// or after TiFlash's `pre_exec` decisions).
+///
+/// ```ignore
+/// TiFlashObserverBuilder::new(tiflash_observer, &mut coprocessor_host)
+///     .priority(TIFLASH_OBSERVER_PRIORITY)
+///     .register_other(AUDIT_OBSERVER_PRIORITY, BoxAdminObserver::new(audit_observer))
+///     .build();
+/// ```
+pub struct TiFlashObserverBuilder<
+    'h,
+    T: Transport + 'static,
+    ER: RaftEngine,
+    E: engine_traits::KvEngine,
+> {
+    observer: TiFlashObserver<T, ER>,
+    priority: u32,
+    host: &'h mut CoprocessorHost<E>,
+}
+
+impl<'h, T: Transport + 'static, ER: RaftEngine, E: engine_traits::KvEngine>
+    TiFlashObserverBuilder<'h, T, ER, E>
+{
+    pub fn new(observer: TiFlashObserver<T, ER>, host: &'h mut CoprocessorHost<E>) -> Self {
+        Self {
+            observer,
+            priority: TIFLASH_OBSERVER_PRIORITY,
+            host,
+        }
+    }
+
+    /// Overrides the priority `TiFlashObserver` itself registers at.
+    /// Defaults to [`TIFLASH_OBSERVER_PRIORITY`].
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Registers an additional admin observer (e.g. an audit observer) at
+    /// `priority`, ahead of or behind TiFlash depending on how it compares
+    /// to the priority set via [`Self::priority`]. Only admin observers are
+    /// supported today since `pre_exec_admin` is the only decision embedders
+    /// have asked to interpose on; extend this builder as other observer
+    /// kinds need the same treatment.
+    pub fn register_other(self, priority: u32, observer: BoxAdminObserver) -> Self {
+        self.host
+            .registry
+            .register_admin_observer(priority, observer);
+        self
+    }
+
+    /// Registers `TiFlashObserver` and returns it so the caller can keep
+    /// using it (e.g. to call [`TiFlashObserver::stop`] on shutdown).
+    pub fn build(self) -> TiFlashObserver<T, ER> {
+        self.observer
+            .register_to_with_priority(self.host, self.priority);
+        self.observer
     }
 }
 
@@ -138,6 +227,17 @@ impl<T: Transport + 'static, ER: RaftEngine> QueryObserver for TiFlashObserver<T
         self.forwarder.on_empty_cmd(ob_ctx.region(), index, term)
     }
 
+    fn pre_exec_query(
+        &self,
+        ob_ctx: &mut ObserverContext<'_>,
+        requests: &[Request],
+        index: u64,
+        term: u64,
+    ) -> bool {
+        self.forwarder
+            .pre_exec_query(ob_ctx.region(), requests, index, term)
+    }
+
     fn post_exec_query(
         &self,
         ob_ctx: &mut ObserverContext<'_>,
@@ -205,6 +305,10 @@ impl<T: Transport + 'static, ER: RaftEngine> PdTaskObserver for TiFlashObserver<
     fn on_compute_engine_size(&self, store_size: &mut Option<StoreSizeInfo>) {
         self.forwarder.on_compute_engine_size(store_size)
     }
+
+    fn pre_region_heartbeat(&self, hb_task: &raftstore::store::worker::HeartbeatTask) -> bool {
+        self.forwarder.pre_region_heartbeat(hb_task)
+    }
 }
 
 impl<T: Transport + 'static, ER: RaftEngine> ApplySnapshotObserver for TiFlashObserver<T, ER> {
@@ -241,3 +345,42 @@ impl<T: Transport + 'static, ER: RaftEngine> RoleObserver for TiFlashObserver<T,
         self.forwarder.on_role_change(ob_ctx.region(), r)
     }
 }
+
+impl<T: Transport + 'static, ER: RaftEngine, E: engine_traits::KvEngine> SplitCheckObserver<E>
+    for TiFlashObserver<T, ER>
+{
+    fn add_checker(
+        &self,
+        ob_ctx: &mut ObserverContext<'_>,
+        host: &mut SplitCheckerHost<'_, E>,
+        _engine: &E,
+        _policy: CheckPolicy,
+    ) {
+        self.forwarder
+            .add_split_checker(ob_ctx.region().get_id(), host)
+    }
+}
+
+impl<T: Transport + 'static, ER: RaftEngine, E: engine_traits::KvEngine> ConsistencyCheckObserver<E>
+    for TiFlashObserver<T, ER>
+{
+    fn update_context(&self, context: &mut Vec<u8>) -> bool {
+        if self.forwarder.consistency_check_update_context() {
+            context.push(b'F');
+            true
+        } else {
+            false
+        }
+    }
+
+    fn compute_hash(
+        &self,
+        region: &metapb::Region,
+        _context: &mut &[u8],
+        _snap: &E::Snapshot,
+    ) -> raftstore::Result<Option<u32>> {
+        Ok(self
+            .forwarder
+            .consistency_check_compute_hash(region.get_id()))
+    }
+}
@@ -1,12 +1,484 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::time::Duration;
+
+use rand::Rng;
 use serde_derive::{Deserialize, Serialize};
+use tikv_util::config::{ReadableDuration, ReadableSize};
+
+/// Exponential backoff with full jitter, shared by every FFI call class that
+/// used to have its own sleep-and-retry loop (flush, snapshot apply, ...).
+/// One `RetryPolicy` per call class lives in `EngineStoreConfig`, so each can
+/// be tuned independently without touching call sites. Lives alongside
+/// `EngineStoreConfig` rather than in `engine_store_ffi::core::retry` (which
+/// actually runs the retry loop) since `engine_store_ffi` depends on this
+/// crate, not the other way around.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+#[serde(rename_all = "kebab-case")]
+pub struct RetryPolicy {
+    pub max_attempts: usize,
+    pub base_interval: ReadableDuration,
+    pub max_interval: ReadableDuration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            base_interval: ReadableDuration::millis(100),
+            max_interval: ReadableDuration::secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Backoff to wait before attempt number `attempt` (0-based, i.e. called
+    /// with 0 before the first retry), doubling `base_interval` each attempt
+    /// up to `max_interval`, then picking uniformly from `[0, backoff]` so
+    /// concurrent callers hitting the same failure don't retry in lockstep.
+    pub fn backoff(&self, attempt: usize) -> Duration {
+        let base = self.base_interval.as_millis();
+        let max = self.max_interval.as_millis();
+        let capped = base.saturating_mul(1u64 << attempt.min(32)).min(max);
+        if capped == 0 {
+            return Duration::from_millis(0);
+        }
+        Duration::from_millis(rand::thread_rng().gen_range(0..=capped))
+    }
+}
+
+/// Durability CompactLog admission requires from the engine store's flush
+/// acknowledgement before it will let raft log entries be compacted; see
+/// `engine_store_ffi::core::forward_raft::command`'s `CompactLog` handling
+/// and its doc comment on why `Memory` cannot yet be honored.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FlushDurabilityLevel {
+    /// Data need only be acknowledged as applied in the engine store's
+    /// in-memory representation (e.g. its memtable). Lower latency, but a
+    /// crash of the engine store between ack and its own next durable
+    /// checkpoint can lose data that TiKV has already compacted out of its
+    /// raft log.
+    Memory,
+    /// Data must be acknowledged as durable (flushed to disk or an object
+    /// store) before CompactLog is allowed to proceed. Slower, but matches
+    /// what a single-tier `try_flush_data` ack has always been treated as.
+    Durable,
+}
+
+impl Default for FlushDurabilityLevel {
+    fn default() -> Self {
+        FlushDurabilityLevel::Durable
+    }
+}
+
+/// A checksum algorithm this store can compute over a pre-handled snapshot
+/// artifact before handing it to the engine store via FFI; see
+/// `engine_store_ffi::core::snapshot_checksum`. Does not affect
+/// `RaftSnapshotData`'s own `SnapshotCfFile.checksum`, which stays a fixed
+/// CRC32 on the wire between raft peers.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChecksumAlgorithm {
+    /// The default: cheap, and all that `calc_crc32`-based verification
+    /// elsewhere in this codebase has ever required.
+    Crc32,
+    /// Faster than CRC32 on large files when hardware CRC32 isn't
+    /// available; not used anywhere else in this codebase today.
+    Xxh3,
+    /// Cryptographically strong; orders of magnitude slower than the other
+    /// two, offered only for artifacts crossing a trust boundary (e.g. an
+    /// exported snapshot handed to `core::region_migration`) rather than
+    /// the routine pre-handle path.
+    Sha256,
+}
+
+impl ChecksumAlgorithm {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Crc32 => "crc32",
+            ChecksumAlgorithm::Xxh3 => "xxh3",
+            ChecksumAlgorithm::Sha256 => "sha256",
+        }
+    }
+}
+
+impl Default for ChecksumAlgorithm {
+    fn default() -> Self {
+        ChecksumAlgorithm::Crc32
+    }
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(default)]
 #[serde(rename_all = "kebab-case")]
 pub struct EngineStoreConfig {
     pub enable_fast_add_peer: bool,
+    // Allow the store to re-bootstrap with its previous store id when the data
+    // directory is found empty but PD still remembers a store registered at the
+    // same address, e.g. after a disk replacement. Off by default since a wrong
+    // match could merge state from an unrelated store.
+    pub allow_reuse_store_on_empty_dir: bool,
+    // Interval for retrying writes that the engine store previously
+    // rejected or timed out on. No code reads it: no background retry task
+    // runs.
+    pub failed_write_retry_interval: ReadableDuration,
+    // Upper bound on queued failed writes that one
+    // `drain_failed_writes_for_retry` call returns.
+    pub failed_write_retry_max_per_tick: usize,
+    // When set, every admin command decision (compact log filtered, merge,
+    // split, ...) is appended to `<data-dir>/admin_decisions.log` before it
+    // takes effect, so a crash mid-apply can be replayed against a known
+    // sequence of decisions instead of only the raft log.
+    pub enable_admin_decision_journal: bool,
+    // Fraction of the engine store's reported disk capacity (from
+    // `fs_stats`) at which the proxy enters disk-full protection: new
+    // snapshots are rejected and CompactLog is force-admitted to keep the
+    // raft log from growing. 0 disables the check.
+    pub disk_full_enter_ratio: f64,
+    // Fraction the used ratio must drop back below before disk-full
+    // protection is lifted. Kept lower than `disk_full_enter_ratio` as
+    // hysteresis so usage hovering around the threshold doesn't flap.
+    pub disk_full_recovery_ratio: f64,
+    // Deadline given to a single long-running FFI call (today only the
+    // background snapshot pre-handle) via `core::request_context::FfiRequestContext`, so the proxy
+    // gives up waiting on a wedged engine store instead of blocking forever.
+    pub ffi_request_timeout: ReadableDuration,
+    // Builds the `WriteCmds` buffer handed to `handle_write_raft_cmd` on a
+    // dedicated decode pool instead of the apply thread. Off by default: the
+    // buffer build is normally cheap enough that a thread hop costs more
+    // than it saves, but large batches (e.g. bulk ingest replayed as plain
+    // writes) can make it worthwhile.
+    pub enable_decode_pipeline: bool,
+    // Caps how many bytes of pending decode work (summed key+value sizes)
+    // may be in flight on the decode pool at once, so a burst of large
+    // batches can't balloon memory usage there.
+    pub decode_pipeline_quota: ReadableSize,
+    // Upper bound on how many `try_flush_data` calls (triggered by CompactLog
+    // admission) may be in flight across all regions at once. When many
+    // regions cross their CompactLog threshold together, e.g. after a bulk
+    // write, this coalesces the resulting burst into size-bounded waves
+    // instead of letting every region call into the engine store at once. 0
+    // disables the limit.
+    pub flush_concurrency_limit: usize,
+    // Use the engine store's own approximate size/keys for a region (once
+    // queryable, see `core::region_stats`) instead of the proxy's local
+    // RocksDB estimate when answering split-check and PD heartbeat size
+    // fields. Off by default until that query exists.
+    pub use_engine_store_region_stats: bool,
+    // When set, the handoff of a pre-handled snapshot to the engine store
+    // (see `core::snapshot_apply_journal`) is bracketed by begin/commit
+    // entries in `<data-dir>/snapshot_apply.log`, so a restart after a crash
+    // mid-apply can be told apart from a routine one instead of only ever
+    // suspecting the last snapshot.
+    pub enable_snapshot_apply_journal: bool,
+    // Retry policy for `try_flush_data` calls triggered by CompactLog
+    // admission (see `core::retry`).
+    pub flush_retry: RetryPolicy,
+    // Retry policy reserved for the pre-handled-snapshot handoff to the
+    // engine store. Not yet wired up: `apply_pre_handled_snapshot` has no
+    // boolean success signal to retry on, unlike `try_flush_data`, so there
+    // is nothing for `core::retry::retry_with_backoff` to call today. Kept
+    // here so the config shape is ready once that FFI call gains one.
+    pub snapshot_apply_retry: RetryPolicy,
+    // Dedup and pace region heartbeats for stores hosting huge numbers of
+    // TiFlash learner regions, via `core::heartbeat_batch`. Off by default,
+    // since it makes a region's report to PD lag by up to
+    // `heartbeat_min_resend_interval` plus `heartbeat_resend_spread` when
+    // nothing about it has changed.
+    pub enable_heartbeat_batching: bool,
+    // Even when nothing about a region changed, PD still expects to hear
+    // from it at least this often so it doesn't start suspecting the region
+    // went silent.
+    pub heartbeat_min_resend_interval: ReadableDuration,
+    // Extra, region-specific delay added on top of `heartbeat_min_resend_interval`
+    // for an unchanged region's forced resend, so a huge store's resends land
+    // spread across the interval instead of bursting all at once.
+    pub heartbeat_resend_spread: ReadableDuration,
+    // For disaggregated deployments where the proxy's own data directory is
+    // meant to be ephemeral (e.g. a diskless compute node), remove a
+    // snapshot's row SSTs from local disk as soon as the engine store has
+    // taken them over, instead of leaving that to raftstore's normal
+    // snapshot GC cycle. This only shrinks how long the SSTs sit on local
+    // disk after handoff; the receive path that lands them there in the
+    // first place is raftstore's snapshot manager, upstream of the proxy,
+    // so this cannot make the apply itself diskless -- that would need the
+    // snapshot manager to stream straight from remote/object storage, which
+    // is out of this crate's reach.
+    pub enable_remote_snapshot_apply: bool,
+    // When set, every forwarded write/admin command is assigned a monotonic
+    // per-region sequence number, journaled to `<data-dir>/write_sequence.log`
+    // alongside the proxy's own best-effort observation of whether it was
+    // later persisted (see `core::write_sequence`). Off by default: it is a
+    // diagnostic aid towards a gap-free delivery contract, not a substitute
+    // for one, since there is no real ack from the engine store yet.
+    pub enable_write_sequence_journal: bool,
+    // Throttles split-check and consistency-check per region via
+    // `core::region_worker`: a real, scan-based round only runs once every
+    // `region_worker_min_interval`; other rounds fall back to an
+    // approximate (no local-scan) split-check. The local consistency hash
+    // is skipped on every round. Off by default so behavior matches
+    // upstream TiKV until an operator opts in.
+    pub enable_dynamic_region_worker_scheduling: bool,
+    // Minimum time between real split-check/consistency-check rounds for
+    // the same region when `enable_dynamic_region_worker_scheduling` is
+    // set. Tunable at runtime via the `/debug/region_worker_interval`
+    // status-server endpoint; this is only the config-file-loaded default.
+    pub region_worker_min_interval: ReadableDuration,
+    // Run the startup self-checks (disk permissions, encryption, PD clock
+    // skew, config constraints, FFI version handshake) in
+    // `proxy_server::run` and publish the results at `/startup_report`
+    // instead of only ever logging them, so a silent misconfiguration shows
+    // up as a report an operator can query rather than a line buried in the
+    // startup log.
+    pub enable_startup_self_check: bool,
+    // When set, a `Fail`-status startup self-check aborts startup (via
+    // `fatal!`, same as an existing hard check like `check_max_open_fds`)
+    // instead of only being recorded in the report. Off by default so
+    // turning on `enable_startup_self_check` alone is non-disruptive.
+    pub startup_self_check_fail_fast: bool,
+    // Clock skew against PD's TSO beyond which the PD clock skew self-check
+    // reports `Warn` instead of `Ok`.
+    pub startup_max_clock_skew: ReadableDuration,
+    // When set, a stale peer's destroy is bracketed by begin/acked entries in
+    // `<data-dir>/segment_gc.log` recording the region's range and epoch, so
+    // an operator can see which ranges each `handle_destroy` call covered
+    // and cross-check against the engine store's own segment GC. See
+    // `core::segment_gc_journal` for why "acked" is only the proxy's own
+    // observation that the FFI call returned, not a genuine acknowledgement.
+    pub enable_segment_gc_journal: bool,
+    // Upper bound on snapshot pre-handles running at once, admitted via
+    // `core::snapshot_priority` in strict region-priority order (regions
+    // being rebuilt from scratch ahead of routine re-syncs) rather than the
+    // FIFO order the underlying thread pool would otherwise give them. 0
+    // disables the bound: unlimited concurrency, unordered, same as before
+    // this feature existed.
+    pub snapshot_prehandle_concurrency_limit: usize,
+    // Snapshots whose SST files sum to at least this size get their
+    // per-file chunk count journaled to `<data-dir>/chunked_snapshot.log` via
+    // `core::chunked_snapshot`, for visibility into how large an apply an
+    // interruption hit. Does not make the apply itself resumable -- see
+    // that module's doc comment for why not.
+    pub chunked_snapshot_apply_threshold: ReadableSize,
+    pub enable_chunked_snapshot_journal: bool,
+    // Parse `txn_types::WriteRef::txn_source` out of every CF_WRITE put and
+    // carry it alongside the decoded `WriteCmds` (see
+    // `proxy_ffi::WriteCmds::txn_sources`), so a caller holding the
+    // `WriteCmds` value -- rather than the generated FFI view, which has no
+    // room for it yet -- can tell a write replicated from another cluster
+    // (TiCDC, BR restore) apart from a locally-committed one. Off by default
+    // since it adds a parse per CF_WRITE put for metadata nothing consumes
+    // until the FFI view itself carries it.
+    pub enable_txn_source_tracking: bool,
+    // Decode the embedded RawKV API V2 expire timestamp out of every
+    // CF_DEFAULT put and carry it alongside the decoded `WriteCmds` (see
+    // `proxy_ffi::WriteCmds::expire_ts_marks`), same rationale and same
+    // "rides on the Rust side only until the FFI view grows room for it"
+    // caveat as `enable_txn_source_tracking`. Has no effect when the
+    // cluster's configured API version is not V2, since earlier versions
+    // never embed a TTL in the value. Off by default for the same reason:
+    // it adds a parse per CF_DEFAULT put for metadata nothing consumes yet.
+    pub enable_ttl_forwarding: bool,
+    // Minimum time between `core::ttl_forwarding` expiry-notification
+    // attempts; has no effect until `maybe_notify_expired_regions` has
+    // anything to call -- see that function's doc comment.
+    pub ttl_expiry_notify_interval: ReadableDuration,
+    // Batch and delay `on_empty_cmd` notifications for the duration of a
+    // detected leader-transfer storm, instead of forwarding every one
+    // immediately; see `core::leader_transfer_coalescing`. Off by default:
+    // coalescing trades immediate leadership-change visibility for write
+    // throughput during the storm, a tradeoff only worth making on stores
+    // that actually see mass transfers (e.g. rolling restarts).
+    pub enable_leader_transfer_coalescing: bool,
+    // Rolling window over which `on_empty_cmd` arrivals are counted to
+    // detect a storm.
+    pub leader_transfer_storm_window: ReadableDuration,
+    // Arrivals within `leader_transfer_storm_window` needed to declare a
+    // storm and start coalescing.
+    pub leader_transfer_storm_threshold: usize,
+    // Once a storm is declared, how long to keep coalescing before flushing
+    // every region's deferred notification and returning to immediate
+    // forwarding.
+    pub leader_transfer_coalesce_window: ReadableDuration,
+    // Selects the memory-mapped shared ring buffer transport (see
+    // `core::shm_transport`) instead of direct synchronous FFI calls for
+    // write forwarding. Has no effect yet: the engine store side of the
+    // capability handshake this needs doesn't exist, so
+    // `negotiate_transport_capability` always falls back to the direct
+    // transport regardless of this setting. Kept so the config shape is
+    // ready once that handshake exists.
+    pub enable_shm_transport: bool,
+    // When a single raft entry's mutations sum to at least this size, log
+    // the sub-batch plan `core::write_batch_split` would slice it into
+    // before it goes to `handle_write_raft_cmd` in one call, so an operator
+    // can see how large a single FFI call a huge write (e.g. batch DML)
+    // produced. 0 disables the check. Does not actually split the call --
+    // see that module for why not.
+    pub write_batch_split_threshold: ReadableSize,
+    // Replace the engine-store FFI call on the write path with a no-op sink
+    // that only marshals `WriteCmds` into its FFI view and discards it (see
+    // `core::write_path_dry_run`), for benchmarking the proxy's own
+    // forwarding throughput without an engine store attached. Selected at
+    // startup only -- this is a benchmarking mode, not something to flip
+    // live, since doing so stops persisting writes anywhere.
+    pub enable_write_dry_run: bool,
+    // How many recent snapshot apply attempts to keep per region (see
+    // `core::snapshot_apply_history`), queryable via
+    // `/debug/snapshot_apply_history/<region_id>` so an operator can see why
+    // a region's AddLearner snapshot applies keep failing instead of
+    // re-deriving it from raw logs. 0 disables recording entirely.
+    pub snapshot_apply_history_len: usize,
+    // Watch for the engine store restarting underneath this proxy (see
+    // `core::restart_detection`) and automatically pause snapshot acceptance
+    // while transport capability negotiation re-runs, instead of requiring a
+    // proxy restart too.
+    pub enable_restart_detection: bool,
+    // Minimum time between engine-store status polls done for restart
+    // detection, regardless of how often the call site fires.
+    pub restart_detection_poll_interval: ReadableDuration,
+    // How many regions `core::region_state_audit` samples per call to
+    // compare locally persisted `RegionLocalState` against PD's view. 0
+    // disables the audit entirely.
+    pub region_state_audit_sample_size: usize,
+    // Whether a divergence found by `core::region_state_audit` also gets
+    // queued in `pending_region_state_repairs` for a consumer to act on.
+    // Does not rewrite `RegionLocalState` itself -- see that module's doc
+    // comment for why not.
+    pub enable_region_state_auto_correct: bool,
+    // Skip forwarding writes whose key falls in one of
+    // `core::replication_filter`'s excluded key ranges (e.g. a table with no
+    // TiFlash replica sharing a region with one that does, after a merge)
+    // instead of shipping every mutation over the FFI regardless of whether
+    // the engine store has any replica interested in it. Off by default: the
+    // excluded range set starts empty, so this only takes effect once
+    // something populates it.
+    pub enable_key_range_replication_filter: bool,
+    // Reserved for `proxy_server::process_supervision`. Left unwired: this
+    // proxy is loaded as a library by the engine-store process's own `main`
+    // (see `run_proxy`) rather than the other way around, so there is no
+    // separate engine-store process for it to launch or restart -- see that
+    // module's doc comment for the full reasoning.
+    pub enable_engine_store_process_supervision: bool,
+    // Track `handle_write_raft_cmd`/`handle_admin_raft_cmd` calls while they
+    // are in flight (see `core::apply_watchdog`) so a call the engine store
+    // never returns from can be flagged via `/debug/apply_watchdog` instead
+    // of only being noticed by attaching a debugger. Off by default: it adds
+    // a hash-map insert/remove around every apply call.
+    pub enable_apply_pipeline_watchdog: bool,
+    // How long a `handle_write_raft_cmd`/`handle_admin_raft_cmd` call must
+    // have been running before `core::apply_watchdog` reports it as hung.
+    pub apply_pipeline_watchdog_deadline: ReadableDuration,
+    // Capture a Rust backtrace at the start of every tracked apply call, so
+    // a report from `enable_apply_pipeline_watchdog` shows which call site
+    // hung rather than just its name and duration. Off by default:
+    // `Backtrace::force_capture` is too expensive to pay on every apply call
+    // unconditionally; see `core::apply_watchdog`'s doc comment for why this
+    // is still only the entry stack, not a live snapshot of the stuck call.
+    pub enable_apply_watchdog_backtrace: bool,
+    // Route leader-change, epoch-update, and flush-request notifications
+    // through `core::notification_inbox` (per-region coalescing, latest
+    // epoch wins) instead of recording/sending each one directly. Off by
+    // default: it adds a hash-map lookup to those call sites.
+    pub enable_notification_inbox: bool,
+    // Max distinct regions `core::notification_inbox` tracks pending
+    // notifications for at once; notifications for a region beyond this cap
+    // are dropped rather than tracked. 0 disables the cap.
+    pub notification_inbox_capacity: usize,
+    // Max entries `core::freeze` buffers for a single frozen region before
+    // auto-unfreezing it (replaying what was buffered and letting further
+    // writes through normally), so a freeze left in place too long can't
+    // grow raft log retention without bound. 0 disables the cap.
+    pub freeze_max_buffered_entries: usize,
+    // Attempt to negotiate summarized `on_empty_cmd` notifications with the
+    // engine store (see `core::empty_cmd_summary`) instead of one callback
+    // per empty raft entry. Off by default: it only has any effect if the
+    // engine store build also advertises support for it.
+    pub enable_empty_cmd_summarization: bool,
+    // Durability CompactLog admission requires from a `try_flush_data` ack;
+    // see `FlushDurabilityLevel`. Defaults to `Durable`, matching the
+    // guarantee a plain `try_flush_data` success has always been treated as.
+    pub compact_log_flush_durability: FlushDurabilityLevel,
+    // Force apply state to be persisted immediately after Split, BatchSplit,
+    // PrepareMerge, CommitMerge, RollbackMerge, and ChangePeer/ChangePeerV2
+    // admin commands, even when `EngineStoreApplyRes` otherwise says a lazy
+    // flush policy can defer it (see `core::sync_commit`). Closes the crash
+    // window where region topology changed in memory but not on disk, as
+    // highlighted by `test_old_compact_log`'s ERROR-state comment. Off by
+    // default: it trades away the latency benefit lazy flushing was chosen
+    // for, on every topology-changing command.
+    pub force_sync_commit_for_metadata_cmds: bool,
+    // HTTP forward proxy (scheme `http://[user:pass@]host:port`) to route PD
+    // and peer gRPC traffic through, for nodes in a subnet that cannot reach
+    // PD/other stores directly. Empty disables it. Applied by setting the
+    // `grpc_proxy`/`https_proxy` environment variables gRPC-core's own HTTP
+    // CONNECT proxy mapper already honors, before the process builds its
+    // first `grpcio::Environment` -- there is no per-channel proxy knob to
+    // hook instead. A `socks5://` URL is accepted but not honored: gRPC-core
+    // only implements the HTTP CONNECT proxy mapper, not a SOCKS5 handshake,
+    // so it is logged and otherwise ignored rather than silently treated as
+    // plain HTTP.
+    pub forward_proxy_url: String,
+    // Preferred algorithm for checksumming pre-handled snapshot artifacts;
+    // see `ChecksumAlgorithm`. Only honored if the engine store negotiates
+    // support for it at startup -- otherwise silently falls back to
+    // `Crc32`, the one every engine store build can already verify.
+    pub snapshot_checksum_algorithm: ChecksumAlgorithm,
+    // During a rolling upgrade, peers on this store may still be backed by
+    // an engine store build that predates the pre_exec/post_exec
+    // persistence protocol and returns `EngineStoreApplyRes::None` for
+    // CompactLog unconditionally, the legacy behavior `test_old_compact_log`
+    // documents as an ERROR state under the new protocol. Set while such
+    // mixed-version peers may exist so that case is logged at `info` instead
+    // of `error` and excluded from `TIFLASH_APPLY_ERROR_COUNTER`; unset once
+    // the whole store's fleet has upgraded, so a real regression there goes
+    // back to paging. Off by default.
+    pub allow_legacy_compact_log_none: bool,
+    // Periodically defragment this store's own raft log and apply-state
+    // history (see `core::background_defrag`) instead of relying solely on
+    // normal `CompactLog` admission and the `record_replay_debt` trigger
+    // (active only with `enable_restart_detection` or after an operator
+    // rewind). Off by default: it is a proactive
+    // optimization, not something correctness depends on.
+    pub enable_background_defrag: bool,
+    // Minimum time between background-defrag sweep attempts, checked on
+    // every `CompactLog` admission.
+    pub background_defrag_check_interval: ReadableDuration,
+    // A sweep only runs when the store's estimated forwarded-write
+    // throughput is at or below this many bytes/sec -- the "low-traffic
+    // window" the sweep is meant to fit inside instead of competing with.
+    pub background_defrag_low_traffic_threshold: ReadableSize,
+    // Upper bound on how many regions a single sweep compacts, so a store
+    // with a huge number of regions gets swept gradually across many
+    // low-traffic windows rather than all its IO landing in one.
+    pub background_defrag_max_regions_per_run: usize,
+    // Read raft log entries ahead of a lagging region's engine store catching
+    // up into a bounded cache (see `core::entry_prefetch`), instead of only
+    // the `record_replay_debt` trigger (active only with
+    // `enable_restart_detection` or after an operator rewind) doing so on
+    // demand.
+    // Off by default: it is a proactive optimization, not something
+    // correctness depends on.
+    pub enable_entry_prefetch: bool,
+    // How many entries a single prefetch reads ahead of the engine store's
+    // last known applied index.
+    pub entry_prefetch_batch_size: usize,
+    // Store-wide cap on raft log entry bytes held in the prefetch cache
+    // across all regions; once this is reached, prefetching evicts cached
+    // entries of any region, lowest raft index first.
+    pub entry_prefetch_memory_quota: ReadableSize,
+    // Hold `core::segment_gc_journal`'s engine-store purge handoff back for
+    // this long after `on_region_changed` observes a learner peer's local
+    // destroy, instead of calling `handle_destroy` immediately (see
+    // `core::delayed_peer_destroy`). 0 disables the grace period and
+    // destroys are handed off immediately, as before this setting existed.
+    pub peer_destroy_grace_period: ReadableDuration,
+    // Worker threads `core::parallel_prehandle` splits one snapshot's SST
+    // files across for checksumming and sizing ahead of the single
+    // `pre_handle_snapshot` FFI call. 0 or 1 runs sequentially, same as
+    // before this setting existed; has no effect on a single-file snapshot,
+    // since there is nothing to split.
+    pub snapshot_prehandle_parallel_workers: usize,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -14,6 +486,72 @@ impl Default for EngineStoreConfig {
     fn default() -> Self {
         Self {
             enable_fast_add_peer: false,
+            allow_reuse_store_on_empty_dir: false,
+            failed_write_retry_interval: ReadableDuration::secs(5),
+            failed_write_retry_max_per_tick: 64,
+            enable_admin_decision_journal: false,
+            disk_full_enter_ratio: 0.0,
+            disk_full_recovery_ratio: 0.0,
+            ffi_request_timeout: ReadableDuration::secs(300),
+            enable_decode_pipeline: false,
+            decode_pipeline_quota: ReadableSize::mb(64),
+            flush_concurrency_limit: 0,
+            use_engine_store_region_stats: false,
+            enable_snapshot_apply_journal: false,
+            flush_retry: RetryPolicy::default(),
+            snapshot_apply_retry: RetryPolicy::default(),
+            enable_heartbeat_batching: false,
+            heartbeat_min_resend_interval: ReadableDuration::secs(60),
+            heartbeat_resend_spread: ReadableDuration::secs(30),
+            enable_remote_snapshot_apply: false,
+            enable_write_sequence_journal: false,
+            enable_dynamic_region_worker_scheduling: false,
+            region_worker_min_interval: ReadableDuration::secs(600),
+            enable_startup_self_check: false,
+            startup_self_check_fail_fast: false,
+            startup_max_clock_skew: ReadableDuration::secs(5),
+            enable_segment_gc_journal: false,
+            snapshot_prehandle_concurrency_limit: 0,
+            chunked_snapshot_apply_threshold: ReadableSize::gb(1),
+            enable_chunked_snapshot_journal: false,
+            enable_txn_source_tracking: false,
+            enable_ttl_forwarding: false,
+            ttl_expiry_notify_interval: ReadableDuration::secs(60),
+            enable_leader_transfer_coalescing: false,
+            leader_transfer_storm_window: ReadableDuration::secs(2),
+            leader_transfer_storm_threshold: 32,
+            leader_transfer_coalesce_window: ReadableDuration::secs(5),
+            enable_shm_transport: false,
+            write_batch_split_threshold: ReadableSize(0),
+            enable_write_dry_run: false,
+            snapshot_apply_history_len: 0,
+            enable_restart_detection: false,
+            restart_detection_poll_interval: ReadableDuration::secs(5),
+            region_state_audit_sample_size: 0,
+            enable_region_state_auto_correct: false,
+            enable_key_range_replication_filter: false,
+            enable_engine_store_process_supervision: false,
+            enable_apply_pipeline_watchdog: false,
+            apply_pipeline_watchdog_deadline: ReadableDuration::secs(30),
+            enable_apply_watchdog_backtrace: false,
+            enable_notification_inbox: false,
+            notification_inbox_capacity: 65536,
+            freeze_max_buffered_entries: 100_000,
+            enable_empty_cmd_summarization: false,
+            compact_log_flush_durability: FlushDurabilityLevel::default(),
+            force_sync_commit_for_metadata_cmds: false,
+            forward_proxy_url: String::new(),
+            snapshot_checksum_algorithm: ChecksumAlgorithm::default(),
+            allow_legacy_compact_log_none: false,
+            enable_background_defrag: false,
+            background_defrag_check_interval: ReadableDuration::secs(300),
+            background_defrag_low_traffic_threshold: ReadableSize::mb(1),
+            background_defrag_max_regions_per_run: 64,
+            enable_entry_prefetch: false,
+            entry_prefetch_batch_size: 256,
+            entry_prefetch_memory_quota: ReadableSize::mb(32),
+            peer_destroy_grace_period: ReadableDuration::secs(0),
+            snapshot_prehandle_parallel_workers: 0,
         }
     }
 }
@@ -22,3 +560,27 @@ impl Default for EngineStoreConfig {
 pub struct ProxyEngineConfigSet {
     pub engine_store: EngineStoreConfig,
 }
+
+/// Applies `cfg.forward_proxy_url` to the process environment, so every
+/// `grpcio::Environment` the process goes on to build -- for the PD client
+/// and for peer-to-peer raft traffic alike, since both share the same
+/// gRPC-core proxy resolution -- routes through it. Must run before the
+/// first such `Environment` is built; gRPC-core reads these variables once,
+/// at channel-creation time.
+pub fn apply_forward_proxy_env(cfg: &EngineStoreConfig) {
+    if cfg.forward_proxy_url.is_empty() {
+        return;
+    }
+    if cfg.forward_proxy_url.starts_with("socks5://")
+        || cfg.forward_proxy_url.starts_with("socks://")
+    {
+        tikv_util::error!(
+            "engine-store.forward-proxy-url is a SOCKS5 URL, which gRPC-core cannot use -- \
+             only HTTP CONNECT proxies are supported; ignoring it";
+            "forward_proxy_url" => &cfg.forward_proxy_url,
+        );
+        return;
+    }
+    std::env::set_var("grpc_proxy", &cfg.forward_proxy_url);
+    std::env::set_var("https_proxy", &cfg.forward_proxy_url);
+}
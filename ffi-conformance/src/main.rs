@@ -0,0 +1,76 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! `ffi-conformance`: drives `EngineStoreServerHelper`'s FFI contract
+//! (handshake, write/admin ordering, snapshot lifecycle, restart semantics)
+//! against a caller-supplied engine-store build and reports pass/fail per
+//! clause, so TiFlash CI can gate a build against this proxy's expectations
+//! instead of only discovering an incompatibility at integration-test time.
+//!
+//! The real calling convention runs the other way around: a TiFlash process
+//! dlopens this repo's own `raftstore-proxy` cdylib and calls its exported
+//! `run_proxy`, passing an `EngineStoreServerHelper` it constructed itself
+//! (see `proxy_server::proxy::run_proxy`) -- nothing in this codebase ever
+//! dlopens an engine-store build. For this binary to dlopen
+//! `--engine-store-lib` and pull a live `EngineStoreServerHelper` back out,
+//! that library needs to export `clauses::CONFORMANCE_ENTRY_POINT`, a symbol
+//! no build in this codebase provides today -- not even `mock-engine-store`,
+//! whose vtable is hard-coded to a mock test cluster's `cluster_ptr`, the
+//! same gap that made a standalone `--mock-engine-store` playground mode
+//! for `proxy_server` (tracked separately) not implementable without
+//! changes to the `mock-engine-store` crate. `clauses::load_helper`
+//! genuinely attempts the lookup via
+//! `dlsym` rather than assuming it will fail, so every clause needing a live
+//! helper is `Skipped` against today's builds only because the symbol really
+//! is absent, and a conformance-minded build that exports it starts passing
+//! with no changes needed on this side.
+
+mod clauses;
+
+use std::process;
+
+use clap::{App, Arg};
+use clauses::ClauseOutcome;
+
+fn main() {
+    let matches = App::new("ffi-conformance")
+        .about("FFI contract conformance gate for engine-store builds")
+        .arg(
+            Arg::with_name("engine-store-lib")
+                .long("engine-store-lib")
+                .value_name("PATH")
+                .takes_value(true)
+                .required(true)
+                .help("Shared library exporting the conformance entry point; see module docs"),
+        )
+        .get_matches();
+
+    let lib_path = matches.value_of("engine-store-lib").unwrap();
+    let helper = clauses::load_helper(lib_path);
+    let results = clauses::run_all(helper);
+
+    let mut failed = 0;
+    let mut skipped = 0;
+    for (clause, outcome) in &results {
+        match outcome {
+            ClauseOutcome::Pass => {
+                println!("PASS  [{:?}] {}", clause.category, clause.name);
+            }
+            ClauseOutcome::Fail(reason) => {
+                failed += 1;
+                println!("FAIL  [{:?}] {}: {}", clause.category, clause.name, reason);
+            }
+            ClauseOutcome::Skipped(reason) => {
+                skipped += 1;
+                println!("SKIP  [{:?}] {}: {}", clause.category, clause.name, reason);
+            }
+        }
+    }
+    println!(
+        "{} clauses: {} passed, {} failed, {} skipped",
+        results.len(),
+        results.len() - failed - skipped,
+        failed,
+        skipped,
+    );
+    process::exit(if failed > 0 { 1 } else { 0 });
+}
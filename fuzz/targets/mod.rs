@@ -8,6 +8,7 @@ mod util;
 use std::io::Cursor;
 
 use anyhow::Result;
+use protobuf::Message;
 use tidb_query_datatype::{
     codec::datum_codec::DatumFlagAndPayloadEncoder,
     expr::{EvalConfig, EvalContext},
@@ -15,6 +16,37 @@ use tidb_query_datatype::{
 
 use self::util::ReadLiteralExt;
 
+// engine_store_ffi's proxy side merges buffers handed across the FFI
+// boundary straight into these protobuf messages (see
+// `proxy_ffi::raftstore_proxy_helper_impls::ffi_batch_read_index` for
+// `ReadIndexRequest`, `engine_store_ffi::core::fast_add_peer` for
+// `RegionLocalState`/`RaftApplyState`) -- input that, unlike a raft
+// message that has already round-tripped through this store's own raft
+// log, can come straight from a mismatched or buggy engine-store build.
+// These targets exercise those same parses directly rather than pulling in
+// engine_store_ffi itself, since that crate needs RocksDB and the FFI
+// bindings to build and isn't meant to be linked into a fuzz binary.
+#[inline(always)]
+pub fn fuzz_ffi_read_index_request(data: &[u8]) -> Result<()> {
+    let mut req = kvproto::kvrpcpb::ReadIndexRequest::default();
+    let _ = req.merge_from_bytes(data);
+    Ok(())
+}
+
+#[inline(always)]
+pub fn fuzz_ffi_region_local_state(data: &[u8]) -> Result<()> {
+    let mut state = kvproto::raft_serverpb::RegionLocalState::default();
+    let _ = state.merge_from_bytes(data);
+    Ok(())
+}
+
+#[inline(always)]
+pub fn fuzz_ffi_raft_apply_state(data: &[u8]) -> Result<()> {
+    let mut state = kvproto::raft_serverpb::RaftApplyState::default();
+    let _ = state.merge_from_bytes(data);
+    Ok(())
+}
+
 #[inline(always)]
 pub fn fuzz_codec_bytes(data: &[u8]) -> Result<()> {
     let _ = tikv_util::codec::bytes::encode_bytes(data);
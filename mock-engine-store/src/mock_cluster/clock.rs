@@ -0,0 +1,39 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::time::Duration;
+
+use test_pd_client::TestPdClient;
+use txn_types::TimeStamp;
+
+/// Simulates an NTP-style jump between the cluster's PD-observed clock and
+/// wall-clock time, by pushing `pd_client`'s TSO ahead of or behind
+/// `TimeStamp::physical_now()` by `skew`. Exercises exactly the same
+/// PD-clock-skew signal the proxy's own `pd_clock_skew` startup self-check
+/// (see `engine_store_ffi::core::startup_check`) and any GC-safepoint logic
+/// sourced from PD's TSO observe in production.
+///
+/// This only moves the clock PD reports, via the existing
+/// `TestPdClient::set_tso`; it cannot jump the proxy process's own
+/// `Instant`/`SystemTime::now()` forward or back, since nothing in
+/// raftstore or engine_store_ffi reads time through an injectable clock
+/// abstraction -- introducing one would mean threading a `Clock` trait
+/// through every lease check, resolved-ts computation, and FFI request
+/// deadline in both crates, which is out of scope here. So this simulates
+/// PD-side skew, not a true system-wide time jump: raft lease expiry and
+/// `FfiRequestContext` deadlines, both computed from local `Instant::now()`,
+/// are unaffected.
+pub fn simulate_pd_clock_skew(pd_client: &TestPdClient, skew: Duration, pd_ahead: bool) {
+    let now_physical = TimeStamp::physical_now();
+    let skewed_physical = if pd_ahead {
+        now_physical.saturating_add(skew.as_millis() as u64)
+    } else {
+        now_physical.saturating_sub(skew.as_millis() as u64)
+    };
+    pd_client.set_tso(TimeStamp::compose(skewed_physical, 0));
+}
+
+/// Undoes [`simulate_pd_clock_skew`] by resetting `pd_client`'s TSO back to
+/// the current wall-clock time.
+pub fn reset_pd_clock_skew(pd_client: &TestPdClient) {
+    pd_client.set_tso(TimeStamp::compose(TimeStamp::physical_now(), 0));
+}
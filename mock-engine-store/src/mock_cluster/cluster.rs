@@ -57,7 +57,9 @@ use tikv_util::{
 use tokio::sync::oneshot;
 use txn_types::WriteBatchFlags;
 
-use super::{cluster_ext::*, common::*, config::Config, transport_simulate::Filter, util::*};
+use super::{
+    cluster_ext::*, common::*, config::Config, topology::*, transport_simulate::Filter, util::*,
+};
 
 // We simulate 3 or 5 nodes, each has a store.
 // Sometimes, we use fixed id to test, which means the id
@@ -170,6 +172,10 @@ pub struct Cluster<T: Simulator<TiFlashEngine>> {
     pub sim: Arc<RwLock<T>>,
     pub pd_client: Arc<TestPdClient>,
     resource_manager: Option<Arc<ResourceGroupManager>>,
+    // Kept alive for the cluster's lifetime so the failpoint it configures is
+    // removed on drop instead of leaking into whatever test runs next in the
+    // same process; see `ScopedFailpoint`.
+    _snapshot_sync_failpoint: ScopedFailpoint,
 }
 
 impl<T: Simulator<TiFlashEngine>> std::panic::UnwindSafe for Cluster<T> {}
@@ -185,7 +191,8 @@ impl<T: Simulator<TiFlashEngine>> Cluster<T> {
     ) -> Cluster<T> {
         test_util::init_log_for_test();
         // Force sync to enable Leader run as a Leader, rather than proxy
-        fail::cfg("apply_on_handle_snapshot_sync", "return").unwrap();
+        let snapshot_sync_failpoint =
+            ScopedFailpoint::cfg("apply_on_handle_snapshot_sync", "return");
 
         Cluster {
             cluster_ext: ClusterExt::default(),
@@ -209,9 +216,29 @@ impl<T: Simulator<TiFlashEngine>> Cluster<T> {
             sim,
             pd_client,
             resource_manager: Some(Arc::new(ResourceGroupManager::default())),
+            _snapshot_sync_failpoint: snapshot_sync_failpoint,
         }
     }
 
+    /// Like `new`, but tags the cluster with `namespace`, stored as
+    /// `ClusterExt::namespace` for a test that spins up several clusters in
+    /// one process -- e.g. to simulate cross-cluster replication -- to put in
+    /// its own log lines; nothing in the mock cluster logs or reads it. Store and region ids
+    /// are already cluster-local, so this does not by itself make two
+    /// clusters' failpoints independent; see `ScopedFailpoint`.
+    pub fn with_namespace(
+        namespace: impl Into<String>,
+        id: u64,
+        count: usize,
+        sim: Arc<RwLock<T>>,
+        pd_client: Arc<TestPdClient>,
+        proxy_cfg: ProxyConfig,
+    ) -> Cluster<T> {
+        let mut cluster = Self::new(id, count, sim, pd_client, proxy_cfg);
+        cluster.cluster_ext.namespace = namespace.into();
+        cluster
+    }
+
     pub fn id(&self) -> u64 {
         self.cfg.server.cluster_id
     }
@@ -795,6 +822,16 @@ impl<T: Simulator<TiFlashEngine>> Cluster<T> {
         self.pd_client.get_down_peers()
     }
 
+    /// A point-in-time snapshot of every region PD currently knows about --
+    /// its peers, epoch, and last-heartbeated leader -- as plain structs a
+    /// test can assert on, or diff against an earlier snapshot via
+    /// `ClusterTopology::diff`, to check invariants across a
+    /// split/merge/conf-change scenario (no overlapping ranges, every
+    /// expected learner present, and so on).
+    pub fn topology(&self) -> ClusterTopology {
+        ClusterTopology::from_pd(self.pd_client.get_all_regions())
+    }
+
     pub fn get_region_epoch(&self, region_id: u64) -> RegionEpoch {
         block_on(self.pd_client.get_region_by_id(region_id))
             .unwrap()
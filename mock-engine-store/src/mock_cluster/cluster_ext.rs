@@ -47,6 +47,13 @@ pub struct ClusterExt {
     pub ffi_helper_lst: Vec<FFIHelperSet>,
     ffi_helper_set: Arc<Mutex<HashMap<u64, FFIHelperSet>>>,
     pub test_data: TestData,
+    // Free-form label for a cluster created via `Cluster::with_namespace`.
+    // Nothing in the mock cluster logs or reads it; a test running several
+    // clusters at once (e.g. simulating cross-cluster replication) can put it
+    // in its own log lines to tell the clusters apart. Store/region ids are already cluster-local (each
+    // `ClusterExt` owns its own `ffi_helper_set`), so this is not needed for
+    // correctness, only for readability.
+    pub namespace: String,
 }
 
 impl ClusterExt {
@@ -117,6 +124,24 @@ impl ClusterExt {
         }
     }
 
+    /// A full, diffable text dump of every store's mock engine-store region
+    /// data and apply state, sorted by store id, for printing alongside a
+    /// failing assertion instead of stepping through each store by hand.
+    /// See `EngineStoreServer::dump_state` for what a single store's dump
+    /// covers and what it deliberately leaves out.
+    pub fn dump_state(&self) -> String {
+        let mut out = String::new();
+        self.access_ffi_helpers(&mut |m: &mut HashMap<u64, FFIHelperSet>| {
+            let mut store_ids: Vec<&u64> = m.keys().collect();
+            store_ids.sort_unstable();
+            for store_id in store_ids {
+                out.push_str(&m[store_id].engine_store_server.dump_state());
+                out.push('\n');
+            }
+        });
+        out
+    }
+
     /// We need to create FFIHelperSet while creating engine.
     /// The FFIHelperSet wil also be stored in ffi_helper_lst.
     pub fn create_ffi_helper_set<T: Simulator<TiFlashEngine>>(
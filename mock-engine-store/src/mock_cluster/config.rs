@@ -8,12 +8,21 @@ use std::{
 use tikv::config::TikvConfig;
 
 use super::ProxyConfig;
+use crate::mock_store::MockRegion;
 
 #[derive(Clone, Default)]
 pub struct MockConfig {
     pub panic_when_flush_no_found: Arc<AtomicBool>,
     /// Whether our mock server should compat new proxy.
     pub proxy_compat: bool,
+    /// Called with the region `ffi_pre_handle_snapshot` produced, after its
+    /// SSTs have been read into `MockRegion::data` but before
+    /// `ffi_apply_pre_handled_snapshot` applies it -- letting a test corrupt
+    /// or drop part of the pre-handled artifact (e.g. clear a cf's data to
+    /// simulate a missing SST, or perturb `apply_state`) to exercise the
+    /// proxy's snapshot-apply validation and error paths, which otherwise
+    /// only ever see a well-formed pre-handle result.
+    pub pre_handle_snapshot_hook: Option<Arc<dyn Fn(&mut MockRegion) + Send + Sync>>,
 }
 
 #[derive(Clone)]
@@ -0,0 +1,32 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+/// RAII guard around `fail::cfg`, so a cluster's failpoint overrides are
+/// automatically undone when whatever owns the guard is dropped, instead of
+/// leaking into the next test that happens to share the process.
+///
+/// This does not give two concurrently running clusters independent values
+/// for the *same* failpoint name -- `fail`'s registry is one flat, global
+/// map keyed by name, and `fail_point!` call sites don't take a
+/// cluster-specific namespace to key into it by; that would mean threading a
+/// namespace through every `fail_point!` call site in raftstore and
+/// engine_store_ffi, which is out of scope here. What this does fix is the
+/// common failure mode of one cluster's setup leaving a failpoint configured
+/// that a later, unrelated cluster in the same process then unexpectedly
+/// trips over.
+pub struct ScopedFailpoint {
+    name: String,
+}
+
+impl ScopedFailpoint {
+    pub fn cfg(name: impl Into<String>, actions: &str) -> Self {
+        let name = name.into();
+        fail::cfg(&name, actions).unwrap();
+        ScopedFailpoint { name }
+    }
+}
+
+impl Drop for ScopedFailpoint {
+    fn drop(&mut self) {
+        fail::remove(&self.name);
+    }
+}
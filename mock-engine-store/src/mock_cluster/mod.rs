@@ -1,18 +1,24 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
 
+pub mod clock;
 pub mod cluster;
 pub mod cluster_ext;
 mod common;
 pub mod config;
+pub mod failpoint;
 pub mod node;
 pub mod server;
+pub mod topology;
 pub mod transport_simulate;
 pub mod util;
 
+pub use clock::*;
 pub use cluster::*;
 pub use cluster_ext::*;
 pub use common::*;
+pub use failpoint::*;
 pub use config::{Config, MockConfig};
+pub use topology::*;
 pub use test_raftstore::{
     is_error_response, make_cb, new_admin_request, new_delete_cmd, new_peer, new_put_cf_cmd,
     new_put_cmd, new_region_leader_cmd, new_request, new_status_request, new_store,
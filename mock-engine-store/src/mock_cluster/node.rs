@@ -16,6 +16,7 @@ use kvproto::{
     raft_cmdpb::*,
     raft_serverpb::{self, RaftMessage},
 };
+use pd_client::PdClient;
 use protobuf::Message;
 use raft::{eraftpb::MessageType, SnapshotStatus};
 use raftstore::{
@@ -337,6 +338,9 @@ impl Simulator<TiFlashEngine> for NodeCluster {
             engine_store_cfg: cfg.proxy_cfg.engine_store.clone(),
             pd_endpoints: cfg.pd.endpoints.clone(),
             snap_handle_pool_size: cfg.proxy_cfg.raft_store.snap_handle_pool_size,
+            feature_gate: self.pd_client.feature_gate().clone(),
+            resource_manager: None,
+            api_version: cfg.storage.api_version(),
         };
         let tiflash_ob = engine_store_ffi::observer::TiFlashObserver::new(
             node_id,
@@ -0,0 +1,141 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+use kvproto::metapb;
+
+/// A peer within a [`RegionTopology`] snapshot, trimmed to what topology
+/// invariants care about.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerTopology {
+    pub id: u64,
+    pub store_id: u64,
+    pub role: metapb::PeerRole,
+}
+
+/// One region's shape at the moment a [`ClusterTopology`] was taken: its key
+/// range, epoch, peers, and last-heartbeated leader.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionTopology {
+    pub id: u64,
+    pub start_key: Vec<u8>,
+    pub end_key: Vec<u8>,
+    pub epoch: metapb::RegionEpoch,
+    pub peers: Vec<PeerTopology>,
+    pub leader_store_id: Option<u64>,
+}
+
+impl RegionTopology {
+    fn from_pd(region: metapb::Region, leader: Option<metapb::Peer>) -> RegionTopology {
+        RegionTopology {
+            id: region.get_id(),
+            start_key: region.get_start_key().to_vec(),
+            end_key: region.get_end_key().to_vec(),
+            epoch: region.get_region_epoch().clone(),
+            peers: region
+                .get_peers()
+                .iter()
+                .map(|p| PeerTopology {
+                    id: p.get_id(),
+                    store_id: p.get_store_id(),
+                    role: p.get_role(),
+                })
+                .collect(),
+            leader_store_id: leader.map(|p| p.get_store_id()),
+        }
+    }
+}
+
+/// A point-in-time snapshot of every region PD knows about, returned by
+/// `Cluster::topology`. Plain data plus [`ClusterTopology::diff`] so tests
+/// can assert on what changed across a split/merge/conf-change rather than
+/// re-deriving it from raw `metapb::Region`s themselves.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClusterTopology {
+    pub regions: Vec<RegionTopology>,
+}
+
+impl ClusterTopology {
+    pub(crate) fn from_pd(regions: Vec<(metapb::Region, Option<metapb::Peer>)>) -> ClusterTopology {
+        ClusterTopology {
+            regions: regions
+                .into_iter()
+                .map(|(region, leader)| RegionTopology::from_pd(region, leader))
+                .collect(),
+        }
+    }
+
+    /// Regions whose key range `[start_key, end_key)` overlaps another
+    /// region's, an invariant that should never hold outside the brief
+    /// window a split or merge is actually in flight. Regions are sorted by
+    /// start key and each is compared with the next one only, so when three
+    /// or more regions overlap not every overlapping pair is listed. Two
+    /// entries with the same id (the same region read twice) are never
+    /// reported as an overlap with itself.
+    pub fn overlapping_regions(&self) -> Vec<(u64, u64)> {
+        let mut sorted: Vec<&RegionTopology> = self.regions.iter().collect();
+        sorted.sort_by(|a, b| a.start_key.cmp(&b.start_key));
+        let mut overlaps = vec![];
+        for pair in sorted.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let a_unbounded = a.end_key.is_empty();
+            if a.id != b.id && (a_unbounded || a.end_key > b.start_key) {
+                overlaps.push((a.id, b.id));
+            }
+        }
+        overlaps
+    }
+
+    /// Regions present in `self` but missing a peer on `store_id`.
+    pub fn regions_missing_store(&self, store_id: u64) -> Vec<u64> {
+        self.regions
+            .iter()
+            .filter(|r| !r.peers.iter().any(|p| p.store_id == store_id))
+            .map(|r| r.id)
+            .collect()
+    }
+
+    /// Diffs two snapshots of the same cluster by region id: regions only in
+    /// `other` (added since `self`), only in `self` (removed since, e.g. a
+    /// merge), and present in both but with a different epoch, peer set, or
+    /// leader (changed).
+    pub fn diff(&self, other: &ClusterTopology) -> TopologyDiff {
+        let before: std::collections::HashMap<u64, &RegionTopology> =
+            self.regions.iter().map(|r| (r.id, r)).collect();
+        let after: std::collections::HashMap<u64, &RegionTopology> =
+            other.regions.iter().map(|r| (r.id, r)).collect();
+
+        let mut added: Vec<u64> = after
+            .keys()
+            .filter(|id| !before.contains_key(*id))
+            .copied()
+            .collect();
+        let mut removed: Vec<u64> = before
+            .keys()
+            .filter(|id| !after.contains_key(*id))
+            .copied()
+            .collect();
+        let mut changed: Vec<u64> = before
+            .iter()
+            .filter_map(|(id, before_region)| match after.get(id) {
+                Some(after_region) if after_region != before_region => Some(*id),
+                _ => None,
+            })
+            .collect();
+        added.sort_unstable();
+        removed.sort_unstable();
+        changed.sort_unstable();
+        TopologyDiff {
+            added,
+            removed,
+            changed,
+        }
+    }
+}
+
+/// The result of [`ClusterTopology::diff`]: region ids added, removed, and
+/// changed between an earlier and a later snapshot.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TopologyDiff {
+    pub added: Vec<u64>,
+    pub removed: Vec<u64>,
+    pub changed: Vec<u64>,
+}
@@ -13,6 +13,7 @@ use collections::{HashMap, HashSet};
 use crossbeam::channel::TrySendError;
 use engine_rocks::RocksSnapshot;
 use kvproto::{raft_cmdpb::RaftCmdRequest, raft_serverpb::RaftMessage};
+use protobuf::Message as _;
 use raft::eraftpb::MessageType;
 use raftstore::{
     router::{LocalReadRouter, RaftStoreRouter},
@@ -845,3 +846,115 @@ impl Filter for DropMessageFilter {
         Ok(())
     }
 }
+
+/// Models WAN-like conditions -- extra latency drawn from a range, a
+/// bandwidth cap, and occasional reordering -- for raft messages sent from
+/// `from_store_id` to `to_store_id`, so tests can exercise snapshot-timeout
+/// handling and read-index latency under cross-AZ-like conditions without a
+/// real network between mock nodes. Unlike [`DelayFilter`] (fixed delay,
+/// every message) or [`RandomLatencyFilter`] (delay-or-not, every store
+/// pair), this scopes to one ordered pair and layers all three effects, the
+/// way an actual WAN link would.
+#[derive(Clone)]
+pub struct WanConditionFilter {
+    from_store_id: u64,
+    to_store_id: u64,
+    min_latency: time::Duration,
+    max_latency: time::Duration,
+    bandwidth_bytes_per_sec: u64,
+    reorder_rate: u32,
+    // At most one message held back for reordering at a time: a message
+    // picked to reorder swaps places with whichever qualifying message
+    // follows it, rather than being buffered indefinitely, so a WAN link
+    // simulated as permanently reordering still makes progress.
+    held: Arc<Mutex<Option<RaftMessage>>>,
+}
+
+impl WanConditionFilter {
+    pub fn new(from_store_id: u64, to_store_id: u64) -> WanConditionFilter {
+        WanConditionFilter {
+            from_store_id,
+            to_store_id,
+            min_latency: time::Duration::from_millis(0),
+            max_latency: time::Duration::from_millis(0),
+            bandwidth_bytes_per_sec: 0,
+            reorder_rate: 0,
+            held: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Extra per-message latency, drawn uniformly from `[min, max]`. `max`
+    /// is raised to `min` if given smaller.
+    #[must_use]
+    pub fn latency(mut self, min: time::Duration, max: time::Duration) -> WanConditionFilter {
+        self.min_latency = min;
+        self.max_latency = max.max(min);
+        self
+    }
+
+    /// Caps throughput between the pair: a message's simulated transmit time
+    /// is its encoded size divided by this rate, added on top of `latency`.
+    /// 0 (the default) disables the cap.
+    #[must_use]
+    pub fn bandwidth(mut self, bytes_per_sec: u64) -> WanConditionFilter {
+        self.bandwidth_bytes_per_sec = bytes_per_sec;
+        self
+    }
+
+    /// Percent chance (0-100) a qualifying message swaps places with the
+    /// next one, modeling out-of-order delivery. 0 (the default) disables
+    /// reordering.
+    #[must_use]
+    pub fn reorder_rate(mut self, rate: u32) -> WanConditionFilter {
+        self.reorder_rate = rate.min(100);
+        self
+    }
+
+    fn matches(&self, m: &RaftMessage) -> bool {
+        m.get_from_peer().get_store_id() == self.from_store_id
+            && m.get_to_peer().get_store_id() == self.to_store_id
+    }
+
+    fn simulated_delay(&self, m: &RaftMessage) -> time::Duration {
+        let spread = self.max_latency.saturating_sub(self.min_latency);
+        let jitter = if spread.is_zero() {
+            time::Duration::from_millis(0)
+        } else {
+            time::Duration::from_nanos(rand::random::<u64>() % (spread.as_nanos() as u64 + 1))
+        };
+        let transmit = if self.bandwidth_bytes_per_sec > 0 {
+            time::Duration::from_secs_f64(
+                m.compute_size() as f64 / self.bandwidth_bytes_per_sec as f64,
+            )
+        } else {
+            time::Duration::from_millis(0)
+        };
+        self.min_latency + jitter + transmit
+    }
+}
+
+impl Filter for WanConditionFilter {
+    fn before(&self, msgs: &mut Vec<RaftMessage>) -> Result<()> {
+        let mut out = Vec::with_capacity(msgs.len());
+        let mut held = self.held.lock().unwrap();
+        for m in msgs.drain(..) {
+            if !self.matches(&m) {
+                out.push(m);
+                continue;
+            }
+            thread::sleep(self.simulated_delay(&m));
+            let swap = self.reorder_rate > 0 && rand::random::<u32>() % 100 < self.reorder_rate;
+            if let Some(prev) = held.take() {
+                out.push(prev);
+            }
+            if swap {
+                *held = Some(m);
+            } else {
+                out.push(m);
+            }
+        }
+        check_messages(&out)?;
+        *msgs = out;
+        Ok(())
+    }
+}
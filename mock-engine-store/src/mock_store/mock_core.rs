@@ -25,6 +25,35 @@ impl MockRegion {
         self.applied_term = term;
     }
 
+    /// One line per region: id, peer, epoch, applied index/term and per-cf
+    /// key counts, but not the keys/values themselves -- meant to be
+    /// diffable across a whole cluster dump without drowning the difference
+    /// that actually matters in row noise. See `EngineStoreServer::dump_state`.
+    pub fn dump_summary(&self) -> String {
+        // Cf array index order matches `interfaces_ffi::ColumnFamilyType`:
+        // Lock = 0, Write = 1, Default = 2.
+        format!(
+            "region={} peer={} epoch=(conf_ver={},version={}) \
+             applied=(index={},term={}) keys=(lock={},write={},default={}) \
+             pending_write=(lock={},write={},default={}) pending_delete=(lock={},write={},default={})",
+            self.region.get_id(),
+            self.peer.get_id(),
+            self.region.get_region_epoch().get_conf_ver(),
+            self.region.get_region_epoch().get_version(),
+            self.apply_state.get_applied_index(),
+            self.applied_term,
+            self.data[0].len(),
+            self.data[1].len(),
+            self.data[2].len(),
+            self.pending_write[0].len(),
+            self.pending_write[1].len(),
+            self.pending_write[2].len(),
+            self.pending_delete[0].len(),
+            self.pending_delete[1].len(),
+            self.pending_delete[2].len(),
+        )
+    }
+
     pub fn new(meta: kvproto::metapb::Region) -> Self {
         MockRegion {
             region: meta,
@@ -40,6 +40,41 @@ impl EngineStoreServer {
         }
     }
 
+    /// A compact, diffable dump of this store's region data and apply
+    /// states -- a `store=` header line, one summary line per region, then
+    /// one pre-handle/fast-add-peer counter line per region with recorded
+    /// stats, each group sorted by region id so two dumps of the same
+    /// logical state always compare equal regardless of `HashMap` iteration
+    /// order. Meant for printing on test failure, not
+    /// for parsing back.
+    ///
+    /// Does not cover in-flight FFI tasks (e.g. a background snapshot
+    /// pre-handle): those live on the real `ProxyForwarder`, which this mock
+    /// server has no handle to, so a caller wanting that context needs to
+    /// dump `ProxyForwarder::pre_handle_snapshot_ctx` separately.
+    pub fn dump_state(&self) -> String {
+        let mut region_ids: Vec<&RegionId> = self.kvstore.keys().collect();
+        region_ids.sort_unstable();
+        let mut lines = vec![format!("store={}", self.id)];
+        for region_id in region_ids {
+            let region = &self.kvstore[region_id];
+            lines.push(format!("  {}", region.dump_summary()));
+        }
+        let region_states = self.region_states.borrow();
+        let mut stat_ids: Vec<&RegionId> = region_states.keys().collect();
+        stat_ids.sort_unstable();
+        for region_id in stat_ids {
+            let stats = &region_states[region_id];
+            lines.push(format!(
+                "  region={} pre_handle_count={} fast_add_peer_count={}",
+                region_id,
+                stats.pre_handle_count.load(Ordering::SeqCst),
+                stats.fast_add_peer_count.load(Ordering::SeqCst),
+            ));
+        }
+        lines.join("\n")
+    }
+
     pub fn mutate_region_states<F: Fn(&mut RegionStats)>(&self, region_id: RegionId, f: F) {
         let has = self.region_states.borrow().contains_key(&region_id);
         if !has {
@@ -143,6 +143,9 @@ pub unsafe extern "C" fn ffi_pre_handle_snapshot(
         region.apply_state.mut_truncated_state().set_index(index);
         region.apply_state.mut_truncated_state().set_term(term);
     }
+    if let Some(hook) = &(*store.engine_store_server).mock_cfg.pre_handle_snapshot_hook {
+        hook(region.as_mut());
+    }
     interfaces_ffi::RawCppPtr {
         ptr: Box::into_raw(Box::new(PrehandledSnapshot {
             region: Some(*region),
@@ -0,0 +1,41 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+use criterion::*;
+use proxy_ffi::{
+    domain_impls::WriteCmds,
+    interfaces::root::DB::{ColumnFamilyType, WriteCmdType},
+};
+
+const BATCH_SIZE: usize = 128;
+
+fn bench_fresh_allocation(c: &mut Criterion) {
+    c.bench_function("write_cmds_with_capacity", |b| {
+        b.iter(|| {
+            let mut cmds = WriteCmds::with_capacity(BATCH_SIZE);
+            for i in 0..BATCH_SIZE {
+                let key = i.to_le_bytes();
+                let val = i.to_le_bytes();
+                cmds.push(&key, &val, WriteCmdType::Put, ColumnFamilyType::Default);
+            }
+            black_box(cmds.gen_view());
+        })
+    });
+}
+
+fn bench_pooled(c: &mut Criterion) {
+    c.bench_function("write_cmds_take_pooled", |b| {
+        b.iter(|| {
+            let mut cmds = WriteCmds::take_pooled(BATCH_SIZE);
+            for i in 0..BATCH_SIZE {
+                let key = i.to_le_bytes();
+                let val = i.to_le_bytes();
+                cmds.push(&key, &val, WriteCmdType::Put, ColumnFamilyType::Default);
+            }
+            black_box(cmds.gen_view());
+            cmds.recycle();
+        })
+    });
+}
+
+criterion_group!(benches, bench_fresh_allocation, bench_pooled);
+criterion_main!(benches);
@@ -1,6 +1,6 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
 
-use std::pin::Pin;
+use std::{cell::RefCell, pin::Pin};
 
 use engine_traits::{CF_DEFAULT, CF_LOCK, CF_WRITE};
 
@@ -59,12 +59,73 @@ impl From<usize> for ColumnFamilyType {
     }
 }
 
+/// Marks the position of a logical transaction (prewrite/commit batch)
+/// boundary inside a `WriteCmds`, so the engine store can apply the
+/// mutations between `begin` and the matching `end` atomically instead of
+/// making them visible one write at a time.
+///
+/// This is metadata carried alongside `WriteCmds` on the Rust side; it is
+/// not yet part of the generated `WriteCmdsView` ABI (see gen-proxy-ffi),
+/// so today it is only usable by callers that hold the `WriteCmds` value
+/// itself rather than a raw view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxnBoundary {
+    pub start_ts: u64,
+    pub begin: bool,
+    // Index into the write cmds that this boundary applies before.
+    pub pos: usize,
+}
+
+/// Records that the write at `pos` originated from `txn_source` (TiCDC,
+/// BR restore, ...; see `txn_types::Write::txn_source`), so a caller with
+/// the `WriteCmds` value can tell a replicated-from-elsewhere write apart
+/// from a locally-committed one.
+///
+/// Same caveat as [`TxnBoundary`]: this rides alongside `WriteCmds` on the
+/// Rust side only. `WriteCmdsView` (the generated ABI, see gen-proxy-ffi)
+/// has no per-write source field, so the engine store cannot see this yet
+/// -- extending it needs a `gen-proxy-ffi` run against a TiFlash header that
+/// declares the new view shape, not done here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxnSourceMark {
+    pub txn_source: u64,
+    pub pos: usize,
+}
+
+/// Records that the write at `pos` carries a RawKV API V2 expire timestamp
+/// (unix seconds), decoded from its value's embedded TTL, so the engine
+/// store can drop the key once it is reached.
+///
+/// Same caveat as [`TxnBoundary`] and [`TxnSourceMark`]: this rides alongside
+/// `WriteCmds` on the Rust side only. `WriteCmdsView` has no per-write
+/// expire-ts field, so the engine store cannot see this yet -- extending it
+/// needs a `gen-proxy-ffi` run against a TiFlash header that declares the
+/// new view shape, not done here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpireTsMark {
+    pub expire_ts: u64,
+    pub pos: usize,
+}
+
 #[derive(Default)]
 pub struct WriteCmds {
     keys: Vec<BaseBuffView>,
     vals: Vec<BaseBuffView>,
     cmd_type: Vec<WriteCmdType>,
     cf: Vec<ColumnFamilyType>,
+    txn_boundaries: Vec<TxnBoundary>,
+    txn_sources: Vec<TxnSourceMark>,
+    expire_ts_marks: Vec<ExpireTsMark>,
+}
+
+// Reused `WriteCmds` buffers, keyed off the calling thread, so the hot
+// per-raft-entry path (`post_exec_query`) doesn't re-allocate four `Vec`s on
+// every apply. Bounded so a burst of unusually large batches doesn't pin
+// their capacity in the pool forever.
+const WRITE_CMDS_POOL_CAP: usize = 8;
+
+thread_local! {
+    static WRITE_CMDS_POOL: RefCell<Vec<WriteCmds>> = RefCell::new(Vec::new());
 }
 
 impl WriteCmds {
@@ -74,6 +135,9 @@ impl WriteCmds {
             vals: Vec::<BaseBuffView>::with_capacity(cap),
             cmd_type: Vec::<WriteCmdType>::with_capacity(cap),
             cf: Vec::<ColumnFamilyType>::with_capacity(cap),
+            txn_boundaries: Vec::new(),
+            txn_sources: Vec::new(),
+            expire_ts_marks: Vec::new(),
         }
     }
 
@@ -81,6 +145,45 @@ impl WriteCmds {
         WriteCmds::default()
     }
 
+    /// Like `with_capacity`, but reuses a `WriteCmds` previously returned to
+    /// the pool via `recycle` on this thread when one is available, instead
+    /// of allocating fresh `Vec`s.
+    pub fn take_pooled(cap: usize) -> WriteCmds {
+        let pooled = WRITE_CMDS_POOL.with(|pool| pool.borrow_mut().pop());
+        match pooled {
+            Some(mut cmds) => {
+                cmds.keys.reserve(cap.saturating_sub(cmds.keys.capacity()));
+                cmds.vals.reserve(cap.saturating_sub(cmds.vals.capacity()));
+                cmds
+            }
+            None => WriteCmds::with_capacity(cap),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.keys.clear();
+        self.vals.clear();
+        self.cmd_type.clear();
+        self.cf.clear();
+        self.txn_boundaries.clear();
+        self.txn_sources.clear();
+        self.expire_ts_marks.clear();
+    }
+
+    /// Returns this `WriteCmds`' buffers to the thread-local pool for reuse
+    /// by a later `take_pooled` call on the same thread. Must only be
+    /// called once the engine store is done reading any view generated from
+    /// it, since `gen_view` hands out raw pointers into these buffers.
+    pub fn recycle(mut self) {
+        self.clear();
+        WRITE_CMDS_POOL.with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if pool.len() < WRITE_CMDS_POOL_CAP {
+                pool.push(self);
+            }
+        });
+    }
+
     pub fn push(&mut self, key: &[u8], val: &[u8], cmd_type: WriteCmdType, cf: ColumnFamilyType) {
         self.keys.push(key.into());
         self.vals.push(val.into());
@@ -88,6 +191,48 @@ impl WriteCmds {
         self.cf.push(cf);
     }
 
+    /// Records that the write at the current position starts (or ends) a
+    /// logical transaction batch with the given `start_ts`.
+    pub fn mark_txn_boundary(&mut self, start_ts: u64, begin: bool) {
+        self.txn_boundaries.push(TxnBoundary {
+            start_ts,
+            begin,
+            pos: self.cmd_type.len(),
+        });
+    }
+
+    pub fn txn_boundaries(&self) -> &[TxnBoundary] {
+        &self.txn_boundaries
+    }
+
+    /// Records that the write about to be pushed (i.e. at the current
+    /// `len()`) came from `txn_source`. Call this immediately before the
+    /// matching `push`.
+    pub fn mark_txn_source(&mut self, txn_source: u64) {
+        self.txn_sources.push(TxnSourceMark {
+            txn_source,
+            pos: self.cmd_type.len(),
+        });
+    }
+
+    pub fn txn_sources(&self) -> &[TxnSourceMark] {
+        &self.txn_sources
+    }
+
+    /// Records that the write about to be pushed (i.e. at the current
+    /// `len()`) expires at `expire_ts` (unix seconds). Call this immediately
+    /// before the matching `push`.
+    pub fn mark_expire_ts(&mut self, expire_ts: u64) {
+        self.expire_ts_marks.push(ExpireTsMark {
+            expire_ts,
+            pos: self.cmd_type.len(),
+        });
+    }
+
+    pub fn expire_ts_marks(&self) -> &[ExpireTsMark] {
+        &self.expire_ts_marks
+    }
+
     pub fn len(&self) -> usize {
         self.cmd_type.len()
     }
@@ -116,6 +261,22 @@ impl RaftCmdHeader {
     }
 }
 
+/// Commit index and wall-clock apply time for a single write forward, kept
+/// alongside a `RaftCmdHeader` on the Rust side only.
+///
+/// `RaftCmdHeader` is bindgen-generated `#[repr(C)]` ABI shared with the
+/// engine store's C++ header (see `interfaces_ffi::RaftCmdHeader`); adding
+/// fields to it needs a `gen-proxy-ffi` run against an updated header, not
+/// done here. Until then the engine store still has to call back into the
+/// proxy for this, same as today -- this struct only makes the values
+/// available to Rust-side callers such as tracing, exactly like
+/// [`TxnBoundary`] and [`TxnSourceMark`] above.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtendedRaftCmdMeta {
+    pub commit_index: u64,
+    pub apply_time_ms: u64,
+}
+
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
 pub enum RawRustPtrType {
@@ -9,10 +9,10 @@ use super::{
     interfaces_ffi,
     interfaces_ffi::{
         BaseBuffView, ColumnFamilyType, CppStrWithView, EngineStoreApplyRes,
-        EngineStoreServerHelper, EngineStoreServerStatus, FastAddPeerRes, HttpRequestRes,
-        RaftCmdHeader, RaftStoreProxyFFIHelper, RawCppPtr, RawCppPtrCarr, RawCppPtrType,
-        RawCppStringPtr, RawVoidPtr, SpecialCppPtrType, StoreStats, RAFT_STORE_PROXY_MAGIC_NUMBER,
-        RAFT_STORE_PROXY_VERSION,
+        EngineStoreServerHelper, EngineStoreServerStatus, FastAddPeerRes, FlushedState,
+        HttpRequestRes, RaftCmdHeader, RaftStoreProxyFFIHelper, RawCppPtr, RawCppPtrCarr,
+        RawCppPtrType, RawCppStringPtr, RawVoidPtr, SpecialCppPtrType, StoreStats,
+        RAFT_STORE_PROXY_MAGIC_NUMBER, RAFT_STORE_PROXY_VERSION,
     },
     UnwrapExternCFunc, WriteCmds,
 };
@@ -99,6 +99,15 @@ impl EngineStoreServerHelper {
         unsafe { (self.fn_handle_get_engine_store_server_status.into_inner())(self.inner) }
     }
 
+    /// The engine store's own last-applied index/term for `region_id`, as of
+    /// its most recent flush -- the real per-region applied-index signal
+    /// `core::replay_debt` needs, rather than a substitute derived from some
+    /// other trigger.
+    pub fn get_flushed_state(&self, region_id: u64) -> FlushedState {
+        debug_assert!(self.fn_get_flushed_state.is_some());
+        unsafe { (self.fn_get_flushed_state.into_inner())(self.inner, region_id) }
+    }
+
     pub fn handle_set_proxy(&self, proxy: *const RaftStoreProxyFFIHelper) {
         debug_assert!(self.fn_atomic_update_proxy.is_some());
         unsafe { (self.fn_atomic_update_proxy.into_inner())(self.inner, proxy as *mut _) }
@@ -24,6 +24,9 @@ pub mod read_index_helper;
 pub mod sst_reader_impls;
 pub mod utils;
 pub mod apply_router_helper;
+// Static layout checks for the FFI structs the Rust side owns the shape of;
+// see the module doc comment for why this isn't full header generation.
+pub mod codegen;
 
 pub use self::{
     basic_ffi_impls::*, domain_impls::*, encryption_impls::*, engine_store_helper_impls::*,
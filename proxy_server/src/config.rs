@@ -5,12 +5,12 @@ use std::{collections::HashSet, iter::FromIterator, path::Path};
 use engine_store_ffi::EngineStoreConfig;
 use engine_traits::{CF_DEFAULT, CF_LOCK, CF_WRITE};
 use itertools::Itertools;
-use online_config::OnlineConfig;
+use online_config::{ConfigChange, ConfigValue, OnlineConfig};
 use serde_derive::{Deserialize, Serialize};
 use serde_with::with_prefix;
 use tikv::config::{TikvConfig, LAST_CONFIG_FILE};
 use tikv_util::{
-    config::{ReadableDuration, ReadableSize},
+    config::{self, ReadableDuration, ReadableSize},
     crit,
     sys::SysQuota,
 };
@@ -290,6 +290,81 @@ impl Default for ProxyConfig {
 }
 
 impl ProxyConfig {
+    /// Checks constraints that span more than one section, which
+    /// `#[derive(OnlineConfig)]` cannot express on a single field. Called
+    /// once after the config is fully assembled, in addition to per-section
+    /// defaults.
+    pub fn validate(&self) -> Result<(), String> {
+        // `check_addr` parses both "IPv4:Port" and "[IPv6]:Port" (see
+        // `tikv_util::config::check_addr`), so IPv6 literals are already
+        // accepted here and by every listener below that binds via
+        // `SocketAddr::from_str` -- Linux dual-stacks a `[::]:port` bind by
+        // default, same as the main tikv-server status/gRPC listeners.
+        config::check_addr(&self.server.addr).map_err(|e| e.to_string())?;
+        if !self.server.status_addr.is_empty() {
+            config::check_addr(&self.server.status_addr).map_err(|e| e.to_string())?;
+        }
+        if !self.server.advertise_addr.is_empty() {
+            config::check_addr(&self.server.advertise_addr).map_err(|e| e.to_string())?;
+        }
+        if !self.server.advertise_status_addr.is_empty() {
+            config::check_addr(&self.server.advertise_status_addr).map_err(|e| e.to_string())?;
+        }
+        // engine-addr may still be empty here: `proxy::run_proxy` calls
+        // `validate()` twice, once right after `gen_proxy_config` reads the
+        // config file (this call, which may see it empty) and once more
+        // after `setup::overwrite_config_with_cmd_args` has had a chance to
+        // fill it in from `--engine-addr`/`--advertise-engine-addr`, which
+        // is how it is actually set in production. It is advertised to PD
+        // as the engine store's peer address (see
+        // `TiKvServer::init_servers`), so once set it must not be an
+        // unspecified host like `0.0.0.0` or `::` even though that would be
+        // a legitimate *listen* address.
+        if !self.server.engine_addr.is_empty()
+            && config::check_addr(&self.server.engine_addr).map_err(|e| e.to_string())?
+        {
+            return Err(format!(
+                "server.engine-addr should not be an unspecified address: {:?}",
+                self.server.engine_addr
+            ));
+        }
+        if self.raft_store.apply_low_priority_pool_size == 0 {
+            return Err("raftstore.apply-low-priority-pool-size should not be 0".to_string());
+        }
+        if self.raft_store.snap_handle_pool_size == 0 {
+            return Err("raftstore.snap-handle-pool-size should not be 0".to_string());
+        }
+        if !(0.0..1.0).contains(&self.raft_store.evict_cache_on_memory_ratio) {
+            return Err(
+                "raftstore.evict-cache-on-memory-ratio should be in [0, 1)".to_string(),
+            );
+        }
+        if !(0.0..1.0).contains(&self.memory_usage_high_water) {
+            return Err("memory-usage-high-water should be in [0, 1)".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.server.reject_messages_on_memory_ratio) {
+            return Err("server.reject-messages-on-memory-ratio should be in [0, 1]".to_string());
+        }
+        if self.import.num_threads == 0 {
+            return Err("import.num-threads should not be 0".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.engine_store.disk_full_enter_ratio) {
+            return Err("engine-store.disk-full-enter-ratio should be in [0, 1]".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.engine_store.disk_full_recovery_ratio) {
+            return Err("engine-store.disk-full-recovery-ratio should be in [0, 1]".to_string());
+        }
+        if self.engine_store.disk_full_enter_ratio > 0.0
+            && self.engine_store.disk_full_recovery_ratio >= self.engine_store.disk_full_enter_ratio
+        {
+            return Err(
+                "engine-store.disk-full-recovery-ratio should be less than disk-full-enter-ratio"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+
     pub fn from_file(
         path: &Path,
         unrecognized_keys: Option<&mut Vec<String>>,
@@ -335,6 +410,96 @@ pub fn ensure_no_common_unrecognized_keys(
     Ok(())
 }
 
+fn proxy_config_change_value(v: &str, typed: &ConfigValue) -> Result<ConfigValue, String> {
+    let v = v.trim_matches('"');
+    let res = match typed {
+        ConfigValue::Duration(_) => {
+            ConfigValue::from(v.parse::<ReadableDuration>().map_err(|e| e.to_string())?)
+        }
+        ConfigValue::Size(_) => {
+            ConfigValue::from(v.parse::<ReadableSize>().map_err(|e| e.to_string())?)
+        }
+        ConfigValue::U64(_) => ConfigValue::from(v.parse::<u64>().map_err(|e| e.to_string())?),
+        ConfigValue::F64(_) => ConfigValue::from(v.parse::<f64>().map_err(|e| e.to_string())?),
+        ConfigValue::U32(_) => ConfigValue::from(v.parse::<u32>().map_err(|e| e.to_string())?),
+        ConfigValue::I32(_) => ConfigValue::from(v.parse::<i32>().map_err(|e| e.to_string())?),
+        ConfigValue::Usize(_) => ConfigValue::from(v.parse::<usize>().map_err(|e| e.to_string())?),
+        ConfigValue::Bool(_) => ConfigValue::from(v.parse::<bool>().map_err(|e| e.to_string())?),
+        ConfigValue::String(_) => ConfigValue::String(v.to_owned()),
+        ConfigValue::Skip => return Err("this option can not be overridden".to_string()),
+        _ => return Err("this option can not be overridden".to_string()),
+    };
+    Ok(res)
+}
+
+fn proxy_config_change_of(
+    dotted_key: &str,
+    value: &str,
+    typed: &ConfigChange,
+) -> Result<ConfigChange, String> {
+    let mut fields: Vec<&str> = dotted_key.split('.').collect();
+    fields.reverse();
+
+    fn helper(
+        mut fields: Vec<&str>,
+        typed: &ConfigChange,
+        value: &str,
+    ) -> Result<ConfigChange, String> {
+        let field = fields.pop().ok_or_else(|| "empty key".to_string())?;
+        // `typed()` keys are the Rust field names, but the CLI/TOML surface
+        // uses kebab-case (and `raft_store` is additionally renamed to
+        // `raftstore` via `#[serde(rename = ...)]`), so normalize both before
+        // the lookup.
+        let field = field.replace('-', "_");
+        let field = if field == "raftstore" {
+            "raft_store".to_string()
+        } else {
+            field
+        };
+        match typed.get(field.as_str()) {
+            None => Err(format!("unknown proxy config key: {}", field)),
+            Some(ConfigValue::Module(submodule)) => {
+                let nested = helper(fields, submodule, value)?;
+                let mut change = ConfigChange::default();
+                change.insert(field, ConfigValue::Module(nested));
+                Ok(change)
+            }
+            Some(leaf) => {
+                if !fields.is_empty() {
+                    return Err(format!("unknown proxy config key: {}", field));
+                }
+                let mut change = ConfigChange::default();
+                change.insert(field.clone(), proxy_config_change_value(value, leaf)?);
+                Ok(change)
+            }
+        }
+    }
+    helper(fields, typed, value)
+}
+
+/// Applies `--proxy-config-override key=value` pairs on top of `proxy_config`,
+/// type-checking each value against `ProxyConfig`'s `OnlineConfig` metadata
+/// (the same mechanism used for online config updates) so a typo like
+/// `snap-handle-pool-size=abc` is rejected instead of silently becoming 0.
+/// Meant to run after the config file has already been parsed, so the file
+/// still owns the base configuration and these are just single-knob patches.
+pub fn apply_proxy_config_overrides(
+    proxy_config: &mut ProxyConfig,
+    overrides: &[String],
+) -> Result<(), String> {
+    let typed = proxy_config.typed();
+    for kv in overrides {
+        let (key, value) = kv
+            .split_once('=')
+            .ok_or_else(|| format!("invalid --proxy-config-override, expect key=value: {}", kv))?;
+        let change = proxy_config_change_of(key, value, &typed)?;
+        proxy_config
+            .update(change)
+            .map_err(|e| format!("failed to apply override {}: {}", kv, e))?;
+    }
+    Ok(())
+}
+
 // Not the same as TiKV
 pub const TIFLASH_DEFAULT_LISTENING_ADDR: &str = "127.0.0.1:20170";
 pub const TIFLASH_DEFAULT_STATUS_ADDR: &str = "127.0.0.1:20292";
@@ -0,0 +1,48 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+//! Records the fields `address_proxy_config`/`validate_and_persist_config`
+//! actually changed on top of the user's TOML, so `GET
+//! /debug/proxy_config_diff` can answer "why isn't my config value in
+//! effect" without diffing the printed config by hand.
+
+use std::sync::Mutex;
+
+use online_config::{ConfigChange, ConfigValue, OnlineConfig};
+use tikv::config::TikvConfig;
+
+lazy_static! {
+    static ref LAST_DIFF: Mutex<Option<serde_json::Value>> = Mutex::new(None);
+}
+
+/// Diffs `configured` (as loaded from TOML, before proxy overrides) against
+/// `effective` (after `address_proxy_config` and
+/// `validate_and_persist_config` ran) and stashes the result for the status
+/// server to serve. Called once from `TiKvServer::init_config`.
+pub fn record_proxy_overrides(configured: &TikvConfig, effective: &TikvConfig) {
+    let diff = configured.diff(effective);
+    *LAST_DIFF.lock().unwrap() = Some(change_to_json(&diff));
+}
+
+/// Returns the diff recorded by the last `record_proxy_overrides` call, or
+/// `None` if the server hasn't finished initializing its config yet.
+pub fn proxy_config_diff() -> Option<serde_json::Value> {
+    LAST_DIFF.lock().unwrap().clone()
+}
+
+fn change_to_json(change: &ConfigChange) -> serde_json::Value {
+    let map = change
+        .iter()
+        .map(|(name, value)| (name.clone(), value_to_json(value)))
+        .collect();
+    serde_json::Value::Object(map)
+}
+
+fn value_to_json(value: &ConfigValue) -> serde_json::Value {
+    match value {
+        ConfigValue::Module(change) => change_to_json(change),
+        ConfigValue::None => serde_json::Value::Null,
+        // Every other variant is a scalar; its `Display` impl (see
+        // `online_config::ConfigValue`) already renders it the same way
+        // it's shown in logs, e.g. "128MiB" for a `Size`.
+        other => serde_json::Value::String(other.to_string()),
+    }
+}
@@ -16,8 +16,10 @@ extern crate tikv_util;
 
 #[macro_use]
 pub mod config;
+pub mod config_diff;
 pub mod engine;
 pub mod hacked_lock_mgr;
+pub mod process_supervision;
 pub mod proxy;
 pub mod run;
 pub mod setup;
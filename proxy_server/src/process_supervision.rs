@@ -0,0 +1,32 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Reserved for a proxy-managed engine-store process supervisor: launch the
+//! engine-store process, restart it with backoff on exit, and re-run FFI
+//! session negotiation on each restart.
+//!
+//! That shape doesn't fit how this proxy is actually deployed. `run_proxy`
+//! (see `crate::proxy::run_proxy`) is the only entry point into this crate,
+//! and it is called *by* the engine-store process's own `main`, passing this
+//! proxy an `engine_store_server_helper` pointer back into that same
+//! process -- there is no `proxy_server` binary (`Cargo.toml` defines no
+//! `[[bin]]` here) and so no separate proxy process that could `fork`/`exec`
+//! an engine-store process and hold a `Child` handle to it. By the time any
+//! Rust code in this crate runs, the engine-store process already exists and
+//! this proxy is already inside it.
+//!
+//! Restarting the engine store therefore has to stay a decision made by
+//! whatever launched that process in the first place (systemd, a Kubernetes
+//! pod controller, TiUP) -- layers above both the proxy and the engine
+//! store, not something reachable from here. What this proxy *can* do, and
+//! already does, is notice from the inside that a restart happened and react
+//! to it: see `engine_store_ffi::core::restart_detection`, which polls
+//! `EngineStoreServerHelper::handle_get_engine_store_server_status` for the
+//! flap this scenario would produce and re-runs transport capability
+//! negotiation, i.e. the "coordinate FFI session re-establishment" half of
+//! this request, from the side that's actually reachable.
+//!
+//! `EngineStoreConfig::enable_engine_store_process_supervision` is kept as a
+//! reserved config flag (currently unread anywhere) so that if this crate
+//! ever gains its own `main` for a disaggregated deployment mode, the
+//! feature has a name and a place to be turned on without a config-schema
+//! change.
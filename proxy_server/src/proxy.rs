@@ -22,7 +22,7 @@ pub fn gen_proxy_config(
     proxy_unrecognized_keys: &mut Vec<String>,
 ) -> ProxyConfig {
     // Double read the same file for proxy-specific arguments.
-    config_path.map_or_else(ProxyConfig::default, |path| {
+    let proxy_config = config_path.map_or_else(ProxyConfig::default, |path| {
         let path = Path::new(path);
         crate::config::ProxyConfig::from_file(
             path,
@@ -39,7 +39,11 @@ pub fn gen_proxy_config(
                 e
             );
         })
-    })
+    });
+    if let Err(e) = proxy_config.validate() {
+        panic!("invalid proxy configuration: {}", e);
+    }
+    proxy_config
 }
 
 /// Generate default TikvConfig, but with some Proxy's default values.
@@ -262,6 +266,21 @@ pub unsafe fn run_proxy(
                 .required(true)
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("proxy-config-override")
+                .long("proxy-config-override")
+                .takes_value(true)
+                .value_name("KEY=VALUE")
+                .multiple(true)
+                .number_of_values(1)
+                .help("Overrides a single proxy config option")
+                .long_help(
+                    "Overrides a single proxy config option after the config file is parsed, \
+                     e.g. `--proxy-config-override raftstore.snap-handle-pool-size=4`. May be \
+                     given multiple times. The value is type-checked against the option's \
+                     declared type.",
+                ),
+        )
         .arg(
             Arg::with_name("only-decryption")
                 .long("only-decryption")
@@ -288,6 +307,15 @@ pub unsafe fn run_proxy(
     overwrite_config_with_cmd_args(&mut config, &mut proxy_config, &matches);
     config.logger_compatible_adjust();
 
+    // Re-validate after the overwrite above: `--addr`/`--advertise-addr`/
+    // `--status-addr`/`--advertise-status-addr`/`--engine-addr`/
+    // `--advertise-engine-addr` are how TiFlash actually sets these in
+    // production, and `gen_proxy_config`'s own `validate()` call only saw
+    // the config-file values, before any of that ran.
+    if let Err(e) = proxy_config.validate() {
+        panic!("invalid proxy configuration: {}", e);
+    }
+
     if is_config_check {
         crate::config::validate_and_persist_config(&mut config, false);
         match crate::config::ensure_no_common_unrecognized_keys(
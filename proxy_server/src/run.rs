@@ -28,7 +28,12 @@ use engine_rocks::{
 use engine_rocks_helper::sst_recovery::{RecoveryRunner, DEFAULT_CHECK_INTERVAL};
 use engine_store_ffi::{
     self,
-    core::DebugStruct,
+    core::{
+        engine_store_disk_full,
+        lifecycle::{notify_lifecycle_stage, LifecycleStage},
+        startup_check::{record_startup_report, CheckResult, StartupReport},
+        DebugStruct,
+    },
     ffi::{
         interfaces_ffi::{
             EngineStoreServerHelper, EngineStoreServerStatus, RaftProxyStatus,
@@ -115,6 +120,7 @@ use tikv_util::{
     Either,
 };
 use tokio::runtime::Builder;
+use txn_types::TimeStamp;
 
 use crate::{
     config::ProxyConfig, engine::ProxyRocksEngine, fatal,
@@ -140,6 +146,7 @@ pub fn run_impl<CER: ConfiguredRaftEngine, F: KvFormat>(
     tikv.init_fs();
     tikv.init_yatp();
     tikv.init_encryption();
+    tikv.run_startup_self_checks();
 
     let mut proxy = RaftStoreProxy::new(
         AtomicU8::new(RaftProxyStatus::Idle as u8),
@@ -162,6 +169,7 @@ pub fn run_impl<CER: ConfiguredRaftEngine, F: KvFormat>(
     info!("set raft-store proxy helper");
 
     engine_store_server_helper.handle_set_proxy(&proxy_helper);
+    notify_lifecycle_stage(LifecycleStage::FfiReady);
 
     info!("wait for engine-store server to start");
     while engine_store_server_helper.handle_get_engine_store_server_status()
@@ -185,6 +193,7 @@ pub fn run_impl<CER: ConfiguredRaftEngine, F: KvFormat>(
     let (engines, engines_info) =
         tikv.init_tiflash_engines(listener, engine_store_server_helper_ptr);
     tikv.init_engines(engines.clone());
+    notify_lifecycle_stage(LifecycleStage::EnginesOpened);
     {
         proxy.set_kv_engine(
             engine_store_ffi::ffi::RaftStoreProxyEngine::from_tiflash_engine(engines.kv.clone()),
@@ -195,9 +204,11 @@ pub fn run_impl<CER: ConfiguredRaftEngine, F: KvFormat>(
     tikv.init_metrics_flusher(fetcher, engines_info);
     tikv.init_storage_stats_task(engines);
     tikv.run_server(server_config);
+    notify_lifecycle_stage(LifecycleStage::RaftstoreStarted);
     tikv.run_status_server();
 
     proxy.set_status(RaftProxyStatus::Running);
+    notify_lifecycle_stage(LifecycleStage::Serving);
 
     {
         debug_assert!(
@@ -220,9 +231,11 @@ pub fn run_impl<CER: ConfiguredRaftEngine, F: KvFormat>(
         engine_store_server_helper.handle_get_engine_store_server_status()
     );
 
+    notify_lifecycle_stage(LifecycleStage::Draining);
     tikv.stop();
 
     proxy.set_status(RaftProxyStatus::Stopped);
+    notify_lifecycle_stage(LifecycleStage::Stopped);
 
     info!("all services in raft-store proxy are stopped");
 
@@ -508,6 +521,8 @@ const DEFAULT_METRICS_FLUSH_INTERVAL: Duration = Duration::from_millis(10_000);
 const DEFAULT_MEMTRACE_FLUSH_INTERVAL: Duration = Duration::from_millis(1_000);
 const DEFAULT_ENGINE_METRICS_RESET_INTERVAL: Duration = Duration::from_millis(60_000);
 const DEFAULT_STORAGE_STATS_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_LEARNER_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const DEFAULT_REGION_STATE_AUDIT_INTERVAL: Duration = Duration::from_secs(60);
 
 /// A complete TiKV server.
 struct TiKvServer<ER: RaftEngine> {
@@ -572,6 +587,7 @@ impl<ER: RaftEngine> TiKvServer<ER> {
             SecurityManager::new(&config.security)
                 .unwrap_or_else(|e| fatal!("failed to create security manager: {}", e)),
         );
+        engine_tiflash::apply_forward_proxy_env(&proxy_config.engine_store);
         let env = Arc::new(
             EnvBuilder::new()
                 .cq_count(config.server.grpc_concurrency)
@@ -683,9 +699,12 @@ impl<ER: RaftEngine> TiKvServer<ER> {
     /// - If the max open file descriptor limit is not high enough to support
     ///   the main database and the raft database.
     fn init_config(mut config: TikvConfig, proxy_config: &ProxyConfig) -> ConfigController {
+        let configured = config.clone();
         crate::config::address_proxy_config(&mut config, proxy_config);
         crate::config::validate_and_persist_config(&mut config, true);
+        notify_lifecycle_stage(LifecycleStage::ConfigValidated);
         info!("after address config"; "config" => ?config);
+        crate::config_diff::record_proxy_overrides(&configured, &config);
 
         ensure_dir_exist(&config.storage.data_dir).unwrap();
         if !config.rocksdb.wal_dir.is_empty() {
@@ -864,6 +883,94 @@ impl<ER: RaftEngine> TiKvServer<ER> {
         .map(Arc::new);
     }
 
+    /// Runs the startup self-checks described by
+    /// `engine_store_ffi::core::startup_check` and publishes the results for
+    /// `/startup_report`. Must run after `init_encryption` (needs
+    /// `self.encryption_key_manager`) and after `TiKvServer::init` (needs
+    /// `self.pd_client`), and before anything that would rather fail fast on
+    /// a bad data dir than get partway through starting servers.
+    fn run_startup_self_checks(&self) {
+        if !self.proxy_config.engine_store.enable_startup_self_check {
+            return;
+        }
+        let mut checks = Vec::new();
+
+        // FFI version handshake: `EngineStoreServerHelper::check` already
+        // compared `magic_number`/`version` against this build's constants
+        // and hard-exited on a mismatch before `run_proxy` ever reached this
+        // code path, so reaching here means it already passed.
+        checks.push(CheckResult::ok(
+            "ffi_version_handshake",
+            "engine-store FFI magic number and version matched",
+        ));
+
+        checks.push(match tikv_util::config::check_data_dir(&self.config.storage.data_dir) {
+            Ok(()) => CheckResult::ok("disk_permissions", "data-dir is writable"),
+            Err(e) => CheckResult::fail(
+                "disk_permissions",
+                format!("data-dir {}: {}", self.config.storage.data_dir, e),
+            ),
+        });
+
+        checks.push(match &self.encryption_key_manager {
+            Some(_) => CheckResult::ok(
+                "encryption_keys",
+                format!(
+                    "encryption method: {:?}",
+                    self.config.security.encryption.data_encryption_method
+                ),
+            ),
+            None => CheckResult::ok("encryption_keys", "encryption at rest is disabled"),
+        });
+
+        match block_on(self.pd_client.get_tso()) {
+            Ok(ts) => {
+                let pd_physical = ts.physical();
+                let local_physical = TimeStamp::physical_now();
+                let skew = local_physical.abs_diff(pd_physical);
+                let max_skew = self.proxy_config.engine_store.startup_max_clock_skew.as_millis();
+                if skew > max_skew {
+                    checks.push(CheckResult::warn(
+                        "pd_clock_skew",
+                        format!("local clock differs from PD by {}ms (limit {}ms)", skew, max_skew),
+                    ));
+                } else {
+                    checks.push(CheckResult::ok(
+                        "pd_clock_skew",
+                        format!("local clock differs from PD by {}ms", skew),
+                    ));
+                }
+            }
+            Err(e) => checks.push(CheckResult::warn(
+                "pd_clock_skew",
+                format!("failed to fetch a timestamp from PD: {}", e),
+            )),
+        }
+
+        let mut rocksdb_max_open_files = self.config.rocksdb.max_open_files;
+        if self.config.rocksdb.titan.enabled {
+            rocksdb_max_open_files *= 2;
+        }
+        checks.push(
+            match tikv_util::config::check_max_open_fds(
+                RESERVED_OPEN_FDS
+                    + (rocksdb_max_open_files + self.config.raftdb.max_open_files) as u64,
+            ) {
+                Ok(()) => CheckResult::ok("config_constraints", "max open fds sufficient"),
+                Err(e) => CheckResult::fail("config_constraints", e.to_string()),
+            },
+        );
+
+        let report = StartupReport { checks };
+        for c in &report.checks {
+            info!("startup self-check"; "name" => c.name, "status" => ?c.status, "message" => &c.message);
+        }
+        if self.proxy_config.engine_store.startup_self_check_fail_fast && report.has_failure() {
+            fatal!("startup self-check failed, see /startup_report or the log above for details");
+        }
+        record_startup_report(report);
+    }
+
     fn init_flow_receiver(&mut self) -> engine_rocks::FlowListener {
         let (tx, rx) = mpsc::channel();
         self.flow_info_sender = Some(tx.clone());
@@ -1217,6 +1324,10 @@ impl<ER: RaftEngine> TiKvServer<ER> {
             panic!("engine address is empty");
         }
 
+        if self.proxy_config.engine_store.allow_reuse_store_on_empty_dir {
+            reuse_store_id_on_empty_dir(&self.pd_client, &engines.engines, &mut default_store);
+        }
+
         let mut node = Node::new(
             self.system.take().unwrap(),
             &server_config.value().clone(),
@@ -1299,6 +1410,9 @@ impl<ER: RaftEngine> TiKvServer<ER> {
             engine_store_cfg: self.proxy_config.engine_store.clone(),
             pd_endpoints: self.config.pd.endpoints.clone(),
             snap_handle_pool_size: self.proxy_config.raft_store.snap_handle_pool_size,
+            feature_gate: self.pd_client.feature_gate().clone(),
+            resource_manager: self.resource_manager.clone(),
+            api_version: self.config.storage.api_version(),
         };
         let tiflash_ob = engine_store_ffi::observer::TiFlashObserver::new(
             node.id(),
@@ -1312,6 +1426,24 @@ impl<ER: RaftEngine> TiKvServer<ER> {
         );
         tiflash_ob.register_to(self.coprocessor_host.as_mut().unwrap());
 
+        {
+            let forwarder = tiflash_ob.forwarder.clone();
+            let pd_client = self.pd_client.clone();
+            self.background_worker
+                .spawn_interval_task(DEFAULT_LEARNER_HEALTH_CHECK_INTERVAL, move || {
+                    forwarder.check_learner_health(pd_client.as_ref());
+                });
+        }
+
+        {
+            let forwarder = tiflash_ob.forwarder.clone();
+            let pd_client = self.pd_client.clone();
+            self.background_worker
+                .spawn_interval_task(DEFAULT_REGION_STATE_AUDIT_INTERVAL, move || {
+                    forwarder.audit_region_state(pd_client.as_ref());
+                });
+        }
+
         cfg_controller.register(
             tikv::config::Module::Server,
             Box::new(ServerConfigManager::new(
@@ -1603,13 +1735,22 @@ impl<ER: RaftEngine> TiKvServer<ER> {
                 available = cmp::min(available, disk_stats.available_space());
 
                 let prev_disk_status = disk::get_disk_status(0); //0 no need care about failpoint.
-                let cur_disk_status = if available <= already_full_threshold {
+                let mut cur_disk_status = if available <= already_full_threshold {
                     disk::DiskUsage::AlreadyFull
                 } else if available <= almost_full_threshold {
                     disk::DiskUsage::AlmostFull
                 } else {
                     disk::DiskUsage::Normal
                 };
+                // OR in the engine store's own disk-full verdict (see
+                // `engine_store_ffi::core::forwarder::refresh_disk_full_status`)
+                // instead of overwriting it: this ticker only ever sees
+                // local-disk usage, so on its own it would clobber
+                // `AlreadyFull` back to `Normal` every second whenever local
+                // disk isn't also full.
+                if engine_store_disk_full() && cur_disk_status != disk::DiskUsage::AlreadyFull {
+                    cur_disk_status = disk::DiskUsage::AlreadyFull;
+                }
                 if prev_disk_status != cur_disk_status {
                     warn!(
                         "disk usage {:?}->{:?}, available={},snap={},kv={},raft={},capacity={}",
@@ -1827,6 +1968,60 @@ impl ConfiguredRaftEngine for PSLogEngine {
     }
 }
 
+/// Recovers a store whose data directory was wiped (e.g. a disk replacement)
+/// but whose store id is still remembered by PD under the same address.
+///
+/// Without this, `Node::try_bootstrap_store` would find no `StoreIdent` in
+/// the empty engines and allocate a brand new store id, which PD later
+/// rejects with a cluster-ID mismatch once the old store id resurfaces.
+/// Guarded by `engine_store.allow-reuse-store-on-empty-dir` since matching
+/// only by address is best-effort and must never be turned on unconditionally.
+fn reuse_store_id_on_empty_dir<EK: engine_traits::KvEngine, ER: engine_traits::RaftEngine>(
+    pd_client: &Arc<RpcClient>,
+    engines: &engine_traits::Engines<EK, ER>,
+    default_store: &mut kvproto::metapb::Store,
+) {
+    use kvproto::raft_serverpb::StoreIdent;
+
+    if engines
+        .kv
+        .get_msg::<StoreIdent>(keys::STORE_IDENT_KEY)
+        .unwrap_or(None)
+        .is_some()
+    {
+        // Already bootstrapped, nothing to recover.
+        return;
+    }
+
+    let stores = match pd_client.get_all_stores(true) {
+        Ok(stores) => stores,
+        Err(e) => {
+            warn!("failed to list stores from pd for rebootstrap check"; "err" => ?e);
+            return;
+        }
+    };
+
+    let matched = stores
+        .into_iter()
+        .find(|s| s.get_address() == default_store.get_address());
+    if let Some(s) = matched {
+        info!(
+            "found a previously registered store with the same address, \
+             re-bootstrapping with its store id instead of allocating a new one";
+            "store_id" => s.get_id(),
+            "address" => s.get_address(),
+        );
+        let mut ident = StoreIdent::default();
+        ident.set_cluster_id(pd_client.get_cluster_id().unwrap_or_default());
+        ident.set_store_id(s.get_id());
+        if let Err(e) = engines.kv.put_msg(keys::STORE_IDENT_KEY, &ident) {
+            warn!("failed to persist recovered store ident"; "err" => ?e);
+            return;
+        }
+        default_store.set_id(s.get_id());
+    }
+}
+
 /// Various sanity-checks and logging before running a server.
 ///
 /// Warnings are logged.
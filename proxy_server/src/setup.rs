@@ -8,7 +8,7 @@ pub use server::setup::initial_logger;
 use tikv::config::{MetricConfig, TikvConfig};
 use tikv_util::{self, logger};
 
-use crate::config::ProxyConfig;
+use crate::config::{apply_proxy_config_overrides, ProxyConfig};
 pub use crate::fatal;
 
 #[allow(dead_code)]
@@ -142,4 +142,13 @@ pub fn overwrite_config_with_cmd_args(
                 .unwrap(),
         ),
     );
+
+    // Applied last, after every other flag and the config file, so overrides
+    // always win.
+    if let Some(overrides) = matches.values_of("proxy-config-override") {
+        let overrides: Vec<String> = overrides.map(ToOwned::to_owned).collect();
+        if let Err(e) = apply_proxy_config_overrides(proxy_config, &overrides) {
+            fatal!("invalid --proxy-config-override: {}", e);
+        }
+    }
 }
@@ -52,8 +52,8 @@ use tikv::{
     },
 };
 use tikv_util::{
-    error, logger::set_log_level, metrics::dump, sys::thread::ThreadBuildWrapper,
-    timer::GLOBAL_TIMER_HANDLE,
+    error, logger::set_log_level, metrics::dump, metrics::ThreadInfoStatistics,
+    sys::thread::ThreadBuildWrapper, timer::GLOBAL_TIMER_HANDLE,
 };
 use tokio::{
     io::{AsyncRead, AsyncWrite},
@@ -166,6 +166,791 @@ where
         })
     }
 
+    /// Reports per-thread CPU usage for the FFI apply/pre-handle thread pool
+    /// (named `region-task`, see `ProxyForwarder::new`), so a stalled
+    /// engine-store apply can be told apart from an idle one without
+    /// attaching a full profiler.
+    fn apply_thread_prof(_req: Request<Body>) -> hyper::Result<Response<Body>> {
+        let mut stats = ThreadInfoStatistics::new();
+        stats.record();
+        let cpu_usages = stats.get_cpu_usages();
+        let mut lines: Vec<String> = cpu_usages
+            .into_iter()
+            .filter(|(name, _)| name.starts_with("region-task"))
+            .map(|(name, permille)| format!("{}\t{}.{}%", name, permille / 10, permille % 10))
+            .collect();
+        lines.sort();
+        let text = lines.join("\n").into_bytes();
+
+        let response = Response::builder()
+            .header("Content-Type", mime::TEXT_PLAIN.to_string())
+            .header("Content-Length", text.len())
+            .body(text.into())
+            .unwrap();
+        Ok(response)
+    }
+
+    /// Reports learner peers found missing from PD's region epoch by the
+    /// periodic `ProxyForwarder::check_learner_health` self-check.
+    fn forgotten_peers(_req: Request<Body>) -> hyper::Result<Response<Body>> {
+        let findings = engine_store_ffi::core::snapshot_forgotten_peers();
+        let text = serde_json::to_vec(&findings).unwrap();
+
+        let response = Response::builder()
+            .header("Content-Type", mime::APPLICATION_JSON.to_string())
+            .header("Content-Length", text.len())
+            .body(text.into())
+            .unwrap();
+        Ok(response)
+    }
+
+    /// Reports regions found with a local `RegionLocalState` diverging from
+    /// PD's view by the periodic `ProxyForwarder::audit_region_state`
+    /// self-check.
+    fn region_state_mismatches(_req: Request<Body>) -> hyper::Result<Response<Body>> {
+        let findings = engine_store_ffi::core::snapshot_region_state_mismatches();
+        let text = serde_json::to_vec(&findings).unwrap();
+
+        let response = Response::builder()
+            .header("Content-Type", mime::APPLICATION_JSON.to_string())
+            .header("Content-Length", text.len())
+            .body(text.into())
+            .unwrap();
+        Ok(response)
+    }
+
+    /// `GET /debug/feature_gates` reports whether each cluster-version gate
+    /// known to the proxy (see `engine_store_ffi::core::feature_gate`) is
+    /// currently enabled, i.e. whether the cluster version PD last reported
+    /// meets that gate's minimum version.
+    fn feature_gates(_req: Request<Body>) -> hyper::Result<Response<Body>> {
+        let states = engine_store_ffi::core::feature_gate::snapshot_feature_gate_states();
+        let text = serde_json::to_vec(&states.into_iter().collect::<HashMap<_, _>>()).unwrap();
+
+        let response = Response::builder()
+            .header("Content-Type", mime::APPLICATION_JSON.to_string())
+            .header("Content-Length", text.len())
+            .body(text.into())
+            .unwrap();
+        Ok(response)
+    }
+
+    /// `PUT /debug/freeze_region/<region_id>?index=<idx>` buffers further
+    /// writes to the region instead of forwarding them to the engine store,
+    /// from `index` onward, so it can be checkpointed consistently with
+    /// other regions frozen alongside it; see `engine_store_ffi::core::freeze`.
+    /// `DELETE` unfreezes it, replaying whatever was buffered.
+    /// `unfreeze_region` replays whatever was buffered straight to the
+    /// engine store, which can take a while for a region with a large
+    /// backlog -- run it on the shared FFI service runtime (see
+    /// `engine_store_ffi::core::runtime`) instead of blocking whichever
+    /// status-server worker thread is handling this request.
+    async fn freeze_region(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        lazy_static! {
+            static ref FREEZE_PATH: Regex = Regex::new(r"^/debug/freeze_region/(?P<id>\d+)$").unwrap();
+        }
+        let region_id: u64 = match FREEZE_PATH
+            .captures(req.uri().path())
+            .and_then(|cap| cap["id"].parse().ok())
+        {
+            Some(id) => id,
+            None => return Ok(make_response(StatusCode::BAD_REQUEST, "invalid region id")),
+        };
+
+        if req.method() == Method::DELETE {
+            let replayed = engine_store_ffi::core::runtime::spawn_blocking_ffi(move || {
+                engine_store_ffi::core::freeze::unfreeze_region(region_id)
+            })
+            .await;
+            return Ok(make_response(
+                StatusCode::OK,
+                format!("{{\"replayed\":{}}}", replayed),
+            ));
+        }
+
+        let query = req.uri().query().unwrap_or("");
+        let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+        let index: u64 = match query_pairs.get("index").and_then(|v| v.parse().ok()) {
+            Some(index) => index,
+            None => return Ok(make_response(StatusCode::BAD_REQUEST, "missing `index`")),
+        };
+        engine_store_ffi::core::freeze::freeze_region(region_id, index);
+        Ok(make_response(StatusCode::OK, "{}"))
+    }
+
+    /// `GET /debug/proxy_config_diff` reports every `TikvConfig` field
+    /// `address_proxy_config`/`validate_and_persist_config` changed on top
+    /// of the user's TOML (label injection, rocksdb/raftstore tweaks, and
+    /// so on) so a user can tell why a value they set is not the one in
+    /// effect; see `crate::config_diff`. `null` if the server hasn't
+    /// finished initializing its config yet.
+    fn proxy_config_diff(_req: Request<Body>) -> hyper::Result<Response<Body>> {
+        let body = serde_json::to_string(&crate::config_diff::proxy_config_diff()).unwrap();
+        Ok(make_response(StatusCode::OK, body))
+    }
+
+    /// `GET /debug/frozen_regions` lists every region currently paused via
+    /// `/debug/freeze_region/<region_id>`, so an operator driving a batch of
+    /// per-shard maintenance can confirm what is still frozen without
+    /// tracking it client-side.
+    fn frozen_regions(_req: Request<Body>) -> hyper::Result<Response<Body>> {
+        let body = serde_json::to_string(&engine_store_ffi::core::freeze::frozen_regions()).unwrap();
+        Ok(make_response(StatusCode::OK, body))
+    }
+
+    /// `GET /debug/export_region/<region_id>` returns `region_id`'s raft-level
+    /// metadata as a JSON [`RegionExportBundle`](engine_store_ffi::core::region_migration::RegionExportBundle),
+    /// for `PUT /debug/import_region` on a proxy in a different cluster; see
+    /// `engine_store_ffi::core::region_migration`. 404 if this store holds no
+    /// local state for the region.
+    fn export_region(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        lazy_static! {
+            static ref EXPORT_REGION_PATH: Regex =
+                Regex::new(r"^/debug/export_region/(?P<id>\d+)$").unwrap();
+        }
+        let region_id: u64 = match EXPORT_REGION_PATH
+            .captures(req.uri().path())
+            .and_then(|cap| cap["id"].parse().ok())
+        {
+            Some(id) => id,
+            None => return Ok(make_response(StatusCode::BAD_REQUEST, "invalid region id")),
+        };
+        match engine_store_ffi::core::region_migration::export_region(region_id) {
+            Some(bundle) => Ok(make_response(
+                StatusCode::OK,
+                serde_json::to_vec(&bundle).unwrap(),
+            )),
+            None => Ok(make_response(StatusCode::NOT_FOUND, "no local state for region")),
+        }
+    }
+
+    /// `PUT /debug/import_region` takes a JSON
+    /// [`RegionExportBundle`](engine_store_ffi::core::region_migration::RegionExportBundle)
+    /// produced by `export_region` on another proxy and seeds this store
+    /// with it; see that module's doc comment for exactly what is (and is
+    /// not) validated and populated.
+    async fn import_region(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        let mut body = Vec::new();
+        req.into_body()
+            .try_for_each(|bytes| {
+                body.extend(bytes);
+                ok(())
+            })
+            .await?;
+
+        let bundle: engine_store_ffi::core::region_migration::RegionExportBundle =
+            match serde_json::from_slice(&body) {
+                Ok(bundle) => bundle,
+                Err(err) => return Ok(make_response(StatusCode::BAD_REQUEST, err.to_string())),
+            };
+        match engine_store_ffi::core::region_migration::import_region(&bundle) {
+            Some(Ok(())) => Ok(make_response(StatusCode::OK, "")),
+            Some(Err(err)) => Ok(make_response(
+                StatusCode::CONFLICT,
+                serde_json::to_string(&err).unwrap(),
+            )),
+            None => Ok(make_response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "proxy forwarder not ready",
+            )),
+        }
+    }
+
+    /// `GET /debug/failpoints` lists failpoints armed via this endpoint
+    /// (name, actions, time armed, remaining TTL); `PUT
+    /// /debug/failpoints/<name>?ttl_ms=<n>` arms one with the request body
+    /// as its `fail::cfg` actions string, auto-disarming after `ttl_ms` if
+    /// given; `DELETE /debug/failpoints/<name>` disarms immediately. Debug
+    /// builds only (the `failpoints` feature); see
+    /// `engine_store_ffi::core::failpoint_ttl` for why this exists
+    /// alongside the plain `/fail` endpoint upstream TiKV already has.
+    #[cfg(feature = "failpoints")]
+    async fn debug_failpoints(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        lazy_static! {
+            static ref FAILPOINT_PATH: Regex =
+                Regex::new(r"^/debug/failpoints/(?P<name>.+)$").unwrap();
+        }
+        let method = req.method().clone();
+        let path = req.uri().path().to_owned();
+        let query = req.uri().query().unwrap_or("").to_owned();
+
+        if path == "/debug/failpoints" && method == Method::GET {
+            let body = serde_json::to_string(
+                &engine_store_ffi::core::failpoint_ttl::list_armed(),
+            )
+            .unwrap();
+            return Ok(make_response(StatusCode::OK, body));
+        }
+
+        let name = match FAILPOINT_PATH.captures(&path).map(|c| c["name"].to_owned()) {
+            Some(name) => name,
+            None => return Ok(make_response(StatusCode::BAD_REQUEST, "missing failpoint name")),
+        };
+
+        match method {
+            Method::DELETE => {
+                engine_store_ffi::core::failpoint_ttl::disarm(&name);
+                Ok(make_response(StatusCode::OK, ""))
+            }
+            Method::PUT => {
+                let mut body = Vec::new();
+                req.into_body()
+                    .try_for_each(|bytes| {
+                        body.extend(bytes);
+                        ok(())
+                    })
+                    .await?;
+                let actions = String::from_utf8_lossy(&body).into_owned();
+                let ttl = url::form_urlencoded::parse(query.as_bytes())
+                    .find(|(k, _)| k == "ttl_ms")
+                    .and_then(|(_, v)| v.parse::<u64>().ok())
+                    .map(Duration::from_millis);
+                match engine_store_ffi::core::failpoint_ttl::arm(name, actions, ttl) {
+                    Ok(()) => Ok(make_response(StatusCode::OK, "")),
+                    Err(err) => Ok(make_response(StatusCode::BAD_REQUEST, err)),
+                }
+            }
+            _ => Ok(make_response(StatusCode::METHOD_NOT_ALLOWED, "")),
+        }
+    }
+
+    /// `GET /debug/replay_debt` reports every region still carrying "replay
+    /// debt" (raft log entries the engine store hasn't caught up to since
+    /// restart) plus their sum, so an operator has a concrete "TiFlash node
+    /// is caught up" signal; see `engine_store_ffi::core::replay_debt`.
+    /// Populated on every heartbeat by `ProxyForwarder::refresh_replay_debt`
+    /// when `enable_restart_detection` is on, and also by a manually-issued
+    /// `PUT /debug/rewind_region/<id>`.
+    fn replay_debt(_req: Request<Body>) -> hyper::Result<Response<Body>> {
+        #[derive(serde_derive::Serialize)]
+        struct ReplayDebtReport {
+            total_entries_remaining: u64,
+            regions: std::collections::HashMap<u64, engine_store_ffi::core::replay_debt::RegionReplayDebt>,
+        }
+        let body = serde_json::to_string(&ReplayDebtReport {
+            total_entries_remaining: engine_store_ffi::core::replay_debt::total_replay_debt(),
+            regions: engine_store_ffi::core::replay_debt::replay_debt_report()
+                .into_iter()
+                .collect(),
+        })
+        .unwrap();
+        Ok(make_response(StatusCode::OK, body))
+    }
+
+    /// `PUT /debug/rebuild_region/<region_id>` marks a region's local
+    /// engine-store copy suspect and starts tracking it through to a fresh
+    /// snapshot applying, so an operator can poll one endpoint instead of
+    /// watching pd-ctl output by hand; see `engine_store_ffi::core::rebuild_region`
+    /// for exactly what this does and does not trigger -- notably, it does
+    /// *not* itself tombstone the peer or ask PD for a replacement, since
+    /// that conf-change decision belongs to raftstore, not this forwarder.
+    /// `GET` reports the tracked phase, `DELETE` stops tracking it.
+    fn rebuild_region(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        lazy_static! {
+            static ref REBUILD_PATH: Regex =
+                Regex::new(r"^/debug/rebuild_region/(?P<id>\d+)$").unwrap();
+        }
+        let region_id: u64 = match REBUILD_PATH
+            .captures(req.uri().path())
+            .and_then(|cap| cap["id"].parse().ok())
+        {
+            Some(id) => id,
+            None => return Ok(make_response(StatusCode::BAD_REQUEST, "invalid region id")),
+        };
+
+        if req.method() == Method::PUT {
+            return if engine_store_ffi::core::rebuild_region::request_rebuild(region_id) {
+                Ok(make_response(StatusCode::OK, "{}"))
+            } else {
+                Ok(make_response(
+                    StatusCode::CONFLICT,
+                    "region already has a tracked rebuild in progress",
+                ))
+            };
+        }
+        if req.method() == Method::DELETE {
+            engine_store_ffi::core::rebuild_region::cancel_rebuild(region_id);
+            return Ok(make_response(StatusCode::OK, "{}"));
+        }
+        match engine_store_ffi::core::rebuild_region::rebuild_status(region_id) {
+            Some(status) => Ok(make_response(
+                StatusCode::OK,
+                serde_json::to_string(&status).unwrap(),
+            )),
+            None => Ok(make_response(StatusCode::NOT_FOUND, "no tracked rebuild")),
+        }
+    }
+
+    /// `POST /debug/restore_point` freezes every region on the store at its
+    /// current applied index and records the tuple as a new restore point;
+    /// `GET` lists previously created ones; `POST
+    /// /debug/restore_point/<id>/resume` unfreezes the regions it recorded,
+    /// replaying whatever was buffered since. See
+    /// `engine_store_ffi::core::restore_point` for what this does and does
+    /// not cover -- it is a consistent freeze point, not a genuine
+    /// checkpoint/rollback of the engine store's own data.
+    async fn restore_point(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        lazy_static! {
+            static ref RESUME_PATH: Regex =
+                Regex::new(r"^/debug/restore_point/(?P<id>\d+)/resume$").unwrap();
+        }
+        if let Some(cap) = RESUME_PATH.captures(req.uri().path()) {
+            let id: u64 = match cap["id"].parse() {
+                Ok(id) => id,
+                Err(_) => return Ok(make_response(StatusCode::BAD_REQUEST, "invalid restore point id")),
+            };
+            return match engine_store_ffi::core::restore_point::resume_restore_point(id) {
+                Some(replayed) => Ok(make_response(
+                    StatusCode::OK,
+                    format!("{{\"replayed\":{}}}", replayed),
+                )),
+                None => Ok(make_response(StatusCode::NOT_FOUND, "unknown restore point id")),
+            };
+        }
+
+        if req.method() == Method::POST {
+            return match engine_store_ffi::core::restore_point::create_restore_point() {
+                Some(point) => Ok(make_response(
+                    StatusCode::OK,
+                    serde_json::to_vec(&point).unwrap(),
+                )),
+                None => Ok(make_response(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "proxy forwarder not ready",
+                )),
+            };
+        }
+
+        let points = engine_store_ffi::core::restore_point::list_restore_points();
+        Ok(make_response(
+            StatusCode::OK,
+            serde_json::to_vec(&points).unwrap(),
+        ))
+    }
+
+    /// `PUT /debug/verbose_trace/<region_id>?duration=<secs>&path=<file>`
+    /// turns on trace-level logging of every FFI interaction for
+    /// `region_id`, written to `path`, for `duration` seconds; see
+    /// `engine_store_ffi::core::verbose_trace`. `DELETE` turns it off early.
+    fn verbose_trace(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        lazy_static! {
+            static ref TRACE_PATH: Regex = Regex::new(r"^/debug/verbose_trace/(?P<id>\d+)$").unwrap();
+        }
+        let region_id: u64 = match TRACE_PATH
+            .captures(req.uri().path())
+            .and_then(|cap| cap["id"].parse().ok())
+        {
+            Some(id) => id,
+            None => return Ok(make_response(StatusCode::BAD_REQUEST, "invalid region id")),
+        };
+
+        if req.method() == Method::DELETE {
+            engine_store_ffi::core::verbose_trace::disable();
+            return Ok(make_response(StatusCode::OK, "{}"));
+        }
+
+        let query = req.uri().query().unwrap_or("");
+        let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+        let duration_secs: u64 = match query_pairs.get("duration").and_then(|v| v.parse().ok()) {
+            Some(secs) => secs,
+            None => return Ok(make_response(StatusCode::BAD_REQUEST, "missing `duration`")),
+        };
+        let path = match query_pairs.get("path") {
+            Some(path) => PathBuf::from(path.as_ref()),
+            None => return Ok(make_response(StatusCode::BAD_REQUEST, "missing `path`")),
+        };
+        match engine_store_ffi::core::verbose_trace::enable(
+            region_id,
+            std::time::Duration::from_secs(duration_secs),
+            &path,
+        ) {
+            Ok(()) => Ok(make_response(StatusCode::OK, "{}")),
+            Err(err) => Ok(make_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
+        }
+    }
+
+    /// Toggles store-scope maintenance mode: `PUT /debug/maintenance_mode?enabled=true|false`
+    /// enables or disables it, `GET` reports the current state. See
+    /// `engine_store_ffi::core::maintenance` for what it gates.
+    fn maintenance_mode(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        if req.method() == Method::PUT {
+            let query = req.uri().query().unwrap_or("");
+            let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+            let enabled = match query_pairs.get("enabled") {
+                Some(val) => match val.parse::<bool>() {
+                    Ok(val) => val,
+                    Err(err) => return Ok(make_response(StatusCode::BAD_REQUEST, err.to_string())),
+                },
+                None => return Ok(make_response(StatusCode::BAD_REQUEST, "missing `enabled`")),
+            };
+            engine_store_ffi::core::maintenance::set_maintenance_mode(enabled);
+        }
+        let body = format!(
+            "{{\"enabled\":{}}}",
+            engine_store_ffi::core::maintenance::is_maintenance_mode()
+        );
+        Ok(make_response(StatusCode::OK, body))
+    }
+
+    /// `GET/PUT /debug/region_worker_interval?secs=<n>` reads or retunes the
+    /// minimum interval between real split-check/consistency-check rounds
+    /// per region; see `engine_store_ffi::core::region_worker`. Has no
+    /// effect unless `engine_store_cfg.enable_dynamic_region_worker_scheduling`
+    /// is also set.
+    fn region_worker_interval(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        if req.method() == Method::PUT {
+            let query = req.uri().query().unwrap_or("");
+            let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+            let secs: u64 = match query_pairs.get("secs").and_then(|v| v.parse().ok()) {
+                Some(secs) => secs,
+                None => return Ok(make_response(StatusCode::BAD_REQUEST, "missing `secs`")),
+            };
+            engine_store_ffi::core::region_worker::set_global_min_interval(
+                std::time::Duration::from_secs(secs),
+            );
+        }
+        let body = match engine_store_ffi::core::region_worker::global_min_interval() {
+            Some(interval) => format!("{{\"secs\":{}}}", interval.as_secs()),
+            None => "{\"secs\":null}".to_string(),
+        };
+        Ok(make_response(StatusCode::OK, body))
+    }
+
+    /// `GET /startup_report` returns the results of the startup self-checks
+    /// (see `engine_store_ffi::core::startup_check`) as a JSON array, or an
+    /// empty array before they have run or if
+    /// `engine_store_cfg.enable_startup_self_check` is off.
+    fn startup_report() -> hyper::Result<Response<Body>> {
+        let checks = engine_store_ffi::core::startup_check::current_startup_report()
+            .map(|r| r.checks)
+            .unwrap_or_default();
+        let body = serde_json::to_string(&checks).unwrap();
+        Ok(make_response(StatusCode::OK, body))
+    }
+
+    /// `GET /debug/raft_log/<region_id>?low=<idx>&high=<idx>` exports the
+    /// region's raw raft log entries in `[low, high)` as JSON, for offline
+    /// diffing against engine-store apply records -- see the `raft_log_diff`
+    /// binary in `engine_store_ffi` for a decoder/differ over the output.
+    fn raft_log_export(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        lazy_static! {
+            static ref RAFT_LOG_PATH: Regex = Regex::new(r"^/debug/raft_log/(?P<id>\d+)$").unwrap();
+        }
+
+        let region_id: u64 = match RAFT_LOG_PATH
+            .captures(req.uri().path())
+            .and_then(|cap| cap["id"].parse().ok())
+        {
+            Some(id) => id,
+            None => return Ok(make_response(StatusCode::BAD_REQUEST, "invalid region id")),
+        };
+
+        let query = req.uri().query().unwrap_or("");
+        let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+        let low: u64 = query_pairs
+            .get("low")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let high: u64 = match query_pairs.get("high").and_then(|v| v.parse().ok()) {
+            Some(high) => high,
+            None => return Ok(make_response(StatusCode::BAD_REQUEST, "missing `high`")),
+        };
+
+        match engine_store_ffi::core::raft_log_export::export_raft_log_range(
+            region_id, low, high,
+        ) {
+            Ok(entries) => {
+                let text = serde_json::to_vec(&entries).unwrap();
+                Ok(Response::builder()
+                    .header("Content-Type", mime::APPLICATION_JSON.to_string())
+                    .header("Content-Length", text.len())
+                    .body(text.into())
+                    .unwrap())
+            }
+            Err(err) => Ok(make_response(StatusCode::INTERNAL_SERVER_ERROR, err)),
+        }
+    }
+
+    /// `GET /debug/snapshot_apply_history/<region_id>` returns the region's
+    /// recent snapshot apply attempts (see
+    /// `engine_store_ffi::core::snapshot_apply_history`) as a JSON array,
+    /// oldest first, so an operator can see why a region's AddLearner
+    /// snapshot applies keep failing instead of re-deriving it from raw
+    /// logs. Always empty unless `engine_store_cfg.snapshot_apply_history_len`
+    /// is non-zero.
+    fn snapshot_apply_history(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        lazy_static! {
+            static ref SNAPSHOT_APPLY_HISTORY_PATH: Regex =
+                Regex::new(r"^/debug/snapshot_apply_history/(?P<id>\d+)$").unwrap();
+        }
+
+        let region_id: u64 = match SNAPSHOT_APPLY_HISTORY_PATH
+            .captures(req.uri().path())
+            .and_then(|cap| cap["id"].parse().ok())
+        {
+            Some(id) => id,
+            None => return Ok(make_response(StatusCode::BAD_REQUEST, "invalid region id")),
+        };
+
+        let attempts =
+            engine_store_ffi::core::snapshot_apply_history::snapshot_apply_history(region_id);
+        let body = serde_json::to_string(&attempts).unwrap();
+        Ok(make_response(StatusCode::OK, body))
+    }
+
+    /// `GET /debug/region_size_amplification/<region_id>` reports bytes
+    /// forwarded to the engine store for the region versus bytes it reports
+    /// retaining, and their ratio when both sides are known; see
+    /// `engine_store_ffi::core::region_size_amplification`.
+    fn region_size_amplification(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        lazy_static! {
+            static ref SIZE_AMPLIFICATION_PATH: Regex =
+                Regex::new(r"^/debug/region_size_amplification/(?P<id>\d+)$").unwrap();
+        }
+
+        let region_id: u64 = match SIZE_AMPLIFICATION_PATH
+            .captures(req.uri().path())
+            .and_then(|cap| cap["id"].parse().ok())
+        {
+            Some(id) => id,
+            None => return Ok(make_response(StatusCode::BAD_REQUEST, "invalid region id")),
+        };
+
+        match engine_store_ffi::core::region_size_amplification::region_size_amplification(
+            region_id,
+        ) {
+            Some(stat) => Ok(make_response(
+                StatusCode::OK,
+                serde_json::to_vec(&stat).unwrap(),
+            )),
+            None => Ok(make_response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "proxy forwarder not ready",
+            )),
+        }
+    }
+
+    /// `GET /debug/consistency_diff/<region_id>` dumps this proxy's locally
+    /// persisted `RegionLocalState`/`RaftApplyState` for the region next to
+    /// the engine store's own reported shard metadata, and the fields where
+    /// they disagree; see `engine_store_ffi::core::consistency_diff`. The
+    /// one-shot version of what support currently assembles by hand from two
+    /// separate debug dumps.
+    fn consistency_diff(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        lazy_static! {
+            static ref CONSISTENCY_DIFF_PATH: Regex =
+                Regex::new(r"^/debug/consistency_diff/(?P<id>\d+)$").unwrap();
+        }
+
+        let region_id: u64 = match CONSISTENCY_DIFF_PATH
+            .captures(req.uri().path())
+            .and_then(|cap| cap["id"].parse().ok())
+        {
+            Some(id) => id,
+            None => return Ok(make_response(StatusCode::BAD_REQUEST, "invalid region id")),
+        };
+
+        match engine_store_ffi::core::consistency_diff::diff_region_consistency(region_id) {
+            Some(report) => Ok(make_response(
+                StatusCode::OK,
+                serde_json::to_vec(&report).unwrap(),
+            )),
+            None => Ok(make_response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "proxy forwarder not ready",
+            )),
+        }
+    }
+
+    /// `POST /debug/check_keys` takes a JSON array of hex-encoded raw keys
+    /// in the request body and reports, per key, whether it has a write
+    /// record in this proxy's local engine (and its MVCC version, if so)
+    /// and whether the engine store has it; see
+    /// `engine_store_ffi::core::key_presence_check`. Automates the
+    /// data-presence triage `proxy_tests::proxy::check_key` does by hand.
+    async fn check_keys(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        let mut body = Vec::new();
+        req.into_body()
+            .try_for_each(|bytes| {
+                body.extend(bytes);
+                ok(())
+            })
+            .await?;
+
+        let hex_keys: Vec<String> = match serde_json::from_slice(&body) {
+            Ok(keys) => keys,
+            Err(err) => return Ok(make_response(StatusCode::BAD_REQUEST, err.to_string())),
+        };
+        let mut keys = Vec::with_capacity(hex_keys.len());
+        for hex_key in hex_keys {
+            match hex::decode(&hex_key) {
+                Ok(key) => keys.push(key),
+                Err(_) => {
+                    return Ok(make_response(
+                        StatusCode::BAD_REQUEST,
+                        format!("invalid hex key: {}", hex_key),
+                    ));
+                }
+            }
+        }
+
+        match engine_store_ffi::core::key_presence_check::check_keys_presence(&keys) {
+            Some(reports) => Ok(make_response(
+                StatusCode::OK,
+                serde_json::to_vec(&reports).unwrap(),
+            )),
+            None => Ok(make_response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "proxy forwarder not ready",
+            )),
+        }
+    }
+
+    /// `GET /debug/apply_watchdog?deadline_ms=<n>` reports every
+    /// `handle_write_raft_cmd`/`handle_admin_raft_cmd` call that has been in
+    /// flight for at least `deadline_ms` (default 30000), bundled from
+    /// `engine_store_ffi::core::apply_watchdog` in place of the manual gdb
+    /// session this used to require. Only ever reports anything when
+    /// `engine_store_cfg.enable_apply_pipeline_watchdog` is on.
+    fn apply_watchdog(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        let query = req.uri().query().unwrap_or("");
+        let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+        let deadline_ms: u64 = query_pairs
+            .get("deadline_ms")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30_000);
+        let hung = engine_store_ffi::core::apply_watchdog::check_hung_apply_calls(
+            std::time::Duration::from_millis(deadline_ms),
+        );
+        let body = serde_json::to_string(&hung).unwrap();
+        Ok(make_response(StatusCode::OK, body))
+    }
+
+    /// `GET /debug/notification_inbox` reports how many distinct regions
+    /// currently have a pending leader-change/epoch-update/flush-request
+    /// notification coalesced in
+    /// `engine_store_ffi::core::notification_inbox`. `null` if no proxy
+    /// forwarder has registered an inbox yet.
+    fn notification_inbox(_req: Request<Body>) -> hyper::Result<Response<Body>> {
+        let body =
+            serde_json::to_string(&engine_store_ffi::core::notification_inbox::global_tracked_regions())
+                .unwrap();
+        Ok(make_response(StatusCode::OK, body))
+    }
+
+    /// `GET /debug/region_garbage` reports the proxy's own live-region
+    /// snapshot (id, epoch, key range) from
+    /// `engine_store_ffi::core::region_garbage_listing`, plus the result of
+    /// diffing it against the engine store's own shard list when that side
+    /// is available. `engine_shards`/`diff` are `null` until
+    /// `request_engine_shard_list` has an FFI slot to call -- see that
+    /// module's doc comment. `live_regions` is `null` only if no proxy
+    /// forwarder has registered its region cache yet.
+    fn region_garbage(_req: Request<Body>) -> hyper::Result<Response<Body>> {
+        #[derive(serde_derive::Serialize)]
+        struct RegionGarbageReport {
+            live_regions: Option<Vec<engine_store_ffi::core::region_garbage_listing::LiveRegionSummary>>,
+            engine_shards: Option<Vec<engine_store_ffi::core::region_garbage_listing::EngineShardSummary>>,
+            diff: Option<engine_store_ffi::core::region_garbage_listing::GarbageDiff>,
+        }
+
+        let live_regions = engine_store_ffi::core::region_garbage_listing::global_live_regions();
+        // No forwarder handle is registered here, so the engine-store side
+        // of the check can't be requested from this endpoint either; left
+        // as a dedicated follow-up once `request_engine_shard_list` itself
+        // has something to call.
+        let engine_shards = None;
+        let diff = match (&live_regions, &engine_shards) {
+            (Some(live), Some(shards)) => Some(
+                engine_store_ffi::core::region_garbage_listing::diff_against_engine_shards(
+                    live, shards,
+                ),
+            ),
+            _ => None,
+        };
+        let body = serde_json::to_string(&RegionGarbageReport {
+            live_regions,
+            engine_shards,
+            diff,
+        })
+        .unwrap();
+        Ok(make_response(StatusCode::OK, body))
+    }
+
+    /// `GET /debug/pending_peer_destroy` lists every learner peer whose
+    /// local destroy has been observed but whose engine-store purge is
+    /// still being held back by `engine_store_cfg.peer_destroy_grace_period`
+    /// (see `engine_store_ffi::core::delayed_peer_destroy`).
+    fn pending_peer_destroy(_req: Request<Body>) -> hyper::Result<Response<Body>> {
+        let body = serde_json::to_string(
+            &engine_store_ffi::core::delayed_peer_destroy::pending_destroys(),
+        )
+        .unwrap();
+        Ok(make_response(StatusCode::OK, body))
+    }
+
+    /// `PUT /debug/rewind_region/<region_id>?to_index=<n>` queues a check of
+    /// how many raft log entries from `to_index` onward this proxy can still
+    /// recover for the region, e.g. after the engine store rolled back to an
+    /// older on-disk checkpoint than what the proxy already acknowledged.
+    /// This does not itself re-deliver anything to the engine store -- see
+    /// `engine_store_ffi::core::rewind::rewind_region`'s doc comment -- it
+    /// only tells an operator whether the data is still there to replay.
+    /// See `engine_store_ffi::core::rewind` for what actually runs the
+    /// queued request and when.
+    fn rewind_region(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        lazy_static! {
+            static ref REWIND_PATH: Regex =
+                Regex::new(r"^/debug/rewind_region/(?P<id>\d+)$").unwrap();
+        }
+        let region_id: u64 = match REWIND_PATH
+            .captures(req.uri().path())
+            .and_then(|cap| cap["id"].parse().ok())
+        {
+            Some(id) => id,
+            None => return Ok(make_response(StatusCode::BAD_REQUEST, "invalid region id")),
+        };
+        let query = req.uri().query().unwrap_or("");
+        let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+        let to_index: u64 = match query_pairs.get("to_index").and_then(|v| v.parse().ok()) {
+            Some(to_index) => to_index,
+            None => return Ok(make_response(StatusCode::BAD_REQUEST, "missing `to_index`")),
+        };
+        engine_store_ffi::core::rewind::request_rewind(region_id, to_index);
+        Ok(make_response(StatusCode::OK, "{}"))
+    }
+
+    /// `PUT /debug/shadow_engine_store?ptr=<hex>` registers a second,
+    /// already-running engine-store helper to mirror writes to for
+    /// dry-run canarying (see `engine_store_ffi::core::shadow`); omitting
+    /// `ptr` or passing `0` clears it. `GET` reports whether one is set.
+    /// There is no supported way to obtain a second live helper pointer in
+    /// production today -- this is wired up for test harnesses that already
+    /// hold two.
+    fn shadow_engine_store(req: Request<Body>) -> hyper::Result<Response<Body>> {
+        if req.method() == Method::PUT {
+            let query = req.uri().query().unwrap_or("");
+            let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+            match query_pairs.get("ptr").map(|v| v.as_ref()) {
+                None | Some("0") => engine_store_ffi::core::shadow::clear_shadow_engine_store(),
+                Some(val) => {
+                    let ptr = match isize::from_str_radix(val.trim_start_matches("0x"), 16) {
+                        Ok(ptr) => ptr,
+                        Err(err) => {
+                            return Ok(make_response(StatusCode::BAD_REQUEST, err.to_string()))
+                        }
+                    };
+                    engine_store_ffi::core::shadow::register_shadow_engine_store(ptr);
+                }
+            }
+        }
+        let enabled = engine_store_ffi::core::shadow::shadow_engine_store().is_some();
+        Ok(make_response(
+            StatusCode::OK,
+            format!("{{\"enabled\":{}}}", enabled),
+        ))
+    }
+
     fn list_heap_prof(_req: Request<Body>) -> hyper::Result<Response<Body>> {
         let profiles = match list_heap_profiles() {
             Ok(s) => s,
@@ -682,12 +1467,126 @@ where
                             )),
                             (Method::GET, "/status") => Ok(Response::default()),
                             (Method::GET, "/debug/pprof/heap_list") => Self::list_heap_prof(req),
+                            (Method::GET, "/debug/apply_thread_prof") => {
+                                Self::apply_thread_prof(req)
+                            }
+                            (Method::GET, "/debug/forgotten_peers") => Self::forgotten_peers(req),
+                            (Method::GET, "/debug/region_state_mismatches") => {
+                                Self::region_state_mismatches(req)
+                            }
+                            (Method::GET, "/debug/feature_gates") => Self::feature_gates(req),
+                            (Method::GET, "/debug/maintenance_mode")
+                            | (Method::PUT, "/debug/maintenance_mode") => {
+                                Self::maintenance_mode(req)
+                            }
+                            (Method::GET, "/debug/region_worker_interval")
+                            | (Method::PUT, "/debug/region_worker_interval") => {
+                                Self::region_worker_interval(req)
+                            }
+                            (Method::GET, "/startup_report") => Self::startup_report(),
+                            (Method::GET, "/debug/shadow_engine_store")
+                            | (Method::PUT, "/debug/shadow_engine_store") => {
+                                Self::shadow_engine_store(req)
+                            }
+                            (Method::GET, path) if path.starts_with("/debug/raft_log/") => {
+                                Self::raft_log_export(req)
+                            }
+                            (Method::GET, path)
+                                if path.starts_with("/debug/snapshot_apply_history/") =>
+                            {
+                                Self::snapshot_apply_history(req)
+                            }
+                            (Method::GET, path)
+                                if path.starts_with("/debug/region_size_amplification/") =>
+                            {
+                                Self::region_size_amplification(req)
+                            }
+                            (Method::GET, path)
+                                if path.starts_with("/debug/consistency_diff/") =>
+                            {
+                                Self::consistency_diff(req)
+                            }
+                            (Method::GET, "/debug/apply_watchdog") => {
+                                Self::apply_watchdog(req)
+                            }
+                            (Method::GET, "/debug/notification_inbox") => {
+                                Self::notification_inbox(req)
+                            }
+                            (Method::GET, "/debug/region_garbage") => {
+                                Self::region_garbage(req)
+                            }
+                            (Method::GET, "/debug/pending_peer_destroy") => {
+                                Self::pending_peer_destroy(req)
+                            }
+                            (Method::GET, "/debug/frozen_regions") => {
+                                Self::frozen_regions(req)
+                            }
+                            (Method::GET, "/debug/replay_debt") => {
+                                Self::replay_debt(req)
+                            }
+                            (Method::GET, path)
+                                if path.starts_with("/debug/export_region/") =>
+                            {
+                                Self::export_region(req)
+                            }
+                            (Method::PUT, "/debug/import_region") => {
+                                Self::import_region(req).await
+                            }
+                            #[cfg(feature = "failpoints")]
+                            (method, path)
+                                if (method == Method::GET
+                                    || method == Method::PUT
+                                    || method == Method::DELETE)
+                                    && (path == "/debug/failpoints"
+                                        || path.starts_with("/debug/failpoints/")) =>
+                            {
+                                Self::debug_failpoints(req).await
+                            }
+                            (Method::GET, "/debug/proxy_config_diff") => {
+                                Self::proxy_config_diff(req)
+                            }
+                            (Method::PUT, path) | (Method::DELETE, path)
+                                if path.starts_with("/debug/freeze_region/") =>
+                            {
+                                Self::freeze_region(req).await
+                            }
+                            (Method::PUT, path)
+                            | (Method::GET, path)
+                            | (Method::DELETE, path)
+                                if path.starts_with("/debug/rebuild_region/") =>
+                            {
+                                Self::rebuild_region(req)
+                            }
+                            (Method::PUT, path)
+                                if path.starts_with("/debug/rewind_region/") =>
+                            {
+                                Self::rewind_region(req)
+                            }
+                            (Method::GET, "/debug/restore_point")
+                            | (Method::POST, "/debug/restore_point") => {
+                                Self::restore_point(req).await
+                            }
+                            (Method::POST, path) if path.starts_with("/debug/restore_point/") => {
+                                Self::restore_point(req).await
+                            }
+                            (Method::POST, "/debug/check_keys") => Self::check_keys(req).await,
+                            (Method::PUT, path) | (Method::DELETE, path)
+                                if path.starts_with("/debug/verbose_trace/") =>
+                            {
+                                Self::verbose_trace(req)
+                            }
                             (Method::GET, "/debug/pprof/heap_activate") => {
                                 Self::activate_heap_prof(req, store_path).await
                             }
                             (Method::GET, "/debug/pprof/heap_deactivate") => {
                                 Self::deactivate_heap_prof(req)
                             }
+                            // Delegates to the engine store's own allocator, since jemalloc
+                            // state inside the FFI boundary is not visible to the proxy's
+                            // heap profiler above.
+                            (Method::GET, "/debug/pprof/heap_ffi") => {
+                                Self::handle_http_request(req, engine_store_server_helper).await
+                            }
                             // (Method::GET, "/debug/pprof/heap") => {
                             //     Self::dump_heap_prof_to_resp(req).await
                             // }
@@ -0,0 +1,37 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+use crate::proxy::*;
+
+mod ob {
+    use super::*;
+
+    /// A hibernating region only ticks its leader lease, but still forwards
+    /// raft cmd headers (via `on_empty_cmd`) to the engine store, since
+    /// TiFlash relies on those to keep `apply_index` moving forward even
+    /// when there is no data to apply. This makes sure normal writes still
+    /// land correctly with `hibernate-regions` on.
+    #[test]
+    fn test_write_with_hibernate_regions() {
+        let (mut cluster, pd_client) = new_mock_cluster(0, 3);
+
+        cluster.cfg.raft_store.hibernate_regions = true;
+        cluster.cfg.raft_store.raft_base_tick_interval = ReadableDuration::millis(10);
+        cluster.cfg.raft_store.raft_election_timeout_ticks = 10;
+
+        pd_client.disable_default_operator();
+        let _ = cluster.run();
+
+        let (key, value) = (b"k1", b"v1");
+        cluster.must_put(key, value);
+        check_key(&cluster, key, value, Some(true), None, None);
+
+        // Let all peers go to sleep.
+        std::thread::sleep(std::time::Duration::from_millis(500));
+
+        let (key2, value2) = (b"k2", b"v2");
+        cluster.must_put(key2, value2);
+        check_key(&cluster, key2, value2, Some(true), None, None);
+
+        cluster.shutdown();
+    }
+}
@@ -13,10 +13,12 @@ mod config;
 mod fast_add_peer;
 mod ffi;
 mod flashback;
+mod hibernate;
 mod normal;
 mod proxy;
 mod region;
 mod replica_read;
+mod scenario;
 mod server_cluster_test;
 mod snapshot;
 mod util;
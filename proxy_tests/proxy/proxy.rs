@@ -72,6 +72,11 @@ pub struct States {
     pub in_disk_region_state: RegionLocalState,
     pub in_disk_raft_state: RaftLocalState,
     pub ident: StoreIdent,
+    // Applied index of the region in the mock engine store. It is read from
+    // the same `kvstore` entry as `in_memory_apply_state`, so it always
+    // equals `in_memory_apply_state.get_applied_index()`; no separate report
+    // from the engine store is collected.
+    pub engine_store_applied_index: u64,
 }
 
 pub fn iter_ffi_helpers<C: Simulator<engine_store_ffi::TiFlashEngine>>(
@@ -114,6 +119,7 @@ pub fn maybe_collect_states(
                 prev_state.insert(
                     id,
                     States {
+                        engine_store_applied_index: region.apply_state.get_applied_index(),
                         in_memory_apply_state: region.apply_state.clone(),
                         in_memory_applied_term: region.applied_term,
                         in_disk_apply_state: apply_state.unwrap(),
@@ -563,6 +569,40 @@ pub fn must_unaltered_disk_truncated_state(
     compare_states(prev_states, new_states, f);
 }
 
+/// Asserts the ordering between the tracked apply-progress views: what's
+/// durable on disk never runs ahead of the engine store's applied index.
+/// The second assertion compares `engine_store_applied_index` with
+/// `in_memory_apply_state`, which come from the same mock `kvstore` entry,
+/// so it always holds. Usable at any point, not just after a full apply
+/// settles, which is what makes it useful for crash-between-phases scenarios.
+pub fn must_three_states_consistent(states: &HashMap<u64, States>) {
+    let f = |s: &States| {
+        assert!(s.in_disk_apply_state.get_applied_index() <= s.engine_store_applied_index);
+        assert!(s.engine_store_applied_index <= s.in_memory_apply_state.get_applied_index());
+    };
+    check_state(states, f);
+}
+
+/// Reusable scenario for a peer whose snapshot apply the caller has paused
+/// with `pause_fp` right after pre-handle (the caller also puts the data and
+/// adds the peer): asserts the three states are consistent while paused,
+/// then removes `pause_fp`, waits 500ms and asserts the same ordering again.
+/// It does not check that the states have become equal.
+pub fn run_snapshot_crash_between_phases_scenario(
+    cluster: &Cluster<NodeCluster>,
+    region_id: u64,
+    pause_fp: &str,
+) {
+    let paused_states = collect_all_states(cluster, region_id);
+    must_three_states_consistent(&paused_states);
+
+    fail::remove(pause_fp);
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    let resumed_states = collect_all_states(cluster, region_id);
+    must_three_states_consistent(&resumed_states);
+}
+
 // Must wait until all nodes satisfy cond given by `pref`.
 pub fn must_wait_until_cond_states(
     cluster: &Cluster<NodeCluster>,
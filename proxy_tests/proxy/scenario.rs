@@ -0,0 +1,130 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+use crate::proxy::*;
+
+/// One step of a [`Scenario`]: an action against the cluster, plus an
+/// optional condition to wait for afterwards, checked against the
+/// per-engine [`States`] snapshots taken immediately before and after the
+/// action ran (the same `prev`/`new` pair `must_wait_until_cond_states`
+/// takes, since replication to the other engines is asynchronous).
+struct ScenarioStep {
+    action: Box<dyn FnOnce(&mut Cluster<NodeCluster>, u64)>,
+    wait_until: Option<Box<dyn Fn(&States, &States) -> bool>>,
+}
+
+/// A declarative sequence of admin/write actions run against a single
+/// region, with an optional state condition (see [`Scenario::wait_until`])
+/// waited for after each one.
+///
+/// Tests like `write::leadership_change_impl` build up a region's history by
+/// hand, interleaving `cluster.must_*` calls with ad-hoc
+/// `collect_all_states`/assertion pairs; that reads fine for one scenario
+/// but doesn't compose, and every new test re-derives the same
+/// before/after-snapshot bookkeeping. `Scenario` factors that bookkeeping
+/// out so a sequence like "put k1; split at k5; compact-log; transfer
+/// leader" can be written as a script:
+///
+/// ```ignore
+/// Scenario::new()
+///     .put(b"k1", b"v1")
+///     .compact_log()
+///     .wait_until(|old, new| old.in_memory_apply_state != new.in_memory_apply_state)
+///     .transfer_leader_to(peer_2)
+///     .run(&mut cluster, region_id);
+/// ```
+///
+/// This only scripts the steps this crate's tests already reach for
+/// (`must_put`, split, compact-log, transfer-leader) -- it is not a general
+/// replacement for `call_command_on_leader` with arbitrary admin requests.
+#[must_use]
+pub struct Scenario {
+    steps: Vec<ScenarioStep>,
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        Scenario { steps: Vec::new() }
+    }
+
+    pub fn put(mut self, key: &'static [u8], value: &'static [u8]) -> Self {
+        self.steps.push(ScenarioStep {
+            action: Box::new(move |cluster, _region_id| cluster.must_put(key, value)),
+            wait_until: None,
+        });
+        self
+    }
+
+    pub fn split_at(mut self, split_key: &'static [u8]) -> Self {
+        self.steps.push(ScenarioStep {
+            action: Box::new(move |cluster, region_id| {
+                let region = cluster.get_region(split_key);
+                assert_eq!(region.get_id(), region_id);
+                cluster.must_split(&region, split_key);
+            }),
+            wait_until: None,
+        });
+        self
+    }
+
+    pub fn compact_log(self) -> Self {
+        self.compact_log_at(100, 10)
+    }
+
+    pub fn compact_log_at(mut self, index: u64, term: u64) -> Self {
+        self.steps.push(ScenarioStep {
+            action: Box::new(move |cluster, region_id| {
+                let epoch = cluster.get_region_epoch(region_id);
+                let compact_log = test_raftstore::new_compact_log_request(index, term);
+                let req = test_raftstore::new_admin_request(region_id, &epoch, compact_log);
+                cluster
+                    .call_command_on_leader(req, Duration::from_secs(3))
+                    .unwrap();
+            }),
+            wait_until: None,
+        });
+        self
+    }
+
+    pub fn transfer_leader_to(mut self, leader: metapb::Peer) -> Self {
+        self.steps.push(ScenarioStep {
+            action: Box::new(move |cluster, region_id| {
+                cluster.must_transfer_leader(region_id, leader);
+            }),
+            wait_until: None,
+        });
+        self
+    }
+
+    /// Waits for `pred(prev, new)` to hold for every engine's [`States`]
+    /// after the step just pushed, where `prev`/`new` are the states
+    /// collected right before and right after that step's action ran.
+    /// Without this, a step's effects are only guaranteed to be visible on
+    /// the leader, not yet replicated everywhere.
+    pub fn wait_until(mut self, pred: impl Fn(&States, &States) -> bool + 'static) -> Self {
+        self.steps
+            .last_mut()
+            .expect("wait_until called with no preceding step")
+            .wait_until = Some(Box::new(pred));
+        self
+    }
+
+    /// Runs every step against `region_id` in order, returning the final
+    /// per-engine states.
+    pub fn run(self, cluster: &mut Cluster<NodeCluster>, region_id: u64) -> HashMap<u64, States> {
+        let mut states = collect_all_states(cluster, region_id);
+        for step in self.steps {
+            let prev = states;
+            (step.action)(cluster, region_id);
+            states = match &step.wait_until {
+                Some(pred) => must_wait_until_cond_states(cluster, region_id, &prev, pred),
+                None => collect_all_states(cluster, region_id),
+            };
+        }
+        states
+    }
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self::new()
+    }
+}
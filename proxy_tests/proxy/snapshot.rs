@@ -90,6 +90,35 @@ fn test_basic_snapshot() {
     must_get_equal(&engine_2, b"k1", b"v");
 }
 
+/// Applies a snapshot with `on_ob_post_apply_snapshot` paused right after
+/// pre-handle finishes, so the region briefly sits between "pre-handled" and
+/// "persisted", and checks that the proxy's on-disk applied index stays at
+/// or below the engine store's in-memory one across that window (see
+/// `must_three_states_consistent`).
+#[test]
+fn test_snapshot_apply_state_consistency() {
+    let (mut cluster, pd_client) = new_mock_cluster_snap(0, 2);
+    fail::cfg("on_can_apply_snapshot", "return(true)").unwrap();
+    disable_auto_gen_compact_log(&mut cluster);
+    pd_client.disable_default_operator();
+    let r1 = cluster.run_conf_change();
+
+    for i in 0..50 {
+        let key = format!("{:03}", i);
+        cluster.must_put(key.as_bytes(), b"v");
+    }
+
+    fail::cfg("on_ob_post_apply_snapshot", "pause").unwrap();
+    pd_client.must_add_peer(r1, new_peer(2, 2));
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    run_snapshot_crash_between_phases_scenario(&cluster, r1, "on_ob_post_apply_snapshot");
+
+    must_get_equal(&cluster.get_engine(2), b"000", b"v");
+    fail::remove("on_can_apply_snapshot");
+    cluster.shutdown();
+}
+
 #[test]
 fn test_huge_multi_snapshot() {
     test_huge_snapshot(true)
@@ -1,5 +1,5 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
-use crate::proxy::*;
+use crate::{proxy::*, scenario::Scenario};
 
 #[test]
 fn test_interaction() {
@@ -103,6 +103,41 @@ fn test_leadership_change_normal() {
     leadership_change_impl(TransferLeaderRunMode::NoCompactLog);
 }
 
+#[test]
+fn test_leadership_change_does_not_trip_apply_term_guard() {
+    // A normal transfer-leader mid-write is exactly the case
+    // `core::applied_term_guard` exists to distinguish from a genuine
+    // divergence: the term keeps advancing, so the guard must not refuse to
+    // persist any of it.
+    let (mut cluster, _pd_client) = new_mock_cluster(0, 3);
+    disable_auto_gen_compact_log(&mut cluster);
+    let _ = cluster.run();
+
+    cluster.must_put(b"k1", b"v1");
+    let region = cluster.get_region(b"k1");
+    let eng_ids = cluster
+        .engines
+        .iter()
+        .map(|e| e.0.to_owned())
+        .collect::<Vec<_>>();
+    let peer_1 = find_peer(&region, eng_ids[0]).cloned().unwrap();
+    let peer_2 = find_peer(&region, eng_ids[1]).cloned().unwrap();
+
+    let before = engine_store_ffi::core::metrics::TIFLASH_APPLY_TERM_REGRESSION_COUNTER.get();
+
+    cluster.must_transfer_leader(region.get_id(), peer_1);
+    cluster.must_put(b"k2", b"v2");
+    check_key(&cluster, b"k2", b"v2", Some(true), None, None);
+    cluster.must_transfer_leader(region.get_id(), peer_2);
+    cluster.must_put(b"k3", b"v3");
+    check_key(&cluster, b"k3", b"v3", Some(true), None, None);
+
+    let after = engine_store_ffi::core::metrics::TIFLASH_APPLY_TERM_REGRESSION_COUNTER.get();
+    assert_eq!(before, after);
+
+    cluster.shutdown();
+}
+
 fn leadership_change_impl(mode: TransferLeaderRunMode) {
     // Test if a empty command can be observed when leadership changes.
     let (mut cluster, _pd_client) = new_mock_cluster(0, 3);
@@ -528,3 +563,40 @@ mod mix_mode {
         fail::remove("no_persist_compact_log");
     }
 }
+
+#[test]
+fn test_scenario_compact_log_and_transfer_leader() {
+    // Same shape as `leadership_change_impl`'s NoCompactLog case, but scripted
+    // with `Scenario` instead of hand-rolled before/after state bookkeeping.
+    let (mut cluster, _pd_client) = new_mock_cluster(0, 3);
+    cluster.cfg.raft_store.raft_log_gc_count_limit = Some(1000);
+    cluster.cfg.raft_store.raft_log_gc_tick_interval = ReadableDuration::millis(10000);
+    disable_auto_gen_compact_log(&mut cluster);
+    let _ = cluster.run();
+
+    cluster.must_put(b"k0", b"v0");
+    let region = cluster.get_region(b"k0");
+    let region_id = region.get_id();
+
+    let eng_ids = cluster
+        .engines
+        .iter()
+        .map(|e| e.0.to_owned())
+        .collect::<Vec<_>>();
+    let peer_2 = find_peer(&region, eng_ids[1]).cloned().unwrap();
+
+    Scenario::new()
+        .put(b"k1", b"v1")
+        .wait_until(|old: &States, new: &States| old.in_memory_apply_state != new.in_memory_apply_state)
+        .compact_log()
+        .wait_until(|old: &States, new: &States| {
+            old.in_memory_apply_state != new.in_memory_apply_state
+                || old.in_memory_applied_term != new.in_memory_applied_term
+        })
+        .transfer_leader_to(peer_2)
+        .run(&mut cluster, region_id);
+
+    check_key(&cluster, b"k1", b"v1", Some(true), None, None);
+
+    cluster.shutdown();
+}